@@ -6,12 +6,12 @@
 //!
 //! Original issue: https://github.com/apache/datafusion-ballista/issues/132
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::env;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub struct Task {
@@ -20,13 +20,92 @@ pub struct Task {
     depends_on_stage: Option<u32>,
 }
 
+/// Wait-for graph snapshot, exported as Graphviz DOT with `--format dot`
+///
+/// Each crate builds on its own, so this is copied into ballista-deadlock-132,
+/// reth-deadlock-10842, sui-blocking-5201, sui-deadlock-335, sui-deadlock-960
+/// and sui-race-4990. Keep the copies identical; reth-deadlock-10842 also
+/// has `remove_edge`.
+#[derive(Debug, Default)]
+pub struct WaitForGraph {
+    edges: Vec<(String, String, String)>,
+}
+
+impl WaitForGraph {
+    fn add_edge(&mut self, from: &str, to: &str, label: &str) {
+        self.edges.push((from.to_string(), to.to_string(), label.to_string()));
+    }
+
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph {} {{\n", name);
+        for (from, to, label) in &self.edges {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", from, to, label));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Small deterministic PRNG (SplitMix64) so generated graphs replay from a seed
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Random stage DAG: 2-3 stages, each later stage depending on one earlier
+/// stage, with `size` tasks spread across them (every stage gets at least
+/// one). Submission order is shuffled so dependents often arrive first.
+fn generate_tasks(seed: u64, size: usize) -> Vec<Task> {
+    let mut rng = SeededRng(seed);
+    let stages = (2 + rng.below(2) as u32).min(size.max(1) as u32);
+    let depends_on: Vec<Option<u32>> = (1..=stages)
+        .map(|stage| (stage > 1).then(|| 1 + rng.below(stage as usize - 1) as u32))
+        .collect();
+
+    let mut tasks: Vec<Task> = (0..size)
+        .map(|i| {
+            let stage = if i < stages as usize {
+                i as u32 + 1
+            } else {
+                1 + rng.below(stages as usize) as u32
+            };
+            Task {
+                id: format!("task_{}_{}", stage, i),
+                stage,
+                depends_on_stage: depends_on[stage as usize - 1],
+            }
+        })
+        .collect();
+
+    for i in (1..tasks.len()).rev() {
+        let j = rng.below(i + 1);
+        tasks.swap(i, j);
+    }
+    tasks
+}
+
 /// Executor with limited task slots
 pub struct Executor {
     name: String,
     max_slots: usize,
     running_tasks: Mutex<Vec<Task>>,
+    waiting_for_slot: Mutex<Vec<Task>>,
     available_slots: Mutex<usize>,
     slot_available: Condvar,
+    completed_tasks: Mutex<usize>,
+    /// Tasks in the order they finished, reported as `completion_order`
+    completion_order: Mutex<Vec<Task>>,
 }
 
 impl Executor {
@@ -35,14 +114,54 @@ impl Executor {
             name: name.to_string(),
             max_slots,
             running_tasks: Mutex::new(Vec::new()),
+            waiting_for_slot: Mutex::new(Vec::new()),
             available_slots: Mutex::new(max_slots),
             slot_available: Condvar::new(),
+            completed_tasks: Mutex::new(0),
+            completion_order: Mutex::new(Vec::new()),
         }
     }
 
     fn available_slots(&self) -> usize {
         *self.available_slots.lock().unwrap()
     }
+
+    fn completed_tasks(&self) -> usize {
+        *self.completed_tasks.lock().unwrap()
+    }
+
+    /// Count a finished task and append it to the completion order
+    fn record_completion(&self, task: &Task) {
+        *self.completed_tasks.lock().unwrap() += 1;
+        self.completion_order.lock().unwrap().push(task.clone());
+    }
+
+    fn completion_order(&self) -> Vec<Task> {
+        self.completion_order.lock().unwrap().clone()
+    }
+
+    /// Snapshot of tasks holding slots, tasks waiting for slots, and the
+    /// stages the running tasks are waiting on
+    fn wait_for_graph(&self) -> WaitForGraph {
+        let running = self.running_tasks.lock().unwrap().clone();
+        let waiting = self.waiting_for_slot.lock().unwrap().clone();
+        let slots = format!("{} slots", self.name);
+        let mut graph = WaitForGraph::default();
+
+        for task in &waiting {
+            graph.add_edge(&task.id, &slots, "waits for");
+        }
+        for task in &running {
+            graph.add_edge(&slots, &task.id, "held by");
+            if let Some(dep_stage) = task.depends_on_stage {
+                graph.add_edge(&task.id, &format!("stage {}", dep_stage), "waits for");
+            }
+        }
+        for task in running.iter().chain(&waiting) {
+            graph.add_edge(&format!("stage {}", task.stage), &task.id, "produced by");
+        }
+        graph
+    }
 }
 
 /// Buggy scheduler - schedules tasks without considering dependencies
@@ -72,7 +191,9 @@ mod buggy {
                     "[BUGGY] Task {} waiting for slot (no slots available)",
                     task.id
                 );
+                self.executor.waiting_for_slot.lock().unwrap().push(task.clone());
                 slots = self.executor.slot_available.wait(slots).unwrap();
+                self.executor.waiting_for_slot.lock().unwrap().retain(|t| t.id != task.id);
             }
             *slots -= 1;
             drop(slots);
@@ -132,6 +253,7 @@ mod buggy {
                     *slots += 1;
                 }
                 executor.slot_available.notify_one();
+                executor.record_completion(&task_clone);
 
                 println!("[BUGGY] Task {} completed", task_clone.id);
             });
@@ -146,6 +268,8 @@ mod fixed {
     pub struct Scheduler {
         executor: Arc<Executor>,
         stage_complete: Arc<Mutex<Vec<u32>>>,
+        /// Submitted tasks of each stage that have not finished yet
+        stage_remaining: Arc<Mutex<HashMap<u32, usize>>>,
         pending_queue: Mutex<VecDeque<Task>>,
     }
 
@@ -154,12 +278,15 @@ mod fixed {
             Self {
                 executor,
                 stage_complete: Arc::new(Mutex::new(Vec::new())),
+                stage_remaining: Arc::new(Mutex::new(HashMap::new())),
                 pending_queue: Mutex::new(VecDeque::new()),
             }
         }
 
         /// FIX: Only schedule tasks whose dependencies are met
         pub fn schedule_task(&self, task: Task) {
+            *self.stage_remaining.lock().unwrap().entry(task.stage).or_insert(0) += 1;
+
             // Check if dependencies are met BEFORE taking a slot
             if let Some(dep_stage) = task.depends_on_stage {
                 let completed = self.stage_complete.lock().unwrap();
@@ -187,6 +314,7 @@ mod fixed {
 
             let executor = Arc::clone(&self.executor);
             let stage_complete = Arc::clone(&self.stage_complete);
+            let stage_remaining = Arc::clone(&self.stage_remaining);
             let task_clone = task.clone();
 
             println!(
@@ -199,12 +327,18 @@ mod fixed {
                 println!("[FIXED] Task {} executing...", task_clone.id);
                 thread::sleep(Duration::from_millis(100));
 
-                // Mark stage complete
+                // Mark stage complete once its last submitted task finishes,
+                // so dependents never run next to an unfinished input
                 {
-                    let mut completed = stage_complete.lock().unwrap();
-                    if !completed.contains(&task_clone.stage) {
-                        completed.push(task_clone.stage);
-                        println!("[FIXED] Stage {} marked complete", task_clone.stage);
+                    let mut remaining = stage_remaining.lock().unwrap();
+                    let left = remaining.get_mut(&task_clone.stage).expect("stage counted on submit");
+                    *left -= 1;
+                    if *left == 0 {
+                        let mut completed = stage_complete.lock().unwrap();
+                        if !completed.contains(&task_clone.stage) {
+                            completed.push(task_clone.stage);
+                            println!("[FIXED] Stage {} marked complete", task_clone.stage);
+                        }
                     }
                 }
 
@@ -214,6 +348,7 @@ mod fixed {
                     *slots += 1;
                 }
                 executor.slot_available.notify_one();
+                executor.record_completion(&task_clone);
 
                 println!("[FIXED] Task {} completed", task_clone.id);
             });
@@ -246,31 +381,85 @@ mod fixed {
     }
 }
 
+/// Number of tasks in a generated graph unless `--size` is given
+const DEFAULT_GRAPH_SIZE: usize = 4;
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let use_csv = args.iter().any(|arg| arg == "--csv");
+    let format_dot = args
+        .iter()
+        .position(|arg| arg == "--format")
+        .and_then(|i| args.get(i + 1))
+        .is_some_and(|format| format == "dot");
+    let parse_flag = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.parse::<u64>().ok())
+    };
+    let seed = parse_flag("--seed");
+    let fuzz = parse_flag("--fuzz");
+    let size = parse_flag("--size").unwrap_or(DEFAULT_GRAPH_SIZE as u64) as usize;
 
     println!("=== Ballista Issue #132: Executor Task Slot Deadlock ===\n");
 
-    if use_fixed {
+    if let Some(spec) = args.iter().position(|arg| arg == "--sweep").and_then(|i| args.get(i + 1)) {
+        let sweep = match Sweep::parse(spec) {
+            Some(sweep) if sweep.param == "slots" && sweep.start > 0 => sweep,
+            _ => {
+                eprintln!("Invalid --sweep '{}': expected slots=start..end[:step] with start >= 1", spec);
+                std::process::exit(2);
+            }
+        };
+        println!("Sweeping {} version over {}..={} executor slots...\n",
+                 if use_fixed { "FIXED" } else { "BUGGY" }, sweep.start, sweep.end);
+        run_sweep_test(use_fixed, &sweep, use_csv);
+    } else if let Some(seed) = seed {
+        println!("Running {} version on generated graph (seed {}, {} tasks)...\n",
+                 if use_fixed { "FIXED" } else { "BUGGY" }, seed, size);
+        run_seeded_test(use_fixed, seed, size);
+    } else if let Some(count) = fuzz {
+        println!("Fuzzing {} version over seeds 0..{} ({} tasks each)...\n",
+                 if use_fixed { "FIXED" } else { "BUGGY" }, count, size);
+        run_fuzz_test(use_fixed, count, size);
+    } else if use_fixed {
         println!("Running FIXED version (dependency-aware scheduling)...\n");
         run_fixed_test();
     } else {
         println!("Running BUGGY version (may deadlock)...\n");
-        run_buggy_test();
+        run_buggy_test(format_dot);
     }
 }
 
-fn run_buggy_test() {
-    // Only 2 slots available
-    let executor = Arc::new(Executor::new("executor-1", 2));
-    let scheduler = buggy::Scheduler::new(Arc::clone(&executor));
-
-    println!("Executor has {} slots", executor.max_slots);
-    println!("Scheduling 2 stage-2 tasks, then 2 stage-1 tasks\n");
-
-    // BUG: Schedule stage 2 tasks first (they depend on stage 1)
-    let tasks = vec![
+/// Two stage-2 tasks submitted ahead of the two stage-1 tasks they need
+fn demo_tasks() -> Vec<Task> {
+    vec![
         Task {
             id: "task_2a".to_string(),
             stage: 2,
@@ -291,11 +480,32 @@ fn run_buggy_test() {
             stage: 1,
             depends_on_stage: None,
         },
-    ];
+    ]
+}
 
-    for task in tasks {
-        scheduler.schedule_task(task);
-        thread::sleep(Duration::from_millis(50));
+fn run_buggy_test(format_dot: bool) {
+    // Only 2 slots available
+    let executor = Arc::new(Executor::new("executor-1", 2));
+    let scheduler = buggy::Scheduler::new(Arc::clone(&executor));
+
+    println!("Executor has {} slots", executor.max_slots);
+    println!("Scheduling 2 stage-2 tasks, then 2 stage-1 tasks\n");
+
+    // BUG: Schedule stage 2 tasks first (they depend on stage 1)
+    let tasks = demo_tasks();
+
+    let submit = move || {
+        for task in tasks {
+            scheduler.schedule_task(task);
+            thread::sleep(Duration::from_millis(50));
+        }
+    };
+    if format_dot {
+        // Scheduling itself blocks once the slots are gone, so submit from a
+        // separate thread to reach the check and snapshot the graph
+        thread::spawn(submit);
+    } else {
+        submit();
     }
 
     // Wait and check for deadlock
@@ -305,13 +515,18 @@ fn run_buggy_test() {
     let slots = executor.available_slots();
     if slots < executor.max_slots {
         println!("\n=== Results ===");
-        println!("[DEADLOCK DETECTED]");
+        print_banner("[DEADLOCK DETECTED]");
         println!("Only {} of {} slots available after 3 seconds", slots, executor.max_slots);
         println!("\nDeadlock scenario:");
         println!("  - Stage 2 tasks took all {} slots", executor.max_slots);
         println!("  - Stage 2 tasks wait for stage 1 to complete");
         println!("  - Stage 1 tasks can't get slots to run");
         println!("  - DEADLOCK: Circular dependency on slots!");
+        print_completion_metrics(&executor);
+        if format_dot {
+            println!("\n=== Wait-For Graph (DOT) ===");
+            print!("{}", executor.wait_for_graph().to_dot("ballista_132"));
+        }
         println!("\nRun with --fixed to see dependency-aware scheduling.");
         std::process::exit(1);
     }
@@ -327,28 +542,7 @@ fn run_fixed_test() {
     let scheduler_clone = Arc::clone(&scheduler);
 
     // Schedule same tasks - but fixed scheduler will queue dependent tasks
-    let tasks = vec![
-        Task {
-            id: "task_2a".to_string(),
-            stage: 2,
-            depends_on_stage: Some(1),
-        },
-        Task {
-            id: "task_2b".to_string(),
-            stage: 2,
-            depends_on_stage: Some(1),
-        },
-        Task {
-            id: "task_1a".to_string(),
-            stage: 1,
-            depends_on_stage: None,
-        },
-        Task {
-            id: "task_1b".to_string(),
-            stage: 1,
-            depends_on_stage: None,
-        },
-    ];
+    let tasks = demo_tasks();
 
     for task in tasks {
         scheduler.schedule_task(task);
@@ -363,9 +557,242 @@ fn run_fixed_test() {
 
     thread::sleep(Duration::from_millis(500));
 
+    print_completion_metrics(&executor);
+
+    let order = executor.completion_order();
+    assert_eq!(order.len(), demo_tasks().len(), "fixed scheduler left tasks unfinished");
+    let last_stage_1 = order.iter().rposition(|task| task.stage == 1);
+    let first_stage_2 = order.iter().position(|task| task.stage == 2);
+    assert!(
+        last_stage_1 < first_stage_2,
+        "a stage-2 task finished before every stage-1 task had: {}",
+        order_ids(&order)
+    );
+    assert_eq!(executor.available_slots(), executor.max_slots, "a slot is still occupied");
+
     println!("\n=== Results ===");
-    println!("[FIXED]");
+    print_banner("[FIXED]");
     println!("All tasks completed without deadlock!");
-    println!("Stage 2 tasks queued until stage 1 completed.");
-    println!("No slot starvation - dependencies respected.");
+    println!("Every stage-1 task finished before any stage-2 task.");
+    println!("All {} slots free again - dependencies respected.", executor.max_slots);
+}
+
+fn order_ids(order: &[Task]) -> String {
+    order.iter().map(|task| task.id.as_str()).collect::<Vec<_>>().join(",")
+}
+
+fn print_completion_metrics(executor: &Executor) {
+    let order = executor.completion_order();
+    println!("\n=== Metrics ===");
+    println!("completed_tasks: {}", order.len());
+    println!("completion_order: {}", order_ids(&order));
+    println!("free_slots: {}/{}", executor.available_slots(), executor.max_slots);
+}
+
+/// Run one generated graph on an executor with `slots` slots. Returns true if
+/// every task completed before the deadline; a deadlocked run leaves its
+/// threads parked.
+fn run_generated(use_fixed: bool, tasks: Vec<Task>, slots: usize) -> bool {
+    let executor = Arc::new(Executor::new("executor-1", slots));
+    let total = tasks.len();
+    let deadline = Instant::now() + Duration::from_millis(500 + 300 * total as u64);
+
+    if use_fixed {
+        let scheduler = Arc::new(fixed::Scheduler::new(Arc::clone(&executor)));
+        let submitter = Arc::clone(&scheduler);
+        thread::spawn(move || {
+            for task in tasks {
+                submitter.schedule_task(task);
+            }
+        });
+        while executor.completed_tasks() < total && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+            scheduler.process_pending();
+        }
+    } else {
+        let scheduler = buggy::Scheduler::new(Arc::clone(&executor));
+        thread::spawn(move || {
+            for task in tasks {
+                scheduler.schedule_task(task);
+            }
+        });
+        while executor.completed_tasks() < total && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    executor.completed_tasks() == total
+}
+
+fn run_seeded_test(use_fixed: bool, seed: u64, size: usize) {
+    let tasks = generate_tasks(seed, size);
+    println!("Generated tasks (submission order):");
+    for task in &tasks {
+        match task.depends_on_stage {
+            Some(dep) => println!("  {} (stage {}, depends on stage {})", task.id, task.stage, dep),
+            None => println!("  {} (stage {})", task.id, task.stage),
+        }
+    }
+    println!();
+
+    let completed = run_generated(use_fixed, tasks, 2);
+
+    println!("\n=== Results ===");
+    if completed {
+        println!("[{}]", if use_fixed { "FIXED" } else { "NOTE" });
+        println!("All tasks in graph seed {} completed.", seed);
+    } else if use_fixed {
+        print_banner("[ERROR]");
+        println!("Fixed scheduler did not finish graph seed {}.", seed);
+        std::process::exit(1);
+    } else {
+        print_banner("[DEADLOCK DETECTED]");
+        println!("Dependent tasks held every slot on graph seed {}.", seed);
+        std::process::exit(1);
+    }
+}
+
+fn run_fuzz_test(use_fixed: bool, count: u64, size: usize) {
+    let mut failing_seeds = Vec::new();
+    for seed in 0..count {
+        let completed = run_generated(use_fixed, generate_tasks(seed, size), 2);
+        println!("[FUZZ] seed={} {}", seed, if completed { "completed" } else { "DEADLOCK" });
+        if !completed {
+            failing_seeds.push(seed);
+        }
+    }
+
+    println!("\n=== Results ===");
+    println!("{} of {} generated graphs deadlocked", failing_seeds.len(), count);
+
+    match (use_fixed, failing_seeds.first()) {
+        (true, None) => {
+            println!();
+            print_banner("[FIXED]");
+            println!("Dependency-aware scheduling completed every generated graph.");
+        }
+        (true, Some(seed)) => {
+            println!();
+            print_banner("[ERROR]");
+            println!("Fixed scheduler stalled; replay with --fixed --seed {}", seed);
+            std::process::exit(1);
+        }
+        (false, Some(seed)) => {
+            println!();
+            print_banner("[DEADLOCK DETECTED]");
+            println!("First deadlocking seed: {} (replay with --seed {})", seed, seed);
+            std::process::exit(1);
+        }
+        (false, None) => {
+            println!();
+            print_banner("[NOTE]");
+            println!("No generated graph deadlocked; try more seeds or a larger --size.");
+        }
+    }
+}
+
+/// Range for `--sweep param=start..end:step`, inclusive of `end`
+///
+/// Each crate builds on its own, so this is copied into ballista-deadlock-132,
+/// sui-blocking-5201 and surrealdb-race-5042. Keep the copies identical.
+struct Sweep {
+    param: String,
+    start: u64,
+    end: u64,
+    step: u64,
+}
+
+impl Sweep {
+    fn parse(spec: &str) -> Option<Self> {
+        let (param, range) = spec.split_once('=')?;
+        let (range, step) = match range.split_once(':') {
+            Some((range, step)) => (range, step.parse().ok()?),
+            None => (range, 1),
+        };
+        let (start, end) = range.split_once("..")?;
+        let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+        (step > 0 && start <= end).then(|| Self { param: param.to_string(), start, end, step })
+    }
+
+    fn values(&self) -> impl Iterator<Item = u64> {
+        (self.start..=self.end).step_by(self.step as usize)
+    }
+}
+
+/// Print `--csv` output: a header, then one line per row. Fields containing a
+/// comma, quote or newline are quoted.
+///
+/// Each crate builds on its own, so this and `csv_field` are copied into
+/// ballista-deadlock-132, sui-blocking-5201, reth-race-12287 and
+/// surrealdb-race-5042. Keep the copies identical.
+fn print_csv(header: &[&str], rows: &[Vec<String>]) {
+    println!("\n=== CSV ===");
+    println!("{}", header.join(","));
+    for row in rows {
+        assert_eq!(row.len(), header.len(), "CSV row width must match the header");
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        println!("{}", fields.join(","));
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Run the demo tasks once per slot count. The buggy scheduler deadlocks
+/// whenever the stage-2 tasks, submitted first, can take every slot.
+fn run_sweep_test(use_fixed: bool, sweep: &Sweep, use_csv: bool) {
+    let tasks = demo_tasks();
+    let dependents = tasks.iter().filter(|task| task.depends_on_stage.is_some()).count();
+    let mut rows = Vec::new();
+    for slots in sweep.values() {
+        let started = Instant::now();
+        let completed = run_generated(use_fixed, demo_tasks(), slots as usize);
+        println!("[SWEEP] slots={} {}", slots, if completed { "ok" } else { "deadlock" });
+        rows.push((slots, completed, started.elapsed()));
+    }
+
+    for &(slots, completed, _) in &rows {
+        let expect_deadlock = !use_fixed && slots as usize <= dependents;
+        assert_eq!(
+            !completed,
+            expect_deadlock,
+            "slots={} should {}",
+            slots,
+            if expect_deadlock { "deadlock" } else { "complete" }
+        );
+    }
+
+    if use_csv {
+        let mut csv = Vec::new();
+        for &(slots, completed, elapsed) in &rows {
+            csv.push(vec![
+                slots.to_string(),
+                (!completed).to_string(),
+                elapsed.as_millis().to_string(),
+                tasks.len().to_string(),
+            ]);
+        }
+        print_csv(&["slots", "bug_observed", "elapsed_ms", "tasks"], &csv);
+    } else {
+        println!("\n=== Sweep (CSV) ===");
+        println!("slots,tasks,outcome");
+        for &(slots, completed, _) in &rows {
+            println!("{},{},{}", slots, tasks.len(), if completed { "ok" } else { "deadlock" });
+        }
+    }
+
+    println!("\n=== Results ===");
+    if use_fixed {
+        print_banner("[FIXED]");
+        println!("Dependency-aware scheduling completed with every slot count.");
+    } else {
+        print_banner("[DEADLOCK DETECTED]");
+        println!("With {} or fewer slots the {} stage-2 tasks held them all.", dependents, dependents);
+        println!("Extra slots only hide the bug until more dependents arrive first.");
+    }
 }
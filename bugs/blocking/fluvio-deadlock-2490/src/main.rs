@@ -10,7 +10,7 @@
 //! during a long-running operation starves other requesters.
 
 use std::env;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Barrier, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -94,6 +94,23 @@ mod buggy {
             blocked_ms as u64
         }
 
+        /// Take the read lock and keep it for `hold`. Returns when the lock
+        /// was acquired and how long the reader waited for it.
+        pub fn read_and_hold(&self, reader: usize, hold: Duration) -> (Instant, Duration) {
+            let start = Instant::now();
+            let cache = self.cache.read().unwrap();
+            let acquired = Instant::now();
+            println!(
+                "[BUGGY] Reader {}: got lock after {}ms, version={}, holding {}ms",
+                reader,
+                (acquired - start).as_millis(),
+                cache.version(),
+                hold.as_millis()
+            );
+            thread::sleep(hold);
+            (acquired, acquired - start)
+        }
+
         pub fn cache(&self) -> &Arc<RwLock<DataCache>> {
             &self.cache
         }
@@ -151,15 +168,73 @@ mod fixed {
             );
             blocked_ms as u64
         }
+
+        /// Take the read lock and keep it for `hold`. Returns when the lock
+        /// was acquired and how long the reader waited for it.
+        pub fn read_and_hold(&self, reader: usize, hold: Duration) -> (Instant, Duration) {
+            let start = Instant::now();
+            let cache = self.cache.read().unwrap();
+            let acquired = Instant::now();
+            println!(
+                "[FIXED] Reader {}: got lock after {}ms, version={}, holding {}ms",
+                reader,
+                (acquired - start).as_millis(),
+                cache.version(),
+                hold.as_millis()
+            );
+            thread::sleep(hold);
+            (acquired, acquired - start)
+        }
+    }
+}
+
+const DEFAULT_READERS: usize = 4;
+/// Readers that start together must all acquire within this window
+const READER_WINDOW: Duration = Duration::from_millis(20);
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
     }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let parse_flag = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.parse::<u64>().ok())
+    };
+    let reader_hold = parse_flag("--reader-hold").map(Duration::from_millis);
+    let readers = parse_flag("--readers").map_or(DEFAULT_READERS, |n| n.max(1) as usize);
 
     println!("=== Fluvio PR #2490: Write Lock Across Async IO ===\n");
 
+    if let Some(hold) = reader_hold {
+        run_reader_hold_test(use_fixed, readers, hold);
+        return;
+    }
+
     if use_fixed {
         println!("Running FIXED version (lock released before IO)...\n");
         run_fixed_test();
@@ -196,7 +271,7 @@ fn run_buggy_test() {
 
     println!("\n=== Results ===");
     if blocked_time > 400 {
-        println!("[BUG DEMONSTRATED]");
+        print_banner("[BUG DEMONSTRATED]");
         println!("Reader blocked for {}ms waiting for write lock!", blocked_time);
         println!("\nProblem: Write lock held during 500ms IO operation");
         println!("  - Writer holds lock: acquire -> update -> IO -> release");
@@ -234,7 +309,7 @@ fn run_fixed_test() {
 
     println!("\n=== Results ===");
     if blocked_time < 100 {
-        println!("[FIXED]");
+        print_banner("[FIXED]");
         println!("Reader blocked for only {}ms!", blocked_time);
         println!("\nFix: Lock released before IO operation");
         println!("  - Writer: acquire -> update -> release -> IO");
@@ -245,3 +320,128 @@ fn run_fixed_test() {
         println!("This may be due to timing; the fix reduces typical blocking.");
     }
 }
+
+/// Blocked times from one `--reader-hold` run
+struct ReaderHoldOutcome {
+    /// How long each of the concurrent readers waited for the read lock
+    blocked: Vec<Duration>,
+    /// Time between the first and last of those readers acquiring it
+    acquire_spread: Duration,
+    /// How long a reader arriving after the writer waited
+    late_blocked: Duration,
+}
+
+/// Start `readers` readers together, each holding the read lock for `hold`.
+/// A writer arrives 10ms in, and one more reader arrives 50ms after the
+/// first readers let go.
+fn run_reader_hold<W: Send + Sync + 'static>(
+    writer: Arc<W>,
+    read: fn(&W, usize, Duration) -> (Instant, Duration),
+    write: fn(&W, Vec<u8>),
+    readers: usize,
+    hold: Duration,
+) -> ReaderHoldOutcome {
+    let start = Arc::new(Barrier::new(readers + 1));
+    let reader_handles: Vec<_> = (0..readers)
+        .map(|i| {
+            let writer = Arc::clone(&writer);
+            let start = Arc::clone(&start);
+            thread::spawn(move || {
+                start.wait();
+                read(&writer, i, hold)
+            })
+        })
+        .collect();
+
+    start.wait();
+    thread::sleep(Duration::from_millis(10));
+    let w = Arc::clone(&writer);
+    let write_handle = thread::spawn(move || write(&w, vec![10, 20, 30, 40, 50]));
+
+    thread::sleep(hold + Duration::from_millis(40));
+    let w = Arc::clone(&writer);
+    let late_handle = thread::spawn(move || read(&w, readers, Duration::ZERO));
+
+    let acquired: Vec<_> = reader_handles.into_iter().map(|h| h.join().unwrap()).collect();
+    write_handle.join().unwrap();
+    let (_, late_blocked) = late_handle.join().unwrap();
+
+    let first = acquired.iter().map(|(at, _)| *at).min().unwrap();
+    let last = acquired.iter().map(|(at, _)| *at).max().unwrap();
+    ReaderHoldOutcome {
+        blocked: acquired.iter().map(|(_, blocked)| *blocked).collect(),
+        acquire_spread: last - first,
+        late_blocked,
+    }
+}
+
+fn run_reader_hold_test(use_fixed: bool, readers: usize, hold: Duration) {
+    println!(
+        "Running {} version with {} readers holding the read lock for {}ms...\n",
+        if use_fixed { "FIXED" } else { "BUGGY" },
+        readers,
+        hold.as_millis()
+    );
+
+    let outcome = if use_fixed {
+        run_reader_hold(
+            Arc::new(fixed::StreamWriter::new()),
+            fixed::StreamWriter::read_and_hold,
+            fixed::StreamWriter::write_and_persist,
+            readers,
+            hold,
+        )
+    } else {
+        run_reader_hold(
+            Arc::new(buggy::StreamWriter::new()),
+            buggy::StreamWriter::read_and_hold,
+            buggy::StreamWriter::write_and_persist,
+            readers,
+            hold,
+        )
+    };
+
+    println!("\n=== Results ===");
+    for (i, blocked) in outcome.blocked.iter().enumerate() {
+        println!("Reader {} blocked: {}ms", i, blocked.as_millis());
+    }
+    println!("Late reader {} blocked: {}ms", readers, outcome.late_blocked.as_millis());
+
+    println!("\n=== Metrics ===");
+    println!("readers: {}", readers);
+    println!("reader_hold_ms: {}", hold.as_millis());
+    println!("acquire_spread_ms: {}", outcome.acquire_spread.as_millis());
+    println!("late_reader_blocked_ms: {}", outcome.late_blocked.as_millis());
+
+    assert!(
+        outcome.acquire_spread < READER_WINDOW,
+        "readers were serialized: acquisitions spread over {:?}",
+        outcome.acquire_spread
+    );
+
+    if use_fixed {
+        assert!(
+            outcome.late_blocked < Duration::from_millis(100),
+            "late reader blocked for {:?} behind the writer",
+            outcome.late_blocked
+        );
+        println!();
+        print_banner("[FIXED]");
+        println!("All {} readers held the read lock at the same time.", readers);
+        println!("The writer waited only for them, then released before IO,");
+        println!("so the late reader got in while the write was being persisted.");
+    } else if outcome.late_blocked > Duration::from_millis(400) {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("All {} readers held the read lock at the same time.", readers);
+        println!(
+            "The late reader was blocked for {}ms by the writer holding the lock during IO.",
+            outcome.late_blocked.as_millis()
+        );
+        println!("\nRun with --fixed --reader-hold {} to compare.", hold.as_millis());
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("The late reader was not significantly blocked (timing variation).");
+    }
+}
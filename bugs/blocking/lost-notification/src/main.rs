@@ -0,0 +1,405 @@
+//! Lost Notification (Lost Wakeup)
+//!
+//! A consumer checks a predicate, finds it false, and goes to wait on a
+//! `Condvar`. In the buggy version the check and the wait happen under two
+//! separate acquisitions of the mutex. If the producer sets the predicate
+//! and notifies between them, the notification reaches no waiter and is
+//! lost. The consumer then waits for a wakeup that already happened and
+//! blocks forever.
+//!
+//! Pattern reproduction: not tied to a single upstream issue. The same
+//! check-then-wait gap appears wherever a flag and a condition variable are
+//! used without one mutex covering both.
+
+use std::env;
+use std::sync::{Arc, Barrier, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Gap between the consumer's check and its wait in the default run
+const CHECK_TO_WAIT: Duration = Duration::from_millis(50);
+/// When the producer notifies in the default run, inside that gap
+const PRODUCER_DELAY: Duration = Duration::from_millis(10);
+/// How long a thread may go without finishing before it counts as blocked
+const DETECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Forces the interleaving for `--force-race`. The consumer stops right after
+/// its predicate check, and the producer sets the predicate and notifies
+/// before the consumer goes on.
+pub struct RaceHook {
+    checked: Barrier,
+    notified: Barrier,
+}
+
+impl RaceHook {
+    fn new() -> Self {
+        Self {
+            checked: Barrier::new(2),
+            notified: Barrier::new(2),
+        }
+    }
+
+    /// Called by the consumer once it has seen the predicate false
+    fn after_check(&self) {
+        self.checked.wait();
+    }
+
+    /// Called by the consumer to wait until the producer has notified
+    fn wait_notified(&self) {
+        self.notified.wait();
+    }
+
+    /// Called by the producer: runs `notify` after the consumer's check
+    fn run_producer(&self, notify: impl FnOnce()) {
+        self.checked.wait();
+        notify();
+        self.notified.wait();
+    }
+}
+
+/// Watches threads and reports those that have not finished by a deadline
+pub struct DeadlockDetector {
+    timeout: Duration,
+    threads: Vec<(String, JoinHandle<()>)>,
+}
+
+impl DeadlockDetector {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            threads: Vec::new(),
+        }
+    }
+
+    fn watch(&mut self, name: &str, handle: JoinHandle<()>) {
+        self.threads.push((name.to_string(), handle));
+    }
+
+    /// Wait up to the timeout for every watched thread. Finished threads
+    /// are joined. The ones still running are returned so the caller can
+    /// report them and, if it can, unblock them.
+    fn check(self) -> Vec<(String, JoinHandle<()>)> {
+        let start = Instant::now();
+        while start.elapsed() < self.timeout && !self.threads.iter().all(|(_, handle)| handle.is_finished()) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let (finished, blocked): (Vec<_>, Vec<_>) =
+            self.threads.into_iter().partition(|(_, handle)| handle.is_finished());
+        for (name, handle) in finished {
+            handle.join().unwrap_or_else(|_| panic!("thread {} panicked", name));
+        }
+        blocked
+    }
+}
+
+/// Buggy version - predicate checked and waited on under separate locks
+mod buggy {
+    use super::*;
+
+    pub struct Signal {
+        ready: Mutex<bool>,
+        cvar: Condvar,
+        hook: Option<Arc<RaceHook>>,
+    }
+
+    impl Signal {
+        pub fn new() -> Self {
+            Self {
+                ready: Mutex::new(false),
+                cvar: Condvar::new(),
+                hook: None,
+            }
+        }
+
+        pub fn with_hook(hook: Arc<RaceHook>) -> Self {
+            Self {
+                hook: Some(hook),
+                ..Self::new()
+            }
+        }
+
+        /// BUG: The mutex is released between the check and the wait
+        pub fn wait(&self) {
+            let ready = *self.ready.lock().unwrap();
+            if ready {
+                println!("[BUGGY] Consumer saw ready, no need to wait");
+                return;
+            }
+            println!("[BUGGY] Consumer saw not ready, preparing to wait");
+
+            // BUG: Window here! The producer can set ready and notify
+            // before this thread is waiting on the condvar
+            match &self.hook {
+                Some(hook) => {
+                    hook.after_check();
+                    hook.wait_notified();
+                }
+                None => thread::sleep(CHECK_TO_WAIT),
+            }
+
+            let guard = self.ready.lock().unwrap();
+            println!("[BUGGY] Consumer waiting on condvar (ready is {})", *guard);
+            // BUG: Waits without re-checking the predicate
+            let _guard = self.cvar.wait(guard).unwrap();
+            println!("[BUGGY] Consumer woke up");
+        }
+
+        pub fn notify(&self) {
+            *self.ready.lock().unwrap() = true;
+            self.cvar.notify_one();
+            println!("[BUGGY] Producer set ready and notified");
+        }
+
+        pub fn is_ready(&self) -> bool {
+            *self.ready.lock().unwrap()
+        }
+    }
+}
+
+/// Fixed version - predicate checked and waited on under one lock
+mod fixed {
+    use super::*;
+
+    pub struct Signal {
+        ready: Mutex<bool>,
+        cvar: Condvar,
+        hook: Option<Arc<RaceHook>>,
+    }
+
+    impl Signal {
+        pub fn new() -> Self {
+            Self {
+                ready: Mutex::new(false),
+                cvar: Condvar::new(),
+                hook: None,
+            }
+        }
+
+        pub fn with_hook(hook: Arc<RaceHook>) -> Self {
+            Self {
+                hook: Some(hook),
+                ..Self::new()
+            }
+        }
+
+        /// FIX: Hold the mutex from the check until `wait` releases it
+        pub fn wait(&self) {
+            let guard = self.ready.lock().unwrap();
+            if *guard {
+                println!("[FIXED] Consumer saw ready, no need to wait");
+                return;
+            }
+            println!("[FIXED] Consumer saw not ready, preparing to wait");
+
+            // The producer can run now, but it needs this mutex to set ready,
+            // so it cannot notify until this thread is waiting
+            match &self.hook {
+                Some(hook) => hook.after_check(),
+                None => thread::sleep(CHECK_TO_WAIT),
+            }
+
+            println!("[FIXED] Consumer waiting on condvar (ready is {})", *guard);
+            // FIX: wait_while re-checks the predicate after every wakeup
+            let _guard = self.cvar.wait_while(guard, |ready| !*ready).unwrap();
+            println!("[FIXED] Consumer woke up");
+            if let Some(hook) = &self.hook {
+                hook.wait_notified();
+            }
+        }
+
+        pub fn notify(&self) {
+            *self.ready.lock().unwrap() = true;
+            self.cvar.notify_one();
+            println!("[FIXED] Producer set ready and notified");
+        }
+
+        pub fn is_ready(&self) -> bool {
+            *self.ready.lock().unwrap()
+        }
+    }
+}
+
+/// What both signal versions need for one consumer/producer run
+trait Signal: Send + Sync + 'static {
+    fn wait(&self);
+    fn notify(&self);
+    fn is_ready(&self) -> bool;
+}
+
+impl Signal for buggy::Signal {
+    fn wait(&self) {
+        self.wait()
+    }
+
+    fn notify(&self) {
+        self.notify()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.is_ready()
+    }
+}
+
+impl Signal for fixed::Signal {
+    fn wait(&self) {
+        self.wait()
+    }
+
+    fn notify(&self) {
+        self.notify()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.is_ready()
+    }
+}
+
+/// Run one consumer and one producer on `signal`. The producer notifies
+/// after `PRODUCER_DELAY`, or inside the hook's window when there is one.
+/// Returns whether the consumer was still blocked at the detector's deadline.
+/// A blocked consumer is then notified again so the run can end cleanly.
+fn run_pair<S: Signal>(signal: S, hook: Option<Arc<RaceHook>>, tag: &str) -> bool {
+    let signal = Arc::new(signal);
+
+    let consumer_signal = Arc::clone(&signal);
+    let consumer = thread::spawn(move || consumer_signal.wait());
+
+    let producer_signal = Arc::clone(&signal);
+    let producer = thread::spawn(move || match hook {
+        Some(hook) => hook.run_producer(|| producer_signal.notify()),
+        None => {
+            thread::sleep(PRODUCER_DELAY);
+            producer_signal.notify();
+        }
+    });
+    producer.join().unwrap();
+
+    let mut detector = DeadlockDetector::new(DETECT_TIMEOUT);
+    detector.watch("consumer", consumer);
+    let blocked = detector.check();
+    let consumer_blocked = !blocked.is_empty();
+
+    for (name, handle) in blocked {
+        println!(
+            "{} DeadlockDetector: {} still blocked after {:?} (ready is {})",
+            tag,
+            name,
+            DETECT_TIMEOUT,
+            signal.is_ready()
+        );
+        // The predicate is already true, so a second notify is all it needs
+        signal.notify();
+        handle.join().unwrap();
+    }
+    consumer_blocked
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let use_fixed = args.iter().any(|arg| arg == "--fixed");
+
+    println!("=== Lost Notification (Condvar Lost Wakeup) ===\n");
+
+    if args.iter().any(|arg| arg == "--force-race") {
+        println!("Forcing the notify between check and wait for BUGGY and FIXED versions...\n");
+        run_force_race_test();
+    } else if use_fixed {
+        println!("Running FIXED version (check and wait under one lock)...\n");
+        run_fixed_test();
+    } else {
+        println!("Running BUGGY version (check and wait under separate locks)...\n");
+        run_buggy_test();
+    }
+}
+
+fn run_buggy_test() {
+    println!(
+        "Consumer checks, then waits {:?} later; producer notifies after {:?}\n",
+        CHECK_TO_WAIT, PRODUCER_DELAY
+    );
+    let blocked = run_pair(buggy::Signal::new(), None, "[BUGGY]");
+
+    println!("\n=== Results ===");
+    println!("Consumer blocked at the deadline: {}", blocked);
+
+    if blocked {
+        println!();
+        print_banner("[DEADLOCK DETECTED]");
+        println!("The producer notified while the consumer was between its check and its wait.");
+        println!("Nobody was waiting yet, so the wakeup was lost and the consumer");
+        println!("waited for a notification that had already happened.");
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("The notification arrived after the consumer was waiting this time (timing-dependent).");
+        println!("Run with --force-race to fix the interleaving.");
+    }
+    println!("\nRun with --fixed to see the check and wait under one lock.");
+}
+
+fn run_fixed_test() {
+    println!(
+        "Consumer checks, then waits {:?} later; producer notifies after {:?}\n",
+        CHECK_TO_WAIT, PRODUCER_DELAY
+    );
+    let blocked = run_pair(fixed::Signal::new(), None, "[FIXED]");
+
+    println!("\n=== Results ===");
+    println!("Consumer blocked at the deadline: {}", blocked);
+    assert!(!blocked, "fixed consumer missed the notification");
+
+    println!();
+    print_banner("[FIXED]");
+    println!("The producer could not set ready until the consumer was waiting,");
+    println!("because the consumer held the mutex from its check until wait released it.");
+}
+
+/// Both versions run the same schedule: the consumer checks, the producer
+/// sets ready and notifies, and only then does the consumer go on
+fn run_force_race_test() {
+    println!("--- BUGGY ---");
+    let hook = Arc::new(RaceHook::new());
+    let buggy_blocked = run_pair(buggy::Signal::with_hook(Arc::clone(&hook)), Some(hook), "[BUGGY]");
+
+    println!("\n--- FIXED ---");
+    let hook = Arc::new(RaceHook::new());
+    let fixed_blocked = run_pair(fixed::Signal::with_hook(Arc::clone(&hook)), Some(hook), "[FIXED]");
+
+    println!("\n=== Results ===");
+    println!("[BUGGY] Consumer blocked at the deadline: {}", buggy_blocked);
+    println!("[FIXED] Consumer blocked at the deadline: {}", fixed_blocked);
+
+    assert!(buggy_blocked, "buggy consumer should miss the forced notification");
+    assert!(!fixed_blocked, "fixed consumer should proceed on the forced schedule");
+
+    println!();
+    print_banner("[BUG DEMONSTRATED]");
+    println!("Buggy: the notify landed between check and wait and was lost.");
+    print_banner("[FIXED]");
+    println!("Fixed: the producer's notify waited for the consumer's mutex, so it came after the wait.");
+}
@@ -0,0 +1,302 @@
+//! Poisoned Lock Cascade
+//!
+//! This reproduces a failure where one worker panics while holding a shared
+//! `Mutex`, poisoning it. Every other worker locks with `.lock().unwrap()`,
+//! so the single panic turns into a cascade and the service stops serving.
+//!
+//! Pattern reproduction: not tied to a single upstream issue. None of the
+//! other bugs in this collection handle poisoning, so any real panic inside
+//! their critical sections would fail the same way.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+const WORKERS: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    key: String,
+    amount: String,
+}
+
+impl Request {
+    fn new(key: &str, amount: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            amount: amount.to_string(),
+        }
+    }
+}
+
+/// Outcome of running a batch of workers against one service
+#[derive(Debug, Default)]
+pub struct BatchOutcome {
+    served: usize,
+    panicked: usize,
+}
+
+/// Buggy version - every lock site unwraps the poison error
+mod buggy {
+    use super::*;
+
+    pub struct Service {
+        balances: Mutex<HashMap<String, u64>>,
+    }
+
+    impl Service {
+        pub fn new() -> Self {
+            Self {
+                balances: Mutex::new(HashMap::new()),
+            }
+        }
+
+        /// BUG: panics (while holding the lock) on a malformed amount, and
+        /// `.lock().unwrap()` panics for every caller after that
+        pub fn handle(&self, request: &Request) -> u64 {
+            let mut balances = self.balances.lock().unwrap();
+            // Parsing under the lock: a bad request panics with the guard held
+            let amount: u64 = request.amount.parse().unwrap();
+            let balance = balances.entry(request.key.clone()).or_insert(0);
+            *balance += amount;
+            *balance
+        }
+
+        pub fn is_poisoned(&self) -> bool {
+            self.balances.is_poisoned()
+        }
+    }
+}
+
+/// Fixed version - recover the guard from a poisoned lock
+mod fixed {
+    use super::*;
+
+    pub struct Service {
+        balances: Mutex<HashMap<String, u64>>,
+        /// `--inject-regression`: unwrap the lock result again
+        inject_regression: bool,
+    }
+
+    impl Service {
+        pub fn new() -> Self {
+            Self {
+                balances: Mutex::new(HashMap::new()),
+                inject_regression: false,
+            }
+        }
+
+        /// Fixed service with the original bug put back, to check that the
+        /// invariant checks notice it
+        pub fn with_injected_regression() -> Self {
+            Self {
+                inject_regression: true,
+                ..Self::new()
+            }
+        }
+
+        /// FIX: a poisoned lock still hands back its guard. Safe here because
+        /// the panic happens before any mutation, so the map is consistent.
+        fn lock_balances(&self) -> MutexGuard<'_, HashMap<String, u64>> {
+            if self.inject_regression {
+                // REGRESSION: the buggy version's unwrap
+                return self.balances.lock().unwrap();
+            }
+            self.balances.lock().unwrap_or_else(|poisoned| {
+                println!("[FIXED] Lock was poisoned by a panicked worker, recovering guard");
+                self.balances.clear_poison();
+                poisoned.into_inner()
+            })
+        }
+
+        pub fn handle(&self, request: &Request) -> u64 {
+            let mut balances = self.lock_balances();
+            // Same panic site as the buggy version - the worker still dies
+            let amount: u64 = request.amount.parse().unwrap();
+            let balance = balances.entry(request.key.clone()).or_insert(0);
+            *balance += amount;
+            *balance
+        }
+
+        pub fn is_poisoned(&self) -> bool {
+            self.balances.is_poisoned()
+        }
+    }
+}
+
+/// Run one worker per request and count how many were served vs panicked
+fn run_batch<F>(requests: Vec<Request>, handle: F, prefix: &str) -> BatchOutcome
+where
+    F: Fn(&Request) -> u64 + Send + Sync + 'static,
+{
+    let handle = Arc::new(handle);
+    let handles: Vec<_> = requests
+        .into_iter()
+        .enumerate()
+        .map(|(i, request)| {
+            let handle = Arc::clone(&handle);
+            thread::spawn(move || (i, request.key.clone(), handle(&request)))
+        })
+        .collect();
+
+    let mut outcome = BatchOutcome::default();
+    for join in handles {
+        match join.join() {
+            Ok((i, key, balance)) => {
+                println!("{} Worker {} served {:?}, balance now {}", prefix, i, key, balance);
+                outcome.served += 1;
+            }
+            Err(_) => {
+                println!("{} Worker panicked", prefix);
+                outcome.panicked += 1;
+            }
+        }
+    }
+    outcome
+}
+
+fn good_requests() -> Vec<Request> {
+    (0..WORKERS)
+        .map(|i| Request::new(if i % 2 == 0 { "alice" } else { "bob" }, "10"))
+        .collect()
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let inject_regression = args.iter().any(|arg| arg == "--inject-regression");
+
+    println!("=== Poisoned Lock Cascade ===\n");
+
+    if args.iter().any(|arg| arg == "--verify") {
+        println!("Checking that the fixed version's invariants catch an injected regression...\n");
+        run_verify();
+    } else if use_fixed && inject_regression {
+        println!("Running FIXED version with the regression injected (.lock().unwrap() again)...\n");
+        run_fixed_test(fixed::Service::with_injected_regression());
+    } else if use_fixed {
+        println!("Running FIXED version (recover guard from poisoned lock)...\n");
+        run_fixed_test(fixed::Service::new());
+    } else {
+        println!("Running BUGGY version (.lock().unwrap() everywhere)...\n");
+        run_buggy_test();
+    }
+}
+
+fn run_buggy_test() {
+    let service = Arc::new(buggy::Service::new());
+
+    println!("--- Malformed request panics while holding the lock ---");
+    let bad = {
+        let service = Arc::clone(&service);
+        run_batch(vec![Request::new("alice", "ten")], move |r| service.handle(r), "[BUGGY]")
+    };
+    println!("[BUGGY] Lock poisoned: {}", service.is_poisoned());
+
+    println!("\n--- {} well-formed requests ---", WORKERS);
+    let good = {
+        let service = Arc::clone(&service);
+        run_batch(good_requests(), move |r| service.handle(r), "[BUGGY]")
+    };
+
+    println!("\n=== Results ===");
+    println!("Initial panics: {}", bad.panicked);
+    println!("Well-formed requests served: {}/{}", good.served, WORKERS);
+    println!("Cascading panics: {}", good.panicked);
+
+    if good.panicked > 0 {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("One malformed request poisoned the lock.");
+        println!("Every later .lock().unwrap() panicked, so the service stopped serving.");
+        println!("\nRun with --fixed to see the poisoned guard recovered.");
+    }
+}
+
+/// Run the fixed scenario and return the first invariant it broke, if any:
+/// every well-formed request after the panic is served, and the lock ends
+/// up unpoisoned
+fn run_fixed_scenario(service: fixed::Service) -> Option<String> {
+    let service = Arc::new(service);
+
+    println!("--- Malformed request panics while holding the lock ---");
+    let bad = {
+        let service = Arc::clone(&service);
+        run_batch(vec![Request::new("alice", "ten")], move |r| service.handle(r), "[FIXED]")
+    };
+    println!("[FIXED] Lock poisoned: {}", service.is_poisoned());
+
+    println!("\n--- {} well-formed requests ---", WORKERS);
+    let good = {
+        let service = Arc::clone(&service);
+        run_batch(good_requests(), move |r| service.handle(r), "[FIXED]")
+    };
+
+    println!("\n=== Results ===");
+    println!("Initial panics: {}", bad.panicked);
+    println!("Well-formed requests served: {}/{}", good.served, WORKERS);
+    println!("Cascading panics: {}", good.panicked);
+
+    if good.served != WORKERS {
+        return Some(format!("only {}/{} well-formed requests served after a panic", good.served, WORKERS));
+    }
+    if service.is_poisoned() {
+        return Some("lock still poisoned".to_string());
+    }
+    None
+}
+
+fn run_fixed_test(service: fixed::Service) {
+    let violation = run_fixed_scenario(service);
+    assert!(violation.is_none(), "fixed service broke its invariant: {}", violation.unwrap());
+
+    println!();
+    print_banner("[FIXED]");
+    println!("The malformed request's worker still panicked, but only that worker.");
+    println!("Later requests recovered the guard and the service kept serving.");
+}
+
+/// Self-test of the serving invariants: the fixed service must pass them,
+/// and the same service unwrapping the lock result again must not
+fn run_verify() {
+    println!("=== FIXED ===\n");
+    let clean = run_fixed_scenario(fixed::Service::new());
+    println!("\n=== FIXED with --inject-regression ===\n");
+    let injected = run_fixed_scenario(fixed::Service::with_injected_regression());
+
+    println!("\n=== Verify ===");
+    println!("Without injection: {}", clean.as_deref().unwrap_or("invariants held"));
+    println!("With injection: {}", injected.as_deref().unwrap_or("invariants held"));
+
+    assert!(clean.is_none(), "fixed service broke its invariant without injection");
+    assert!(injected.is_some(), "injected regression went unnoticed by the invariants");
+
+    println!();
+    print_banner("[FIXED]");
+    println!("The invariants pass on the fixed service and catch the reintroduced unwrap.");
+}
@@ -0,0 +1,457 @@
+//! Priority Inversion
+//!
+//! A low-priority task takes a lock, a high-priority task blocks on it, and a
+//! medium-priority task that never touches the lock preempts the low one. The
+//! high-priority task now waits for as long as the medium task keeps the CPU,
+//! not just for the rest of the low task's critical section.
+//!
+//! Pattern reproduction: not tied to a single upstream issue. OS thread
+//! priorities need privileges and a multi-core run would hide the preemption,
+//! so the tasks are real threads that only make progress when a simulated
+//! single-CPU priority scheduler gives them the turn. Time is counted in
+//! scheduler ticks, which makes every run identical.
+
+use std::cmp::Reverse;
+use std::env;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+
+/// Ticks the low-priority task spends inside its critical section
+const LOW_CRITICAL_TICKS: u64 = 3;
+/// CPU-bound ticks of the medium-priority task unless `--medium-work` is given
+const DEFAULT_MEDIUM_WORK: u64 = 20;
+
+/// One step of a task's script. Each tick of a step is one scheduler turn.
+#[derive(Debug, Clone, Copy)]
+pub enum Step {
+    Lock,
+    Work(u64),
+    Unlock,
+}
+
+pub struct TaskSpec {
+    name: &'static str,
+    priority: u8,
+    arrival: u64,
+    script: Vec<Step>,
+}
+
+#[derive(Debug)]
+pub struct Task {
+    name: &'static str,
+    base_priority: u8,
+    effective_priority: u8,
+    arrival: u64,
+    blocked_since: Option<u64>,
+    /// Tick the task blocked on the lock and tick it was handed the lock
+    wait_span: Option<(u64, u64)>,
+    finished_at: Option<u64>,
+}
+
+impl Task {
+    fn runnable(&self, now: u64) -> bool {
+        self.arrival <= now && self.finished_at.is_none() && self.blocked_since.is_none()
+    }
+}
+
+/// How the lock changes its owner's priority when a task blocks on it
+pub trait LockProtocol: Send + Sync {
+    /// Priority the owner runs at once a task of `waiter_priority` waits for it
+    fn owner_priority(&self, owner: &Task, waiter_priority: u8) -> u8;
+}
+
+/// Buggy version - the lock owner keeps its own priority
+mod buggy {
+    use super::*;
+
+    pub struct NoInheritance;
+
+    impl LockProtocol for NoInheritance {
+        /// BUG: a low-priority owner stays low, so any medium-priority task
+        /// preempts it while a high-priority task is waiting for the lock
+        fn owner_priority(&self, owner: &Task, _waiter_priority: u8) -> u8 {
+            owner.effective_priority
+        }
+    }
+}
+
+/// Fixed version - priority inheritance
+mod fixed {
+    use super::*;
+
+    pub struct PriorityInheritance;
+
+    impl LockProtocol for PriorityInheritance {
+        /// FIX: the owner runs at the highest priority waiting on its lock
+        /// until it releases it, so only tasks above the waiter can preempt it
+        fn owner_priority(&self, owner: &Task, waiter_priority: u8) -> u8 {
+            owner.effective_priority.max(waiter_priority)
+        }
+    }
+}
+
+struct State {
+    now: u64,
+    current: Option<usize>,
+    tasks: Vec<Task>,
+    lock_owner: Option<usize>,
+    lock_waiters: Vec<usize>,
+    /// Initial of the task that ran each tick, '.' when the CPU was idle
+    timeline: String,
+}
+
+/// Single-CPU scheduler: at every tick the runnable task with the highest
+/// effective priority gets the turn, and every other task thread waits
+pub struct Scheduler<P: LockProtocol> {
+    protocol: P,
+    prefix: &'static str,
+    state: Mutex<State>,
+    turn: Condvar,
+}
+
+impl<P: LockProtocol> Scheduler<P> {
+    pub fn new(protocol: P, specs: &[TaskSpec], prefix: &'static str) -> Self {
+        let tasks = specs
+            .iter()
+            .map(|spec| Task {
+                name: spec.name,
+                base_priority: spec.priority,
+                effective_priority: spec.priority,
+                arrival: spec.arrival,
+                blocked_since: None,
+                wait_span: None,
+                finished_at: None,
+            })
+            .collect();
+        let scheduler = Self {
+            protocol,
+            prefix,
+            state: Mutex::new(State {
+                now: 0,
+                current: None,
+                tasks,
+                lock_owner: None,
+                lock_waiters: Vec::new(),
+                timeline: String::new(),
+            }),
+            turn: Condvar::new(),
+        };
+        {
+            let mut state = scheduler.state.lock().unwrap();
+            scheduler.schedule(&mut state);
+        }
+        scheduler
+    }
+
+    /// Give the turn to the highest-priority runnable task, idling the CPU
+    /// until the next arrival if nothing can run
+    fn schedule(&self, state: &mut State) {
+        loop {
+            let now = state.now;
+            let next = state
+                .tasks
+                .iter()
+                .enumerate()
+                .filter(|(_, task)| task.runnable(now))
+                .max_by_key(|(i, task)| (task.effective_priority, Reverse(*i)))
+                .map(|(i, _)| i);
+
+            match next {
+                Some(next) => {
+                    if let Some(prev) = state.current.filter(|&prev| prev != next) {
+                        if state.tasks[prev].runnable(now) {
+                            println!("{} t={} {} preempts {}", self.prefix, now,
+                                state.tasks[next].name, state.tasks[prev].name);
+                        } else if let Some(owner) = state.lock_owner.filter(|&owner| owner != next) {
+                            // The inversion itself: someone waits for the lock,
+                            // and its owner is runnable but not chosen
+                            if state.tasks[owner].runnable(now) && !state.lock_waiters.is_empty() {
+                                println!("{} t={} {} runs ahead of lock owner {}", self.prefix, now,
+                                    state.tasks[next].name, state.tasks[owner].name);
+                            }
+                        }
+                    }
+                    state.current = Some(next);
+                    return;
+                }
+                None if state.tasks.iter().any(|task| task.arrival > now) => {
+                    state.timeline.push('.');
+                    state.now += 1;
+                }
+                None => {
+                    state.current = None;
+                    return;
+                }
+            }
+        }
+    }
+
+    fn wait_turn(&self, id: usize) -> MutexGuard<'_, State> {
+        let state = self.state.lock().unwrap();
+        self.turn.wait_while(state, |state| state.current != Some(id)).unwrap()
+    }
+
+    fn end_turn(&self, mut state: MutexGuard<'_, State>, id: usize) {
+        let initial = state.tasks[id].name.chars().next().unwrap().to_ascii_uppercase();
+        state.timeline.push(initial);
+        state.now += 1;
+        self.schedule(&mut state);
+        self.turn.notify_all();
+    }
+
+    pub fn work(&self, id: usize, ticks: u64) {
+        for _ in 0..ticks {
+            let state = self.wait_turn(id);
+            self.end_turn(state, id);
+        }
+    }
+
+    pub fn lock(&self, id: usize) {
+        let mut state = self.wait_turn(id);
+        let now = state.now;
+        let Some(owner) = state.lock_owner else {
+            state.lock_owner = Some(id);
+            println!("{} t={} {} acquired the lock", self.prefix, now, state.tasks[id].name);
+            self.end_turn(state, id);
+            return;
+        };
+
+        state.tasks[id].blocked_since = Some(now);
+        state.lock_waiters.push(id);
+        println!("{} t={} {} blocked on the lock held by {}", self.prefix, now,
+            state.tasks[id].name, state.tasks[owner].name);
+
+        let waiter_priority = state.tasks[id].effective_priority;
+        let owner_priority = self.protocol.owner_priority(&state.tasks[owner], waiter_priority);
+        if owner_priority != state.tasks[owner].effective_priority {
+            state.tasks[owner].effective_priority = owner_priority;
+            println!("{} t={} {} inherits priority {} from {}", self.prefix, now,
+                state.tasks[owner].name, owner_priority, state.tasks[id].name);
+        }
+        self.end_turn(state, id);
+
+        // The owner's unlock hands the lock over and clears blocked_since
+        let state = self.state.lock().unwrap();
+        let _state = self.turn.wait_while(state, |state| state.tasks[id].blocked_since.is_some()).unwrap();
+    }
+
+    pub fn unlock(&self, id: usize) {
+        let mut state = self.wait_turn(id);
+        let now = state.now;
+        state.lock_owner = None;
+
+        let base = state.tasks[id].base_priority;
+        if state.tasks[id].effective_priority != base {
+            state.tasks[id].effective_priority = base;
+            println!("{} t={} {} drops back to priority {}", self.prefix, now, state.tasks[id].name, base);
+        }
+
+        let next = state
+            .lock_waiters
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, &waiter)| state.tasks[waiter].effective_priority)
+            .map(|(pos, _)| pos);
+        match next {
+            Some(pos) => {
+                let waiter = state.lock_waiters.remove(pos);
+                let since = state.tasks[waiter].blocked_since.take().unwrap();
+                state.tasks[waiter].wait_span = Some((since, now));
+                state.lock_owner = Some(waiter);
+                println!("{} t={} {} released the lock to {}, which waited {} ticks", self.prefix, now,
+                    state.tasks[id].name, state.tasks[waiter].name, now - since);
+            }
+            None => println!("{} t={} {} released the lock", self.prefix, now, state.tasks[id].name),
+        }
+        self.end_turn(state, id);
+    }
+
+    /// Finishing takes no tick; the next task gets the turn straight away
+    pub fn exit(&self, id: usize) {
+        let mut state = self.wait_turn(id);
+        let now = state.now;
+        state.tasks[id].finished_at = Some(now);
+        println!("{} t={} {} finished", self.prefix, now, state.tasks[id].name);
+        self.schedule(&mut state);
+        self.turn.notify_all();
+    }
+}
+
+/// What happened to the high-priority task in one run
+#[derive(Debug)]
+pub struct Report {
+    timeline: String,
+    high_wait: u64,
+    medium_ticks_during_wait: usize,
+    high_finished_at: u64,
+}
+
+fn run_tasks<P: LockProtocol + 'static>(protocol: P, specs: Vec<TaskSpec>, prefix: &'static str) -> Report {
+    let scheduler = Arc::new(Scheduler::new(protocol, &specs, prefix));
+
+    let handles: Vec<_> = specs
+        .into_iter()
+        .enumerate()
+        .map(|(id, spec)| {
+            let scheduler = Arc::clone(&scheduler);
+            thread::spawn(move || {
+                for step in spec.script {
+                    match step {
+                        Step::Lock => scheduler.lock(id),
+                        Step::Work(ticks) => scheduler.work(id, ticks),
+                        Step::Unlock => scheduler.unlock(id),
+                    }
+                }
+                scheduler.exit(id);
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let state = scheduler.state.lock().unwrap();
+    let high = state.tasks.iter().find(|task| task.name == "high").unwrap();
+    let (since, acquired) = high.wait_span.expect("high never blocked on the lock");
+    let medium_ticks_during_wait = state.timeline[since as usize..acquired as usize]
+        .chars()
+        .filter(|&c| c == 'M')
+        .count();
+
+    Report {
+        timeline: state.timeline.clone(),
+        high_wait: acquired - since,
+        medium_ticks_during_wait,
+        high_finished_at: high.finished_at.unwrap(),
+    }
+}
+
+/// Low takes the lock first, high arrives and blocks on it, then medium
+/// arrives with CPU-bound work and no interest in the lock
+fn task_specs(medium_work: u64) -> Vec<TaskSpec> {
+    vec![
+        TaskSpec {
+            name: "low",
+            priority: 1,
+            arrival: 0,
+            script: vec![Step::Lock, Step::Work(LOW_CRITICAL_TICKS), Step::Unlock, Step::Work(1)],
+        },
+        TaskSpec {
+            name: "high",
+            priority: 3,
+            arrival: 1,
+            script: vec![Step::Lock, Step::Work(1), Step::Unlock],
+        },
+        TaskSpec {
+            name: "medium",
+            priority: 2,
+            arrival: 2,
+            script: vec![Step::Work(medium_work)],
+        },
+    ]
+}
+
+fn print_report(report: &Report, medium_work: u64) {
+    println!("\n=== Results ===");
+    println!("Timeline (L=low, M=medium, H=high): {}", report.timeline);
+    println!("High-priority wait for the lock: {} ticks", report.high_wait);
+    println!("Medium ran {} ticks while high was blocked", report.medium_ticks_during_wait);
+
+    println!("\n=== Metrics ===");
+    println!("medium_work_ticks: {}", medium_work);
+    println!("low_critical_ticks: {}", LOW_CRITICAL_TICKS);
+    println!("high_wait_ticks: {}", report.high_wait);
+    println!("medium_ticks_during_wait: {}", report.medium_ticks_during_wait);
+    println!("high_finished_at: {}", report.high_finished_at);
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let use_fixed = args.iter().any(|arg| arg == "--fixed");
+
+    let medium_work = match args.iter().position(|arg| arg == "--medium-work") {
+        Some(i) => match args.get(i + 1).and_then(|value| value.parse::<u64>().ok()) {
+            Some(ticks) => ticks,
+            None => {
+                eprintln!("--medium-work expects a number of ticks");
+                std::process::exit(2);
+            }
+        },
+        None => DEFAULT_MEDIUM_WORK,
+    };
+
+    println!("=== Priority Inversion ===\n");
+
+    if use_fixed {
+        println!("Running FIXED version (priority inheritance)...\n");
+        run_fixed_test(medium_work);
+    } else {
+        println!("Running BUGGY version (lock owner keeps its low priority)...\n");
+        run_buggy_test(medium_work);
+    }
+}
+
+fn run_buggy_test(medium_work: u64) {
+    println!("Tasks: low (1) holds the lock for {} ticks, high (3) needs it,", LOW_CRITICAL_TICKS);
+    println!("medium (2) runs {} ticks without touching it\n", medium_work);
+
+    let report = run_tasks(buggy::NoInheritance, task_specs(medium_work), "[BUGGY]");
+    print_report(&report, medium_work);
+
+    // Low cannot finish its critical section until medium is done
+    assert_eq!(report.medium_ticks_during_wait as u64, medium_work);
+    assert!(report.high_wait > medium_work);
+
+    if report.medium_ticks_during_wait > 0 {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("High waited {} ticks for a lock held for {} ticks.", report.high_wait, LOW_CRITICAL_TICKS);
+        println!("Medium preempted the lock owner, so high waited for medium as well.");
+        println!("\nRun with --fixed to see priority inheritance.");
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("Medium had no work, so nothing preempted the lock owner.");
+    }
+}
+
+fn run_fixed_test(medium_work: u64) {
+    println!("Tasks: low (1) holds the lock for {} ticks, high (3) needs it,", LOW_CRITICAL_TICKS);
+    println!("medium (2) runs {} ticks without touching it\n", medium_work);
+
+    let report = run_tasks(fixed::PriorityInheritance, task_specs(medium_work), "[FIXED]");
+    print_report(&report, medium_work);
+
+    // Bounded by the rest of low's critical section plus its unlock
+    assert!(report.high_wait <= LOW_CRITICAL_TICKS + 1, "high waited {} ticks", report.high_wait);
+    assert_eq!(report.medium_ticks_during_wait, 0);
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Low inherited high's priority while high waited, so medium could not preempt it.");
+    println!("High's wait is bounded by low's critical section, however long medium runs.");
+}
@@ -0,0 +1,98 @@
+//! Runs the binary the way its README does and checks the banner each run
+//! prints. A run that outlives `TIMEOUT` is killed and fails its test.
+
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BIN: &str = env!("CARGO_BIN_EXE_priority-inversion");
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+struct Run {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+/// Run `BIN` with `args` and collect its exit code and output.
+///
+/// Each crate builds on its own, so `Run`, `run`, `read_to_end` and
+/// `assert_banner` are copied into every crate's `tests/banner.rs`. Keep the
+/// copies identical.
+fn run(args: &[&str]) -> Run {
+    let mut child = Command::new(BIN)
+        .args(args)
+        .env_remove("NO_COLOR")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start the binary");
+    let stdout = read_to_end(child.stdout.take().unwrap());
+    let stderr = read_to_end(child.stderr.take().unwrap());
+
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            child.kill().unwrap();
+            child.wait().unwrap();
+            panic!("`{} {}` was still running after {:?}", BIN, args.join(" "), TIMEOUT);
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+
+    Run {
+        code: status.code(),
+        stdout: stdout.join().unwrap(),
+        stderr: stderr.join().unwrap(),
+    }
+}
+
+/// Drain a pipe on its own thread so a chatty run never blocks on a full pipe
+fn read_to_end(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        pipe.read_to_end(&mut bytes).unwrap();
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+/// Assert that the run exits with `code` and prints at least one of `banners`
+fn assert_banner(args: &[&str], code: i32, banners: &[&str]) {
+    let run = run(args);
+    assert!(
+        run.code == Some(code) && banners.iter().any(|banner| run.stdout.contains(banner)),
+        "`{} {}` exited with {:?}, expected {} and one of {:?}\n--- stdout ---\n{}\n--- stderr ---\n{}",
+        BIN,
+        args.join(" "),
+        run.code,
+        code,
+        banners,
+        run.stdout,
+        run.stderr
+    );
+}
+
+/// The race is timing-dependent, and a run that misses it prints `[NOTE]`
+#[test]
+fn buggy_run_demonstrates_the_bug() {
+    assert_banner(&[], 0, &["[BUG DEMONSTRATED]", "[NOTE]"]);
+}
+
+#[test]
+fn fixed_run_prints_fixed() {
+    assert_banner(&["--fixed"], 0, &["[FIXED]"]);
+}
+
+#[test]
+fn no_color_prints_banners_without_escape_codes() {
+    let colored = run(&["--fixed"]);
+    assert!(colored.stdout.contains("\x1b[32m[FIXED"), "{}", colored.stdout);
+
+    let plain = run(&["--fixed", "--no-color"]);
+    assert!(plain.stdout.contains("[FIXED]"), "{}", plain.stdout);
+    assert!(!plain.stdout.contains('\x1b'), "{}", plain.stdout);
+}
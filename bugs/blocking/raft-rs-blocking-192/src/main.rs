@@ -10,34 +10,140 @@
 
 use std::collections::HashSet;
 use std::env;
+use std::fmt;
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub type NodeId = u64;
 pub type Term = u64;
 pub type LogIndex = u64;
 
-/// Represents a Raft configuration (set of voter nodes)
+/// Reasons a configuration can never reach quorum
 #[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// `len / 2 + 1` is 1 for an empty voter set, which no vote can satisfy
+    NoVoters,
+    /// One side of a joint configuration has no voters, so joint quorum is
+    /// unreachable
+    EmptyJointSide(&'static str),
+    /// A node cannot be a voter and a learner in the same configuration
+    LearnerIsVoter(NodeId),
+    /// `promote` was asked to promote a node that is not a learner
+    NotALearner(NodeId),
+}
+
+/// Decides whether `votes` out of `total` voters form a quorum
+pub trait QuorumPolicy: Send + Sync {
+    fn is_quorum(&self, total: usize, votes: usize) -> bool;
+
+    /// Short name used when a configuration is printed or compared
+    fn name(&self) -> &'static str;
+}
+
+/// Strict majority, `total / 2 + 1`; the default for every configuration
+pub struct MajorityQuorum;
+
+impl QuorumPolicy for MajorityQuorum {
+    fn is_quorum(&self, total: usize, votes: usize) -> bool {
+        votes > total / 2
+    }
+
+    fn name(&self) -> &'static str {
+        "majority"
+    }
+}
+
+/// Every voter must agree. Tolerates no failures, so it only makes sense for
+/// small clusters that want every replica to hold every committed entry.
+pub struct UnanimousQuorum;
+
+impl QuorumPolicy for UnanimousQuorum {
+    fn is_quorum(&self, total: usize, votes: usize) -> bool {
+        total > 0 && votes >= total
+    }
+
+    fn name(&self) -> &'static str {
+        "unanimous"
+    }
+}
+
+/// Represents a Raft configuration: the voters, plus learners that replicate
+/// the log without counting toward quorum
+#[derive(Clone)]
 pub struct Configuration {
     voters: HashSet<NodeId>,
+    learners: HashSet<NodeId>,
+    quorum: Arc<dyn QuorumPolicy>,
 }
 
+impl fmt::Debug for Configuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Configuration")
+            .field("voters", &self.voters)
+            .field("learners", &self.learners)
+            .field("quorum", &self.quorum.name())
+            .finish()
+    }
+}
+
+impl PartialEq for Configuration {
+    fn eq(&self, other: &Self) -> bool {
+        self.voters == other.voters
+            && self.learners == other.learners
+            && self.quorum.name() == other.quorum.name()
+    }
+}
+
+impl Eq for Configuration {}
+
 impl Configuration {
-    fn new(voters: &[NodeId]) -> Self {
-        Self {
+    fn new(voters: &[NodeId]) -> Result<Self, ConfigError> {
+        Self::with_learners(voters, &[])
+    }
+
+    fn with_learners(voters: &[NodeId], learners: &[NodeId]) -> Result<Self, ConfigError> {
+        if voters.is_empty() {
+            return Err(ConfigError::NoVoters);
+        }
+        if let Some(&node) = learners.iter().find(|node| voters.contains(node)) {
+            return Err(ConfigError::LearnerIsVoter(node));
+        }
+        Ok(Self {
             voters: voters.iter().cloned().collect(),
+            learners: learners.iter().cloned().collect(),
+            quorum: Arc::new(MajorityQuorum),
+        })
+    }
+
+    /// Replace the default majority rule
+    fn with_quorum_policy(mut self, quorum: Arc<dyn QuorumPolicy>) -> Self {
+        self.quorum = quorum;
+        self
+    }
+
+    /// Move a learner that has caught up into the voter set
+    fn promote(&mut self, node: NodeId) -> Result<(), ConfigError> {
+        if !self.learners.remove(&node) {
+            return Err(ConfigError::NotALearner(node));
         }
+        self.voters.insert(node);
+        Ok(())
     }
 
+    /// Fewest voters the policy accepts. Under the default majority rule a
+    /// single-voter configuration has a quorum of 1, so the lone voter
+    /// commits on its own vote. Learners are not counted.
     fn quorum_size(&self) -> usize {
-        self.voters.len() / 2 + 1
+        let total = self.voters.len();
+        (0..=total)
+            .find(|&votes| self.quorum.is_quorum(total, votes))
+            .unwrap_or(total + 1)
     }
 
     fn has_quorum(&self, votes: &HashSet<NodeId>) -> bool {
         let count = self.voters.intersection(votes).count();
-        count >= self.quorum_size()
+        self.quorum.is_quorum(self.voters.len(), count)
     }
 }
 
@@ -49,11 +155,21 @@ pub struct JointConfiguration {
 }
 
 impl JointConfiguration {
-    fn new(c_old: Configuration, c_new: Configuration) -> Self {
-        Self { c_old, c_new }
+    /// `Configuration::new` already rejects empty voter sets; this also
+    /// covers configurations built without it
+    fn new(c_old: Configuration, c_new: Configuration) -> Result<Self, ConfigError> {
+        if c_old.voters.is_empty() {
+            return Err(ConfigError::EmptyJointSide("C_old"));
+        }
+        if c_new.voters.is_empty() {
+            return Err(ConfigError::EmptyJointSide("C_new"));
+        }
+        Ok(Self { c_old, c_new })
     }
 
-    /// Joint consensus requires both configurations to have quorum
+    /// Joint consensus requires both configurations to have quorum, each
+    /// under its own policy. Only voters count on either side; learners
+    /// never do.
     fn has_joint_quorum(&self, votes: &HashSet<NodeId>) -> bool {
         self.c_old.has_quorum(votes) && self.c_new.has_quorum(votes)
     }
@@ -66,6 +182,26 @@ pub enum LogEntry {
     Normal(Vec<u8>),
 }
 
+/// Index of the latest config change entry in `log`. Log indexes start at 1.
+fn config_entry_index(log: &[LogEntry]) -> Option<LogIndex> {
+    log.iter()
+        .rposition(|entry| matches!(entry, LogEntry::ConfigChange(_)))
+        .map(|position| position as LogIndex + 1)
+}
+
+/// How a config change ended, as reported by `wait_for_commit`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigChangeOutcome {
+    /// Joint quorum was reached; carries the config change entry's log index
+    Committed(LogIndex),
+    /// The fixed node gave up on C_new and restored this configuration
+    RolledBack(Configuration),
+    /// Still undecided when the caller's timeout ran out
+    TimedOut,
+    /// The buggy node has C_old quorum but not C_new and will never recover
+    Blocked,
+}
+
 /// Replication status from a node
 #[derive(Debug)]
 pub struct ReplicationStatus {
@@ -74,6 +210,73 @@ pub struct ReplicationStatus {
     match_index: LogIndex,
 }
 
+/// Nodes the leader cannot currently reach. Their replication responses are
+/// dropped, as if the network lost them.
+#[derive(Debug, Default)]
+pub struct Partition {
+    unreachable: Mutex<HashSet<NodeId>>,
+}
+
+impl Partition {
+    pub fn set_reachable(&self, node: NodeId, reachable: bool) {
+        let mut unreachable = self.unreachable.lock().unwrap();
+        if reachable {
+            unreachable.remove(&node);
+        } else {
+            unreachable.insert(node);
+        }
+    }
+
+    pub fn is_reachable(&self, node: NodeId) -> bool {
+        !self.unreachable.lock().unwrap().contains(&node)
+    }
+}
+
+/// Source of time for the config change timer
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// Wall clock used by the normal demo runs
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Manually advanced clock so the rollback timer can be driven without sleeping
+pub struct MockClock {
+    base: Instant,
+    offset: Mutex<Duration>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            offset: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.offset.lock().unwrap() += by;
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.base + *self.offset.lock().unwrap()
+    }
+}
+
 /// Buggy version - blocks indefinitely when joint quorum cannot be achieved
 mod buggy {
     use super::*;
@@ -81,8 +284,10 @@ mod buggy {
     pub struct RaftNode {
         id: NodeId,
         current_config: Mutex<Option<JointConfiguration>>,
+        log: Mutex<Vec<LogEntry>>,
         committed_index: Mutex<LogIndex>,
         replication_responses: Mutex<HashSet<NodeId>>,
+        partition: Partition,
         blocked: Mutex<bool>,
         block_cvar: Condvar,
     }
@@ -92,30 +297,47 @@ mod buggy {
             Self {
                 id,
                 current_config: Mutex::new(None),
+                log: Mutex::new(Vec::new()),
                 committed_index: Mutex::new(0),
                 replication_responses: Mutex::new(HashSet::new()),
+                partition: Partition::default(),
                 blocked: Mutex::new(false),
                 block_cvar: Condvar::new(),
             }
         }
 
         /// BUG: Start a configuration change that may block forever
-        pub fn begin_config_change(&self, c_old: Configuration, c_new: Configuration) {
+        pub fn begin_config_change(&self, c_old: Configuration, c_new: Configuration) -> Result<(), ConfigError> {
             println!("[BUGGY] Node {} starting config change", self.id);
             println!("[BUGGY] C_old: {:?}", c_old.voters);
             println!("[BUGGY] C_new: {:?}", c_new.voters);
 
-            let joint = JointConfiguration::new(c_old, c_new);
+            let joint = JointConfiguration::new(c_old, c_new)?;
             *self.current_config.lock().unwrap() = Some(joint.clone());
+            let index = self.append_entry(LogEntry::ConfigChange(joint));
+            println!("[BUGGY] Appended config change at log index {}", index);
 
             // Add self to replication responses
             self.replication_responses.lock().unwrap().insert(self.id);
 
             println!("[BUGGY] Entered joint consensus state, waiting for quorum...");
+            Ok(())
+        }
+
+        /// Record whether `node` can be reached across the partition
+        pub fn set_reachable(&self, node: NodeId, reachable: bool) {
+            println!("[BUGGY] Node {} marked {}", node, if reachable { "reachable" } else { "unreachable" });
+            self.partition.set_reachable(node, reachable);
         }
 
         /// Receive replication response from a follower
+        /// Responses from unreachable nodes are dropped; the buggy node
+        /// still has no way to act on a partition
         pub fn receive_replication_response(&self, status: ReplicationStatus) {
+            if !self.partition.is_reachable(status.node) {
+                println!("[BUGGY] Dropped response from unreachable node {}", status.node);
+                return;
+            }
             println!("[BUGGY] Received response from node {}: success={}",
                      status.node, status.success);
 
@@ -139,8 +361,9 @@ mod buggy {
                          has_old_quorum, has_new_quorum);
 
                 if joint.has_joint_quorum(&responses) {
-                    println!("[BUGGY] Joint quorum achieved! Committing config change.");
-                    *self.committed_index.lock().unwrap() = 1;
+                    let index = config_entry_index(&self.log.lock().unwrap()).unwrap_or(0);
+                    println!("[BUGGY] Joint quorum achieved! Committing config change at index {}.", index);
+                    *self.committed_index.lock().unwrap() = index;
                 } else {
                     // BUG: No timeout or rollback mechanism!
                     // If we have C_old quorum but not C_new (or vice versa),
@@ -155,20 +378,20 @@ mod buggy {
         }
 
         /// Wait for commit with timeout (to demonstrate blocking)
-        pub fn wait_for_commit(&self, timeout: Duration) -> bool {
+        pub fn wait_for_commit(&self, timeout: Duration) -> ConfigChangeOutcome {
             let start = std::time::Instant::now();
             loop {
                 let committed = *self.committed_index.lock().unwrap();
                 if committed > 0 {
-                    return true;
+                    return ConfigChangeOutcome::Committed(committed);
                 }
 
                 if *self.blocked.lock().unwrap() {
-                    return false;
+                    return ConfigChangeOutcome::Blocked;
                 }
 
                 if start.elapsed() >= timeout {
-                    return false;
+                    return ConfigChangeOutcome::TimedOut;
                 }
 
                 thread::sleep(Duration::from_millis(50));
@@ -178,6 +401,21 @@ mod buggy {
         pub fn is_blocked(&self) -> bool {
             *self.blocked.lock().unwrap()
         }
+
+        pub fn committed_index(&self) -> LogIndex {
+            *self.committed_index.lock().unwrap()
+        }
+
+        /// Append `entry` to the log and return its index
+        pub fn append_entry(&self, entry: LogEntry) -> LogIndex {
+            let mut log = self.log.lock().unwrap();
+            log.push(entry);
+            log.len() as LogIndex
+        }
+
+        pub fn log(&self) -> Vec<LogEntry> {
+            self.log.lock().unwrap().clone()
+        }
     }
 }
 
@@ -185,61 +423,141 @@ mod buggy {
 mod fixed {
     use super::*;
 
+    /// Default for how long a joint configuration may lack C_new quorum
+    /// before rollback
+    pub const CONFIG_CHANGE_TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// What to do when the joint configuration has C_old quorum but not C_new
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum RollbackPolicy {
+        /// Roll back once the config change timeout has passed since the change began
+        ImmediateTimeout,
+        /// Never roll back; keep waiting for C_new (the original behaviour)
+        WaitIndefinitely,
+        /// Like ImmediateTimeout, but the first response from each C_new voter
+        /// restarts the timer. Repeated responses from a voter that already
+        /// answered are not new progress and leave the timer alone.
+        Adaptive,
+    }
+
+    impl RollbackPolicy {
+        pub fn parse(name: &str) -> Option<Self> {
+            match name {
+                "immediate" => Some(Self::ImmediateTimeout),
+                "wait" => Some(Self::WaitIndefinitely),
+                "adaptive" => Some(Self::Adaptive),
+                _ => None,
+            }
+        }
+    }
+
     pub struct RaftNode {
         id: NodeId,
+        clock: Arc<dyn Clock>,
         current_config: Mutex<Option<JointConfiguration>>,
         original_config: Mutex<Option<Configuration>>,
+        log: Mutex<Vec<LogEntry>>,
         committed_index: Mutex<LogIndex>,
         replication_responses: Mutex<HashSet<NodeId>>,
-        config_change_start: Mutex<Option<std::time::Instant>>,
-        rolled_back: Mutex<bool>,
+        partition: Partition,
+        config_change_start: Mutex<Option<Instant>>,
+        last_new_progress: Mutex<Option<Instant>>,
+        rolled_back_to: Mutex<Option<Configuration>>,
+        policy: RollbackPolicy,
+        config_change_timeout: Duration,
     }
 
     impl RaftNode {
         pub fn new(id: NodeId) -> Self {
+            Self::with_clock(id, Arc::new(SystemClock))
+        }
+
+        /// Shorter timeouts restore availability sooner; longer ones give
+        /// slow C_new replicas more time to catch up before rollback
+        pub fn new_with_timeout(id: NodeId, config_change_timeout: Duration) -> Self {
+            Self {
+                config_change_timeout,
+                ..Self::new(id)
+            }
+        }
+
+        pub fn with_clock(id: NodeId, clock: Arc<dyn Clock>) -> Self {
+            Self::with_policy(id, clock, RollbackPolicy::ImmediateTimeout)
+        }
+
+        pub fn with_policy(id: NodeId, clock: Arc<dyn Clock>, policy: RollbackPolicy) -> Self {
             Self {
                 id,
+                clock,
+                policy,
+                config_change_timeout: CONFIG_CHANGE_TIMEOUT,
                 current_config: Mutex::new(None),
                 original_config: Mutex::new(None),
+                log: Mutex::new(Vec::new()),
                 committed_index: Mutex::new(0),
                 replication_responses: Mutex::new(HashSet::new()),
+                partition: Partition::default(),
                 config_change_start: Mutex::new(None),
-                rolled_back: Mutex::new(false),
+                last_new_progress: Mutex::new(None),
+                rolled_back_to: Mutex::new(None),
             }
         }
 
-        pub fn begin_config_change(&self, c_old: Configuration, c_new: Configuration) {
+        pub fn begin_config_change(&self, c_old: Configuration, c_new: Configuration) -> Result<(), ConfigError> {
             println!("[FIXED] Node {} starting config change", self.id);
             println!("[FIXED] C_old: {:?}", c_old.voters);
             println!("[FIXED] C_new: {:?}", c_new.voters);
+            if !c_new.learners.is_empty() {
+                println!("[FIXED] C_new learners: {:?}", c_new.learners);
+            }
+
+            let joint = JointConfiguration::new(c_old.clone(), c_new)?;
 
             // FIX: Save original config for potential rollback
-            *self.original_config.lock().unwrap() = Some(c_old.clone());
+            *self.original_config.lock().unwrap() = Some(c_old);
 
-            let joint = JointConfiguration::new(c_old, c_new);
             *self.current_config.lock().unwrap() = Some(joint.clone());
+            let index = self.append_entry(LogEntry::ConfigChange(joint));
+            println!("[FIXED] Appended config change at log index {}", index);
 
             // FIX: Record start time for timeout
-            *self.config_change_start.lock().unwrap() = Some(std::time::Instant::now());
+            *self.config_change_start.lock().unwrap() = Some(self.clock.now());
 
             self.replication_responses.lock().unwrap().insert(self.id);
 
             println!("[FIXED] Entered joint consensus state, waiting for quorum...");
+            Ok(())
+        }
+
+        /// Record whether `node` can be reached across the partition
+        pub fn set_reachable(&self, node: NodeId, reachable: bool) {
+            println!("[FIXED] Node {} marked {}", node, if reachable { "reachable" } else { "unreachable" });
+            self.partition.set_reachable(node, reachable);
         }
 
         pub fn receive_replication_response(&self, status: ReplicationStatus) {
+            if !self.partition.is_reachable(status.node) {
+                println!("[FIXED] Dropped response from unreachable node {}", status.node);
+                return;
+            }
             println!("[FIXED] Received response from node {}: success={}",
                      status.node, status.success);
 
             if status.success {
-                self.replication_responses.lock().unwrap().insert(status.node);
+                let is_new_voter = self.current_config.lock().unwrap()
+                    .as_ref()
+                    .is_some_and(|joint| joint.c_new.voters.contains(&status.node));
+                let first_response = self.replication_responses.lock().unwrap().insert(status.node);
+                if is_new_voter && first_response {
+                    *self.last_new_progress.lock().unwrap() = Some(self.clock.now());
+                }
             }
 
             self.check_commit_progress();
         }
 
         /// FIX: Check for timeout and rollback if needed
-        fn check_commit_progress(&self) {
+        pub fn check_commit_progress(&self) {
             let config = self.current_config.lock().unwrap().clone();
             let responses = self.replication_responses.lock().unwrap().clone();
 
@@ -251,69 +569,213 @@ mod fixed {
                          has_old_quorum, has_new_quorum);
 
                 if joint.has_joint_quorum(&responses) {
-                    println!("[FIXED] Joint quorum achieved! Committing config change.");
-                    *self.committed_index.lock().unwrap() = 1;
+                    let index = config_entry_index(&self.log.lock().unwrap()).unwrap_or(0);
+                    println!("[FIXED] Joint quorum achieved! Committing config change at index {}.", index);
+                    *self.committed_index.lock().unwrap() = index;
                 } else if has_old_quorum && !has_new_quorum {
                     // FIX: Check if we should rollback
-                    self.maybe_rollback();
+                    self.maybe_rollback(joint, &responses);
                 }
             }
         }
 
-        /// FIX: Rollback to original config after timeout
-        fn maybe_rollback(&self) {
-            let start_time = self.config_change_start.lock().unwrap().clone();
-            let config_change_timeout = Duration::from_millis(500);
+        /// Whether enough C_new voters have answered or are still reachable
+        /// for C_new to reach quorum at all
+        fn new_quorum_reachable(&self, joint: &JointConfiguration, responses: &HashSet<NodeId>) -> bool {
+            let c_new = &joint.c_new;
+            let possible = c_new.voters.iter()
+                .filter(|node| responses.contains(node) || self.partition.is_reachable(**node))
+                .count();
+            c_new.quorum.is_quorum(c_new.voters.len(), possible)
+        }
+
+        /// FIX: Rollback to original config after timeout, or at once if a
+        /// partition leaves C_new unable to reach quorum
+        fn maybe_rollback(&self, joint: &JointConfiguration, responses: &HashSet<NodeId>) {
+            if self.policy != RollbackPolicy::WaitIndefinitely && !self.new_quorum_reachable(joint, responses) {
+                println!("[FIXED] C_new quorum unreachable! Rolling back without waiting for the timeout.");
+                self.roll_back();
+                return;
+            }
+
+            let start_time = match self.policy {
+                RollbackPolicy::WaitIndefinitely => None,
+                RollbackPolicy::ImmediateTimeout => *self.config_change_start.lock().unwrap(),
+                // Partial C_new progress counts as a fresh start for the timer
+                RollbackPolicy::Adaptive => {
+                    let start = *self.config_change_start.lock().unwrap();
+                    let progress = *self.last_new_progress.lock().unwrap();
+                    start.max(progress)
+                }
+            };
 
             if let Some(start) = start_time {
-                if start.elapsed() >= config_change_timeout {
-                    println!("[FIXED] Config change timeout! Rolling back to original config.");
-
-                    // Rollback to C_old
-                    if let Some(original) = self.original_config.lock().unwrap().take() {
-                        println!("[FIXED] Rolled back to config: {:?}", original.voters);
-                        *self.current_config.lock().unwrap() = None;
-                        *self.rolled_back.lock().unwrap() = true;
-                        *self.committed_index.lock().unwrap() = 1; // Mark as resolved
-                    }
+                if self.clock.now().duration_since(start) >= self.config_change_timeout {
+                    println!(
+                        "[FIXED] Config change timeout ({:?})! Rolling back to original config.",
+                        self.config_change_timeout
+                    );
+                    self.roll_back();
                 }
             }
         }
 
-        pub fn wait_for_commit(&self, timeout: Duration) -> bool {
+        /// Rollback to C_old. The config entry was never committed, so
+        /// `committed_index` is left alone; `rolled_back_to` records the outcome.
+        fn roll_back(&self) {
+            if let Some(original) = self.original_config.lock().unwrap().take() {
+                println!("[FIXED] Rolled back to config: {:?}", original.voters);
+                *self.current_config.lock().unwrap() = None;
+                *self.rolled_back_to.lock().unwrap() = Some(original);
+            }
+        }
+
+        pub fn wait_for_commit(&self, timeout: Duration) -> ConfigChangeOutcome {
             let start = std::time::Instant::now();
             loop {
                 // Trigger progress check with timeout handling
                 self.check_commit_progress();
 
+                if let Some(original) = self.rolled_back_to.lock().unwrap().clone() {
+                    return ConfigChangeOutcome::RolledBack(original);
+                }
+
                 let committed = *self.committed_index.lock().unwrap();
                 if committed > 0 {
-                    return true;
+                    return ConfigChangeOutcome::Committed(committed);
                 }
 
                 if start.elapsed() >= timeout {
-                    return false;
+                    return ConfigChangeOutcome::TimedOut;
                 }
 
                 thread::sleep(Duration::from_millis(50));
             }
         }
 
+        pub fn config_change_timeout(&self) -> Duration {
+            self.config_change_timeout
+        }
+
         pub fn was_rolled_back(&self) -> bool {
-            *self.rolled_back.lock().unwrap()
+            self.rolled_back_to.lock().unwrap().is_some()
+        }
+
+        pub fn committed_index(&self) -> LogIndex {
+            *self.committed_index.lock().unwrap()
+        }
+
+        /// Append `entry` to the log and return its index
+        pub fn append_entry(&self, entry: LogEntry) -> LogIndex {
+            let mut log = self.log.lock().unwrap();
+            log.push(entry);
+            log.len() as LogIndex
+        }
+
+        pub fn log(&self) -> Vec<LogEntry> {
+            self.log.lock().unwrap().clone()
         }
     }
 }
 
+// Both leaders are shared with the replication threads through an `Arc`, and
+// the configurations they hold move with them. Fail the build if a field
+// change makes any of them lose `Send` or `Sync`.
+// The checks live here and not in `tests/auto_traits.rs` because this is a
+// binary crate, and an integration test cannot import a binary's types.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Configuration>();
+    assert_send_sync::<JointConfiguration>();
+    assert_send_sync::<MajorityQuorum>();
+    assert_send_sync::<UnanimousQuorum>();
+    assert_send_sync::<MockClock>();
+    assert_send_sync::<Partition>();
+    assert_send_sync::<buggy::RaftNode>();
+    assert_send_sync::<fixed::RaftNode>();
+};
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let use_mock_clock = args.iter().any(|arg| arg == "--mock-clock");
+    let scenario = args.iter()
+        .position(|arg| arg == "--scenario")
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+        .unwrap_or("partition");
+    let policy = match args.iter().position(|arg| arg == "--policy").and_then(|i| args.get(i + 1)) {
+        Some(name) => match fixed::RollbackPolicy::parse(name) {
+            Some(policy) => policy,
+            None => {
+                eprintln!("Unknown --policy '{}': expected 'immediate', 'wait' or 'adaptive'", name);
+                std::process::exit(2);
+            }
+        },
+        None => fixed::RollbackPolicy::ImmediateTimeout,
+    };
 
     println!("=== raft-rs Issue #192: Joint Consensus Blocking ===\n");
 
-    if use_fixed {
-        println!("Running FIXED version (timeout and rollback)...\n");
-        run_fixed_test();
+    if scenario == "unreachable" {
+        println!("Running FIXED version with D and E marked unreachable...\n");
+        run_unreachable_test();
+    } else if scenario == "unanimous" {
+        println!("Running FIXED version with a unanimous C_new quorum...\n");
+        run_unanimous_test();
+    } else if scenario == "log" {
+        println!("Running {} version with normal entries around the config change...\n",
+                 if use_fixed { "FIXED" } else { "BUGGY" });
+        run_log_test(use_fixed);
+    } else if scenario == "timeout" {
+        println!("Running FIXED version with short and long config change timeouts...\n");
+        run_timeout_test();
+    } else if scenario == "learner" {
+        println!("Adding a node as a learner, then promoting it to voter...\n");
+        run_learner_test();
+    } else if scenario == "edge-configs" {
+        println!("Checking quorum math on zero- and single-voter configurations...\n");
+        run_edge_config_test();
+    } else if scenario == "slow-new" {
+        println!("Running FIXED version with a slow C_new ({:?} policy)...\n", policy);
+        run_slow_new_test(policy);
+    } else if scenario == "success" {
+        println!("Running {} version with all nodes reachable...\n",
+                 if use_fixed { "FIXED" } else { "BUGGY" });
+        run_success_test(use_fixed);
+    } else if scenario != "partition" {
+        eprintln!("Unknown --scenario '{}': expected 'partition', 'success', 'slow-new', 'edge-configs', 'learner', 'timeout', 'log', 'unanimous' or 'unreachable'", scenario);
+        std::process::exit(2);
+    } else if use_mock_clock {
+        println!("Running FIXED version against a mock clock...\n");
+        run_mock_clock_test();
+    } else if use_fixed {
+        println!("Running FIXED version (timeout and rollback, {:?} policy)...\n", policy);
+        run_fixed_test(policy);
     } else {
         println!("Running BUGGY version (blocks indefinitely)...\n");
         run_buggy_test();
@@ -328,11 +790,15 @@ fn run_buggy_test() {
 
     // Old config: A(1), B(2), C(3)
     // New config: D(4), E(5) - both unreachable
-    let c_old = Configuration::new(&[1, 2, 3]);
-    let c_new = Configuration::new(&[4, 5]);
+    let c_old = Configuration::new(&[1, 2, 3]).unwrap();
+    let c_new = Configuration::new(&[4, 5]).unwrap();
+
+    // The buggy node is told about the partition but has no use for it
+    leader.set_reachable(4, false);
+    leader.set_reachable(5, false);
 
     // Start config change
-    leader.begin_config_change(c_old, c_new);
+    leader.begin_config_change(c_old, c_new).unwrap();
 
     // Simulate responses from old config nodes (A, B, C respond)
     let leader_clone = Arc::clone(&leader);
@@ -359,37 +825,39 @@ fn run_buggy_test() {
 
     // Wait for commit with timeout
     let timeout = Duration::from_secs(2);
-    let committed = leader.wait_for_commit(timeout);
+    let outcome = leader.wait_for_commit(timeout);
 
     println!("\n=== Results ===");
-    if !committed && leader.is_blocked() {
-        println!("[BUG DEMONSTRATED]");
-        println!("Cluster is BLOCKED!");
-        println!("\nProblem:");
-        println!("  - Have quorum from C_old (A, B, C responded)");
-        println!("  - No quorum from C_new (D, E unreachable)");
-        println!("  - Joint consensus requires BOTH quorums");
-        println!("  - No timeout or rollback mechanism!");
-        println!("  - Cluster stuck indefinitely");
-        println!("\nRun with --fixed to see timeout/rollback.");
-    } else if committed {
-        println!("Config change committed (unexpected)");
-    } else {
-        println!("Timed out waiting for commit");
+    println!("Outcome: {:?}", outcome);
+    match outcome {
+        ConfigChangeOutcome::Blocked => {
+            print_banner("[BUG DEMONSTRATED]");
+            println!("Cluster is BLOCKED!");
+            println!("\nProblem:");
+            println!("  - Have quorum from C_old (A, B, C responded)");
+            println!("  - No quorum from C_new (D, E unreachable)");
+            println!("  - Joint consensus requires BOTH quorums");
+            println!("  - No timeout or rollback mechanism!");
+            println!("  - Cluster stuck indefinitely");
+            println!("\nRun with --fixed to see timeout/rollback.");
+        }
+        ConfigChangeOutcome::Committed(_) => println!("Config change committed (unexpected)"),
+        ConfigChangeOutcome::RolledBack(_) => unreachable!("the buggy node never rolls back"),
+        ConfigChangeOutcome::TimedOut => println!("Timed out waiting for commit"),
     }
 }
 
-fn run_fixed_test() {
+fn run_fixed_test(policy: fixed::RollbackPolicy) {
     println!("Scenario: Config change from [A,B,C] to [D,E]");
     println!("Problem: New config nodes D,E are unreachable");
     println!("Fix: Rollback to C_old after timeout\n");
 
-    let leader = Arc::new(fixed::RaftNode::new(1));
+    let leader = Arc::new(fixed::RaftNode::with_policy(1, Arc::new(SystemClock), policy));
 
-    let c_old = Configuration::new(&[1, 2, 3]);
-    let c_new = Configuration::new(&[4, 5]);
+    let c_old = Configuration::new(&[1, 2, 3]).unwrap();
+    let c_new = Configuration::new(&[4, 5]).unwrap();
 
-    leader.begin_config_change(c_old, c_new);
+    leader.begin_config_change(c_old.clone(), c_new).unwrap();
 
     let leader_clone = Arc::clone(&leader);
     thread::spawn(move || {
@@ -413,19 +881,557 @@ fn run_fixed_test() {
 
     // Wait for commit (should succeed via rollback)
     let timeout = Duration::from_secs(2);
-    let committed = leader.wait_for_commit(timeout);
+    let outcome = leader.wait_for_commit(timeout);
 
     println!("\n=== Results ===");
-    if committed && leader.was_rolled_back() {
-        println!("[FIXED]");
-        println!("Config change rolled back successfully!");
-        println!("\nFix: Implemented timeout and rollback");
-        println!("  - After timeout, rollback to original config");
-        println!("  - Cluster remains operational with C_old");
-        println!("  - Admin can retry config change later");
-    } else if committed {
-        println!("Config change committed normally");
+    println!("Outcome: {:?}", outcome);
+    match outcome {
+        ConfigChangeOutcome::RolledBack(restored) => {
+            assert_eq!(restored, c_old, "rolled back to a configuration other than C_old");
+            print_banner("[FIXED]");
+            println!("Config change rolled back successfully!");
+            println!("\nFix: Implemented timeout and rollback");
+            println!("  - After timeout, rollback to original config");
+            println!("  - Cluster remains operational with C_old");
+            println!("  - Admin can retry config change later");
+        }
+        ConfigChangeOutcome::Committed(_) => println!("Config change committed normally"),
+        ConfigChangeOutcome::TimedOut if policy == fixed::RollbackPolicy::WaitIndefinitely => {
+            print_banner("[NOTE]");
+            println!("WaitIndefinitely policy: still waiting for C_new after {:?}", timeout);
+            println!("  - Without a rollback deadline this is the original blocking behaviour");
+        }
+        ConfigChangeOutcome::TimedOut => println!("Unexpected: timed out"),
+        ConfigChangeOutcome::Blocked => unreachable!("the fixed node never reports Blocked"),
+    }
+}
+
+fn run_mock_clock_test() {
+    println!("Scenario: Config change from [A,B,C] to [D,E], timer driven by MockClock");
+    println!("Check: rollback fires only once the clock passes the timeout\n");
+
+    let clock = Arc::new(MockClock::new());
+    let leader = fixed::RaftNode::with_clock(1, Arc::clone(&clock) as Arc<dyn Clock>);
+
+    leader
+        .begin_config_change(Configuration::new(&[1, 2, 3]).unwrap(), Configuration::new(&[4, 5]).unwrap())
+        .unwrap();
+
+    // C_old quorum arrives immediately, C_new never responds
+    for node in [2, 3] {
+        leader.receive_replication_response(ReplicationStatus {
+            node,
+            success: true,
+            match_index: 1,
+        });
+    }
+
+    // Advance only partway: the timer must not fire yet
+    clock.advance(fixed::CONFIG_CHANGE_TIMEOUT / 2);
+    leader.check_commit_progress();
+    let early_rollback = leader.was_rolled_back();
+    assert!(!early_rollback, "rollback fired before the config change timeout");
+    assert_eq!(leader.committed_index(), 0);
+    println!("[MOCK] Advanced {:?}: rolled_back={}", fixed::CONFIG_CHANGE_TIMEOUT / 2, early_rollback);
+
+    // Advance past the timeout: the next progress check rolls back
+    clock.advance(fixed::CONFIG_CHANGE_TIMEOUT);
+    leader.check_commit_progress();
+    assert!(leader.was_rolled_back(), "rollback did not fire after the timeout");
+    assert_eq!(leader.committed_index(), 0, "a rollback must not mark the config entry committed");
+    assert_eq!(
+        leader.wait_for_commit(Duration::ZERO),
+        ConfigChangeOutcome::RolledBack(Configuration::new(&[1, 2, 3]).unwrap())
+    );
+    println!(
+        "[MOCK] Advanced past {:?}: rolled_back={}, committed_index={}",
+        fixed::CONFIG_CHANGE_TIMEOUT,
+        leader.was_rolled_back(),
+        leader.committed_index()
+    );
+
+    // Adaptive: D's first answer restarts the timer, its repeats do not
+    let clock = Arc::new(MockClock::new());
+    let adaptive = fixed::RaftNode::with_policy(
+        1,
+        Arc::clone(&clock) as Arc<dyn Clock>,
+        fixed::RollbackPolicy::Adaptive,
+    );
+    adaptive
+        .begin_config_change(Configuration::new(&[1, 2, 3]).unwrap(), Configuration::new(&[4, 5]).unwrap())
+        .unwrap();
+    let respond = |node| {
+        adaptive.receive_replication_response(ReplicationStatus { node, success: true, match_index: 1 })
+    };
+    respond(2);
+    respond(3);
+
+    let timeout = fixed::CONFIG_CHANGE_TIMEOUT;
+    clock.advance(timeout * 4 / 5);
+    respond(4);
+    clock.advance(timeout * 3 / 5);
+    adaptive.check_commit_progress();
+    assert!(!adaptive.was_rolled_back(), "D's first response should have restarted the timer");
+
+    respond(4);
+    respond(4);
+    clock.advance(timeout / 2);
+    adaptive.check_commit_progress();
+    assert!(
+        adaptive.was_rolled_back(),
+        "D's repeated responses restarted the timer; rollback should fire {:?} after its first",
+        timeout
+    );
+    println!("[MOCK] Adaptive: D's first response restarted the timer, its repeats did not");
+
+    println!("\n=== Results ===");
+    print_banner("[FIXED]");
+    println!("Rollback timer verified without sleeping!");
+    println!("  - No rollback before CONFIG_CHANGE_TIMEOUT");
+    println!("  - Rollback once the timeout elapsed, with nothing committed");
+    println!("  - Adaptive restarts the timer only on a C_new voter's first response");
+}
+
+/// Happy path: C_new nodes respond, so joint quorum is reached and the
+/// change commits without blocking or rolling back.
+fn run_success_test(use_fixed: bool) {
+    println!("Scenario: Config change from [A,B,C] to [D,E]");
+    println!("All nodes reachable: joint quorum should commit normally\n");
+
+    let c_old = Configuration::new(&[1, 2, 3]).unwrap();
+    let c_new = Configuration::new(&[4, 5]).unwrap();
+    let timeout = Duration::from_secs(2);
+
+    // Responses arrive well within the fixed version's rollback timeout
+    let responders: [(NodeId, u64); 4] = [(2, 50), (3, 75), (4, 100), (5, 125)];
+
+    let (outcome, blocked) = if use_fixed {
+        let leader = Arc::new(fixed::RaftNode::new(1));
+        leader.begin_config_change(c_old, c_new).unwrap();
+        for (node, delay_ms) in responders {
+            let leader_clone = Arc::clone(&leader);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(delay_ms));
+                leader_clone.receive_replication_response(ReplicationStatus {
+                    node,
+                    success: true,
+                    match_index: 1,
+                });
+            });
+        }
+        (leader.wait_for_commit(timeout), false)
     } else {
-        println!("Unexpected: timed out");
+        let leader = Arc::new(buggy::RaftNode::new(1));
+        leader.begin_config_change(c_old, c_new).unwrap();
+        let mut handles = Vec::new();
+        for (node, delay_ms) in responders {
+            let leader_clone = Arc::clone(&leader);
+            handles.push(thread::spawn(move || {
+                thread::sleep(Duration::from_millis(delay_ms));
+                leader_clone.receive_replication_response(ReplicationStatus {
+                    node,
+                    success: true,
+                    match_index: 1,
+                });
+            }));
+        }
+        // The buggy node flags itself blocked as soon as it sees C_old quorum
+        // without C_new, so wait for every response before checking.
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        (leader.wait_for_commit(timeout), leader.is_blocked())
+    };
+
+    assert_eq!(
+        outcome,
+        ConfigChangeOutcome::Committed(1),
+        "healthy config change did not commit at the config entry"
+    );
+
+    println!("\n=== Results ===");
+    print_banner("[NOTE]");
+    println!("Config change committed normally ({:?})", outcome);
+    println!("  - Joint quorum reached from both C_old and C_new");
+    if blocked {
+        println!("  - Buggy node briefly flagged itself blocked before C_new answered");
     }
 }
+
+/// C_new is reachable but slow: D answers before the rollback timeout and
+/// E only after it. Immediate rollback abandons the change; Adaptive treats
+/// D's answer as progress, keeps waiting, and commits once E answers.
+fn run_slow_new_test(policy: fixed::RollbackPolicy) {
+    println!("Scenario: Config change from [A,B,C] to [D,E]");
+    println!("D responds at 400ms, E at 700ms (rollback timeout {:?})\n",
+             fixed::CONFIG_CHANGE_TIMEOUT);
+
+    let leader = Arc::new(fixed::RaftNode::with_policy(1, Arc::new(SystemClock), policy));
+    leader
+        .begin_config_change(Configuration::new(&[1, 2, 3]).unwrap(), Configuration::new(&[4, 5]).unwrap())
+        .unwrap();
+
+    for (node, delay_ms) in [(2, 100), (3, 150), (4, 400), (5, 700)] {
+        let leader_clone = Arc::clone(&leader);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(delay_ms));
+            leader_clone.receive_replication_response(ReplicationStatus {
+                node,
+                success: true,
+                match_index: 1,
+            });
+        });
+    }
+
+    let outcome = leader.wait_for_commit(Duration::from_secs(2));
+
+    println!("\n=== Results ===");
+    println!("Policy: {:?}", policy);
+    println!("Outcome: {:?}", outcome);
+
+    match outcome {
+        ConfigChangeOutcome::Committed(_) => {
+            println!();
+            print_banner("[FIXED]");
+            println!("Config change committed once the slow C_new node answered.");
+            if policy == fixed::RollbackPolicy::Adaptive {
+                println!("  - D's response counted as progress and extended the deadline");
+            }
+        }
+        ConfigChangeOutcome::RolledBack(_) => {
+            println!();
+            print_banner("[NOTE]");
+            println!("Rolled back before E answered: the change was abandoned while making progress.");
+            println!("Run with --policy adaptive to keep waiting on partial C_new progress.");
+        }
+        ConfigChangeOutcome::TimedOut | ConfigChangeOutcome::Blocked => {
+            println!("\nUnexpected: {:?}", outcome);
+        }
+    }
+}
+
+/// Edge cases of the quorum math: configurations that could never reach
+/// quorum are rejected up front, and a lone voter is its own quorum.
+fn run_edge_config_test() {
+    let empty = Configuration::new(&[]);
+    println!("Configuration::new(&[]) -> {:?}", empty);
+    assert_eq!(empty, Err(ConfigError::NoVoters));
+
+    let single = Configuration::new(&[1]).unwrap();
+    let own_vote: HashSet<NodeId> = [1].into_iter().collect();
+    println!(
+        "Configuration::new(&[1]) -> quorum_size {}, has_quorum({{1}}) = {}",
+        single.quorum_size(),
+        single.has_quorum(&own_vote)
+    );
+    assert_eq!(single.quorum_size(), 1);
+    assert!(single.has_quorum(&own_vote));
+    assert!(!single.has_quorum(&HashSet::new()));
+
+    let valid = Configuration::new(&[1, 2, 3]).unwrap();
+    let no_voters = Configuration { voters: HashSet::new(), learners: HashSet::new(), quorum: Arc::new(MajorityQuorum) };
+    let empty_new = JointConfiguration::new(valid.clone(), no_voters.clone());
+    let empty_old = JointConfiguration::new(no_voters, valid.clone());
+    println!("JointConfiguration::new([1,2,3], []) -> {:?}", empty_new.as_ref().err());
+    println!("JointConfiguration::new([], [1,2,3]) -> {:?}", empty_old.as_ref().err());
+    assert_eq!(empty_new.err(), Some(ConfigError::EmptyJointSide("C_new")));
+    assert_eq!(empty_old.err(), Some(ConfigError::EmptyJointSide("C_old")));
+
+    let leader = fixed::RaftNode::new(1);
+    let rejected = leader.begin_config_change(valid, Configuration { voters: HashSet::new(), learners: HashSet::new(), quorum: Arc::new(MajorityQuorum) });
+    println!("begin_config_change([1,2,3], []) -> {:?}", rejected);
+    assert_eq!(rejected, Err(ConfigError::EmptyJointSide("C_new")));
+
+    println!("\n=== Results ===");
+    println!("Empty configuration rejected: yes");
+    println!("Single-voter quorum: 1");
+    println!("Joint configuration with an empty side rejected: yes");
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Configurations that could never reach quorum are errors, not silent hangs.");
+}
+
+/// Send a successful replication response from each of `nodes`
+fn replicate_from(leader: &fixed::RaftNode, nodes: &[NodeId]) {
+    for &node in nodes {
+        leader.receive_replication_response(ReplicationStatus {
+            node,
+            success: true,
+            match_index: 1,
+        });
+    }
+}
+
+/// Node D(4) joins [A,B,C] the way Raft adds members safely: first as a
+/// learner that catches up on the log, then promoted to voter by a second
+/// config change. Until the promotion, D's votes must never count.
+fn run_learner_test() {
+    let c_old = Configuration::new(&[1, 2, 3]).unwrap();
+    let with_learner = Configuration::with_learners(&[1, 2, 3], &[4]).unwrap();
+    let mut promoted = with_learner.clone();
+    promoted.promote(4).unwrap();
+
+    assert_eq!(
+        Configuration::with_learners(&[1, 2, 3], &[3]),
+        Err(ConfigError::LearnerIsVoter(3))
+    );
+    assert_eq!(promoted.clone().promote(4), Err(ConfigError::NotALearner(4)));
+    assert_eq!(with_learner.quorum_size(), c_old.quorum_size());
+
+    let leader_and_learner: HashSet<NodeId> = [1, 4].into_iter().collect();
+
+    println!("--- Step 1: [A,B,C] -> [A,B,C] + learner D ---");
+    let joint = JointConfiguration::new(c_old.clone(), with_learner.clone()).unwrap();
+    assert!(
+        !joint.has_joint_quorum(&leader_and_learner),
+        "learner D counted toward joint quorum before promotion"
+    );
+
+    let leader = fixed::RaftNode::new(1);
+    leader.begin_config_change(c_old, with_learner.clone()).unwrap();
+    // D catches up on the log; its response alone must not commit anything
+    replicate_from(&leader, &[4]);
+    let committed_on_learner = leader.committed_index();
+    assert_eq!(committed_on_learner, 0, "config change committed on the learner's response");
+    replicate_from(&leader, &[2]);
+    assert_eq!(leader.committed_index(), 1, "learner config change did not commit");
+
+    println!("\n--- Step 2: promote D, [A,B,C] + learner D -> [A,B,C,D] ---");
+    let joint = JointConfiguration::new(with_learner.clone(), promoted.clone()).unwrap();
+    assert!(!joint.has_joint_quorum(&leader_and_learner));
+    assert!(joint.has_joint_quorum(&[1, 2, 4].into_iter().collect()));
+
+    let leader = fixed::RaftNode::new(1);
+    leader.begin_config_change(with_learner, promoted.clone()).unwrap();
+    replicate_from(&leader, &[4]);
+    assert_eq!(leader.committed_index(), 0, "{{A,D}} is not a quorum of C_old [A,B,C]");
+    replicate_from(&leader, &[2]);
+    assert_eq!(leader.committed_index(), 1, "promotion did not commit");
+
+    println!("\n=== Results ===");
+    println!("Committed after learner D's response alone: {}", committed_on_learner > 0);
+    println!("Voters after promotion: {}", promoted.voters.len());
+    println!("Learners after promotion: {}", promoted.learners.len());
+    println!("Quorum after promotion: {}", promoted.quorum_size());
+
+    println!();
+    print_banner("[FIXED]");
+    println!("D replicated the log as a learner without ever counting toward quorum.");
+    println!("Only the second change, from learner to voter, let D's vote count.");
+}
+
+/// Start [A,B,C] -> [D,E] with D and E unreachable and wait up to `window`.
+/// Returns the outcome and how long it took to resolve.
+fn rollback_within(leader: &fixed::RaftNode, window: Duration) -> (ConfigChangeOutcome, Duration) {
+    leader
+        .begin_config_change(Configuration::new(&[1, 2, 3]).unwrap(), Configuration::new(&[4, 5]).unwrap())
+        .unwrap();
+    replicate_from(leader, &[2, 3]);
+
+    let start = Instant::now();
+    let outcome = leader.wait_for_commit(window);
+    (outcome, start.elapsed())
+}
+
+/// The same partition with two timeouts: a short one rolls back well before
+/// the 500ms default, and a long one is still waiting for C_new when the
+/// observation window closes
+fn run_timeout_test() {
+    let window = Duration::from_millis(300);
+
+    println!("--- 50ms timeout ---");
+    let short = fixed::RaftNode::new_with_timeout(1, Duration::from_millis(50));
+    let (short_outcome, short_elapsed) = rollback_within(&short, Duration::from_secs(2));
+    assert!(
+        matches!(short_outcome, ConfigChangeOutcome::RolledBack(_)),
+        "50ms timeout did not roll back: {:?}",
+        short_outcome
+    );
+    assert!(
+        short_elapsed < fixed::CONFIG_CHANGE_TIMEOUT,
+        "50ms timeout took {:?} to roll back",
+        short_elapsed
+    );
+
+    println!("\n--- 5s timeout, watched for {:?} ---", window);
+    let long = fixed::RaftNode::new_with_timeout(1, Duration::from_secs(5));
+    let (long_outcome, _) = rollback_within(&long, window);
+    assert_eq!(long_outcome, ConfigChangeOutcome::TimedOut, "5s timeout resolved within {:?}", window);
+    assert_eq!(long.committed_index(), 0);
+
+    assert_eq!(fixed::RaftNode::new(1).config_change_timeout(), fixed::CONFIG_CHANGE_TIMEOUT);
+
+    println!("\n=== Results ===");
+    println!("{:?} timeout: rolled back after {:?}", short.config_change_timeout(), short_elapsed);
+    println!(
+        "{:?} timeout: {:?} after {:?}",
+        long.config_change_timeout(),
+        long_outcome,
+        window
+    );
+
+    println!();
+    print_banner("[FIXED]");
+    println!("The rollback deadline follows the timeout given to new_with_timeout.");
+    println!("RaftNode::new keeps the {:?} default.", fixed::CONFIG_CHANGE_TIMEOUT);
+}
+
+/// One letter per log entry: `C` for a config change, `N` for a normal entry
+fn log_layout(log: &[LogEntry]) -> String {
+    log.iter()
+        .map(|entry| match entry {
+            LogEntry::ConfigChange(_) => 'C',
+            LogEntry::Normal(_) => 'N',
+        })
+        .collect()
+}
+
+/// Two normal entries, then the config change, then one more normal entry.
+/// Every node answers, so the change commits at the config entry's index.
+fn run_log_test(use_fixed: bool) {
+    let c_old = Configuration::new(&[1, 2, 3]).unwrap();
+    let c_new = Configuration::new(&[4, 5]).unwrap();
+    let responders: [NodeId; 4] = [2, 3, 4, 5];
+
+    let (config_index, committed_index, log) = if use_fixed {
+        let leader = fixed::RaftNode::new(1);
+        leader.append_entry(LogEntry::Normal(b"put a".to_vec()));
+        leader.append_entry(LogEntry::Normal(b"put b".to_vec()));
+        leader.begin_config_change(c_old, c_new).unwrap();
+        let config_index = leader.log().len() as LogIndex;
+        let after = leader.append_entry(LogEntry::Normal(b"put c".to_vec()));
+        assert_eq!(after, config_index + 1);
+        replicate_from(&leader, &responders);
+        (config_index, leader.committed_index(), leader.log())
+    } else {
+        let leader = buggy::RaftNode::new(1);
+        leader.append_entry(LogEntry::Normal(b"put a".to_vec()));
+        leader.append_entry(LogEntry::Normal(b"put b".to_vec()));
+        leader.begin_config_change(c_old, c_new).unwrap();
+        let config_index = leader.log().len() as LogIndex;
+        let after = leader.append_entry(LogEntry::Normal(b"put c".to_vec()));
+        assert_eq!(after, config_index + 1);
+        for node in responders {
+            leader.receive_replication_response(ReplicationStatus {
+                node,
+                success: true,
+                match_index: after,
+            });
+        }
+        (config_index, leader.committed_index(), leader.log())
+    };
+
+    let layout = log_layout(&log);
+    assert_eq!(layout, "NNCN", "normal entries and the config change interleaved out of order");
+    assert_eq!(config_entry_index(&log), Some(config_index));
+    assert_eq!(
+        committed_index, config_index,
+        "committed index does not match the config change entry"
+    );
+
+    println!("\n=== Results ===");
+    println!("Log layout (C=config change, N=normal): {}", layout);
+    println!("Config change entry index: {}", config_index);
+    println!("Committed index: {}", committed_index);
+
+    println!();
+    print_banner("[NOTE]");
+    println!("The config change is a real log entry, and joint quorum commits up to its index.");
+}
+
+/// [A,B,C] -> [D,E,F] with F unreachable. D and E are a majority of C_new,
+/// so the majority rule commits; the unanimous rule waits for F.
+fn run_unanimous_test() {
+    let c_old = Configuration::new(&[1, 2, 3]).unwrap();
+    let majority = Configuration::new(&[4, 5, 6]).unwrap();
+    let unanimous = majority.clone().with_quorum_policy(Arc::new(UnanimousQuorum));
+    let votes: HashSet<NodeId> = [1, 2, 4, 5].into_iter().collect();
+
+    assert_eq!(majority.quorum_size(), 2);
+    assert_eq!(unanimous.quorum_size(), 3);
+    assert!(majority.has_quorum(&votes));
+    assert!(!unanimous.has_quorum(&votes), "unanimous quorum reached with F missing");
+    assert_ne!(majority, unanimous);
+
+    let joint_majority = JointConfiguration::new(c_old.clone(), majority.clone()).unwrap();
+    let joint_unanimous = JointConfiguration::new(c_old.clone(), unanimous.clone()).unwrap();
+    assert!(joint_majority.has_joint_quorum(&votes));
+    assert!(!joint_unanimous.has_joint_quorum(&votes));
+
+    let window = Duration::from_millis(300);
+    let mut outcomes = Vec::new();
+    for c_new in [majority, unanimous] {
+        println!("--- C_new quorum: {} ---", c_new.quorum.name());
+        // Long rollback timeout so the window shows waiting, not rollback
+        let leader = fixed::RaftNode::new_with_timeout(1, Duration::from_secs(5));
+        leader.begin_config_change(c_old.clone(), c_new.clone()).unwrap();
+        replicate_from(&leader, &[2, 4, 5]);
+        let outcome = leader.wait_for_commit(window);
+        println!("[FIXED] Without F after {:?}: {:?}\n", window, outcome);
+        outcomes.push((c_new.quorum.name(), outcome));
+    }
+
+    assert_eq!(outcomes[0].1, ConfigChangeOutcome::Committed(1));
+    assert_eq!(
+        outcomes[1].1,
+        ConfigChangeOutcome::TimedOut,
+        "a single missing vote must block a unanimous commit"
+    );
+
+    println!("=== Results ===");
+    for (name, outcome) in &outcomes {
+        println!("{}: {:?}", name, outcome);
+    }
+
+    println!();
+    print_banner("[NOTE]");
+    println!("D and E are a majority of [D,E,F], but the unanimous policy also needs F.");
+    println!("Each side of the joint configuration is checked under its own policy.");
+}
+
+/// The default partition run twice with the 500ms timeout. Left silent, D
+/// and E cost the full timeout. Marked unreachable, they are a known
+/// partition, and the node rolls back as soon as C_old has quorum.
+fn run_unreachable_test() {
+    let c_old = Configuration::new(&[1, 2, 3]).unwrap();
+    let c_new = Configuration::new(&[4, 5]).unwrap();
+    let mut elapsed = Vec::new();
+
+    for mark_unreachable in [false, true] {
+        println!("--- D and E {} ---", if mark_unreachable { "marked unreachable" } else { "silent" });
+        let leader = fixed::RaftNode::new(1);
+        if mark_unreachable {
+            leader.set_reachable(4, false);
+            leader.set_reachable(5, false);
+        }
+        leader.begin_config_change(c_old.clone(), c_new.clone()).unwrap();
+        // Silent, D and E never answer. Marked unreachable, D's response is
+        // sent but cannot cross the partition.
+        let responders: &[NodeId] = if mark_unreachable { &[2, 3, 4] } else { &[2, 3] };
+        replicate_from(&leader, responders);
+
+        let start = Instant::now();
+        let outcome = leader.wait_for_commit(Duration::from_secs(2));
+        elapsed.push(start.elapsed());
+        assert_eq!(outcome, ConfigChangeOutcome::RolledBack(c_old.clone()));
+        println!();
+    }
+
+    let (silent, partitioned) = (elapsed[0], elapsed[1]);
+    assert!(silent >= fixed::CONFIG_CHANGE_TIMEOUT, "silent C_new rolled back after {:?}", silent);
+    assert!(
+        partitioned < fixed::CONFIG_CHANGE_TIMEOUT,
+        "known partition still waited {:?} to roll back",
+        partitioned
+    );
+
+    println!("=== Results ===");
+    println!("Rollback with silent D, E: {:?}", silent);
+    println!("Rollback with D, E unreachable: {:?}", partitioned);
+
+    println!();
+    print_banner("[FIXED]");
+    println!("With D and E known unreachable, C_new could never reach quorum,");
+    println!("so the node rolled back at once instead of waiting out the timeout.");
+}
@@ -8,7 +8,7 @@
 
 use std::collections::HashMap;
 use std::env;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::Duration;
 
@@ -51,48 +51,119 @@ impl ChainState {
     }
 }
 
+/// Wait-for graph snapshot, exported as Graphviz DOT with `--format dot`
+///
+/// Each crate builds on its own, so this is copied into ballista-deadlock-132,
+/// reth-deadlock-10842, sui-blocking-5201, sui-deadlock-335, sui-deadlock-960
+/// and sui-race-4990. Keep the copies identical; reth-deadlock-10842 also
+/// has `remove_edge`.
+#[derive(Debug, Default)]
+pub struct WaitForGraph {
+    edges: Vec<(String, String, String)>,
+}
+
+impl WaitForGraph {
+    fn add_edge(&mut self, from: &str, to: &str, label: &str) {
+        self.edges.push((from.to_string(), to.to_string(), label.to_string()));
+    }
+
+    /// Drop an edge once it stops holding. Edges point from an operation to
+    /// the lock it waits for, and from a lock to the operation holding it, so
+    /// a deadlock shows up as a cycle.
+    fn remove_edge(&mut self, from: &str, to: &str) {
+        self.edges.retain(|(f, t, _)| f != from || t != to);
+    }
+
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph {} {{\n", name);
+        for (from, to, label) in &self.edges {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", from, to, label));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
 /// Buggy implementation - inconsistent lock order
 mod buggy {
     use super::*;
 
     pub struct ChainStateManager {
         state: Arc<ChainState>,
+        wait_for: Mutex<WaitForGraph>,
     }
 
     impl ChainStateManager {
         pub fn new(state: Arc<ChainState>) -> Self {
-            Self { state }
+            Self {
+                state,
+                wait_for: Mutex::new(WaitForGraph::default()),
+            }
+        }
+
+        fn waiting(&self, op: &str, lock: &str) {
+            self.wait_for.lock().unwrap().add_edge(op, lock, "waits for");
+        }
+
+        fn acquired(&self, op: &str, lock: &str) {
+            let mut graph = self.wait_for.lock().unwrap();
+            graph.remove_edge(op, lock);
+            graph.add_edge(lock, op, "held by");
+        }
+
+        fn released(&self, op: &str, locks: &[&str]) {
+            let mut graph = self.wait_for.lock().unwrap();
+            for lock in locks {
+                graph.remove_edge(lock, op);
+            }
+        }
+
+        /// Snapshot of who holds and who waits for which lock
+        pub fn wait_for_dot(&self) -> String {
+            self.wait_for.lock().unwrap().to_dot("reth_10842")
         }
 
         /// BUG: Acquires numbers lock first, then blocks lock
         pub fn read_operation(&self, hash: &str) -> Option<Block> {
             println!("[BUGGY] read_operation: acquiring numbers lock...");
+            self.waiting("read_operation", "numbers");
             let numbers = self.state.numbers.read().unwrap();
+            self.acquired("read_operation", "numbers");
 
             thread::sleep(Duration::from_millis(50)); // Simulate work
 
-            if let Some(&block_number) = numbers.get(hash) {
+            let result = if let Some(&block_number) = numbers.get(hash) {
                 println!("[BUGGY] read_operation: acquiring blocks lock...");
+                self.waiting("read_operation", "blocks");
                 let blocks = self.state.blocks.read().unwrap();
+                self.acquired("read_operation", "blocks");
                 blocks.get(&block_number).cloned()
             } else {
                 None
-            }
+            };
+
+            self.released("read_operation", &["numbers", "blocks"]);
+            result
         }
 
         /// BUG: Acquires blocks lock first, then numbers lock (WRONG ORDER!)
         pub fn write_operation(&self, block: Block) {
             println!("[BUGGY] write_operation: acquiring blocks lock...");
+            self.waiting("write_operation", "blocks");
             let mut blocks = self.state.blocks.write().unwrap();
+            self.acquired("write_operation", "blocks");
 
             thread::sleep(Duration::from_millis(50)); // Simulate work
 
             println!("[BUGGY] write_operation: acquiring numbers lock...");
+            self.waiting("write_operation", "numbers");
             let mut numbers = self.state.numbers.write().unwrap();
+            self.acquired("write_operation", "numbers");
 
             numbers.insert(block.hash.clone(), block.number);
             blocks.insert(block.number, block);
 
+            self.released("write_operation", &["blocks", "numbers"]);
             println!("[BUGGY] write_operation: completed");
         }
 
@@ -153,9 +224,38 @@ mod fixed {
     }
 }
 
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let format_dot = args
+        .iter()
+        .position(|arg| arg == "--format")
+        .and_then(|i| args.get(i + 1))
+        .is_some_and(|format| format == "dot");
 
     println!("=== Reth Issue #10842: Lock Ordering Deadlock ===\n");
 
@@ -165,11 +265,11 @@ fn main() {
     } else {
         println!("Running BUGGY version (inconsistent lock order)...\n");
         println!("NOTE: This may deadlock! Kill with Ctrl+C if it hangs.\n");
-        run_buggy_test();
+        run_buggy_test(format_dot);
     }
 }
 
-fn run_buggy_test() {
+fn run_buggy_test(format_dot: bool) {
     let state = Arc::new(ChainState::new());
     let manager = Arc::new(buggy::ChainStateManager::new(Arc::clone(&state)));
 
@@ -215,12 +315,25 @@ fn run_buggy_test() {
 
         if start.elapsed() > timeout {
             println!("\n=== Results ===");
-            println!("[DEADLOCK DETECTED]");
+            print_banner("[DEADLOCK DETECTED]");
             println!("Threads did not complete within 3 seconds!");
             println!("\nDeadlock scenario:");
             println!("  Thread 1: holds numbers(read), waiting for blocks(read)");
             println!("  Thread 2: holds blocks(write), waiting for numbers(write)");
             println!("\nClassic lock ordering deadlock!");
+
+            // Each thread's wait on the other's lock closes the cycle
+            let dot = manager.wait_for_dot();
+            for edge in [
+                "\"read_operation\" -> \"blocks\" [label=\"waits for\"];",
+                "\"write_operation\" -> \"numbers\" [label=\"waits for\"];",
+            ] {
+                assert!(dot.contains(edge), "wait-for graph is missing {}:\n{}", edge, dot);
+            }
+            if format_dot {
+                println!("\n=== Wait-For Graph (DOT) ===");
+                print!("{}", dot);
+            }
             println!("\nRun with --fixed to see consistent lock ordering.");
             std::process::exit(1);
         }
@@ -267,7 +380,7 @@ fn run_fixed_test() {
     handle2.join().unwrap();
 
     println!("\n=== Results ===");
-    println!("[FIXED]");
+    print_banner("[FIXED]");
     println!("Both threads completed successfully!");
     println!("Consistent lock order (numbers -> blocks) prevents deadlock.");
 }
@@ -0,0 +1,458 @@
+//! Single-Queue Scheduler Contention
+//!
+//! This reproduces the throughput ceiling of a scheduler where every worker
+//! takes tasks from one `Mutex<VecDeque>`. Each dispatch does a little
+//! bookkeeping under the queue lock, so all dispatches are serialized and
+//! adding workers stops helping once they spend their time waiting for it.
+//!
+//! Pattern reproduction: not tied to a single upstream issue. The shared
+//! queue is the model behind ballista-deadlock-132's `pending_queue`; the fix
+//! is the per-worker work-stealing deque used by tokio and rayon.
+
+use std::collections::VecDeque;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_WORKERS: usize = 16;
+const DEFAULT_TASKS: usize = 2000;
+const DEFAULT_ITERATIONS: usize = 3;
+/// Worker counts compared by `--bench`, up to `--workers`
+const BENCH_WORKERS: [usize; 5] = [1, 2, 4, 8, 16];
+/// Scheduler bookkeeping done under the queue lock on every dispatch
+const DISPATCH_COST: Duration = Duration::from_micros(10);
+
+/// A short I/O-bound task
+#[derive(Debug)]
+pub struct Task {
+    duration: Duration,
+}
+
+impl Task {
+    fn run(&self) {
+        thread::sleep(self.duration);
+    }
+}
+
+/// Tasks get longer in four steps from 100µs to 400µs, so the workers given
+/// the last block of tasks have the most work
+fn make_tasks(count: usize) -> Vec<Task> {
+    (0..count)
+        .map(|id| Task {
+            duration: Duration::from_micros(100 + (id * 4 / count.max(1)) as u64 * 100),
+        })
+        .collect()
+}
+
+/// Lock `mutex`, counting the acquisition as contended if another worker
+/// held it
+fn lock_counted<'a, T>(mutex: &'a Mutex<T>, contended: &AtomicU64) -> MutexGuard<'a, T> {
+    match mutex.try_lock() {
+        Ok(guard) => guard,
+        Err(_) => {
+            contended.fetch_add(1, Ordering::Relaxed);
+            mutex.lock().unwrap()
+        }
+    }
+}
+
+/// Do the dispatch bookkeeping and add its duration to `held_ns`, the total
+/// time the lock it runs under has been held
+fn dispatch(held_ns: &AtomicU64) {
+    let start = Instant::now();
+    thread::sleep(DISPATCH_COST);
+    held_ns.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+}
+
+/// Buggy version - every worker pops from one shared queue
+mod buggy {
+    use super::*;
+
+    pub struct SharedQueue {
+        queue: Mutex<VecDeque<Task>>,
+        held_ns: AtomicU64,
+        contended: AtomicU64,
+    }
+
+    impl SharedQueue {
+        pub fn new(tasks: Vec<Task>) -> Self {
+            Self {
+                queue: Mutex::new(tasks.into()),
+                held_ns: AtomicU64::new(0),
+                contended: AtomicU64::new(0),
+            }
+        }
+
+        /// BUG: one lock for all workers; its hold time bounds the
+        /// dispatch rate no matter how many workers there are
+        fn pop(&self) -> Option<Task> {
+            let mut queue = lock_counted(&self.queue, &self.contended);
+            let task = queue.pop_front()?;
+            dispatch(&self.held_ns);
+            Some(task)
+        }
+
+        /// Run tasks until the queue is empty; returns how many this worker ran
+        pub fn run_worker(&self, _worker: usize) -> usize {
+            let mut completed = 0;
+            while let Some(task) = self.pop() {
+                task.run();
+                completed += 1;
+            }
+            completed
+        }
+
+        pub fn contended(&self) -> u64 {
+            self.contended.load(Ordering::Relaxed)
+        }
+
+        pub fn steals(&self) -> u64 {
+            0
+        }
+
+        pub fn busiest_lock(&self) -> Duration {
+            Duration::from_nanos(self.held_ns.load(Ordering::Relaxed))
+        }
+    }
+}
+
+/// Fixed version - one deque per worker, idle workers steal
+mod fixed {
+    use super::*;
+
+    pub struct WorkStealingPool {
+        deques: Vec<Mutex<VecDeque<Task>>>,
+        /// Total hold time of each deque's lock
+        held_ns: Vec<AtomicU64>,
+        contended: AtomicU64,
+        steals: AtomicU64,
+    }
+
+    impl WorkStealingPool {
+        /// Give each worker one contiguous block of the tasks
+        pub fn new(tasks: Vec<Task>, workers: usize) -> Self {
+            let workers = workers.max(1);
+            let block = tasks.len().div_ceil(workers).max(1);
+            let mut deques: Vec<VecDeque<Task>> = (0..workers).map(|_| VecDeque::new()).collect();
+            for (i, task) in tasks.into_iter().enumerate() {
+                deques[i / block].push_back(task);
+            }
+
+            Self {
+                deques: deques.into_iter().map(Mutex::new).collect(),
+                held_ns: (0..workers).map(|_| AtomicU64::new(0)).collect(),
+                contended: AtomicU64::new(0),
+                steals: AtomicU64::new(0),
+            }
+        }
+
+        /// FIX: the owner pops from the back of its own deque, a lock no
+        /// other worker touches unless it is stealing
+        fn pop_local(&self, worker: usize) -> Option<Task> {
+            let mut deque = lock_counted(&self.deques[worker], &self.contended);
+            let task = deque.pop_back()?;
+            dispatch(&self.held_ns[worker]);
+            Some(task)
+        }
+
+        /// Take half of the first non-empty victim's deque from the front,
+        /// keep the rest locally and return one task to run now
+        fn steal(&self, worker: usize) -> Option<Task> {
+            let workers = self.deques.len();
+            for offset in 1..workers {
+                let victim = (worker + offset) % workers;
+                let mut stolen: VecDeque<Task> = {
+                    let mut deque = lock_counted(&self.deques[victim], &self.contended);
+                    let half = deque.len().div_ceil(2);
+                    if half == 0 {
+                        continue;
+                    }
+                    dispatch(&self.held_ns[victim]);
+                    deque.drain(..half).collect()
+                };
+                self.steals.fetch_add(1, Ordering::Relaxed);
+
+                let task = stolen.pop_front();
+                lock_counted(&self.deques[worker], &self.contended).extend(stolen);
+                return task;
+            }
+            None
+        }
+
+        /// Run local tasks, steal when out, and stop once every deque is
+        /// empty; returns how many this worker ran
+        pub fn run_worker(&self, worker: usize) -> usize {
+            let mut completed = 0;
+            while let Some(task) = self.pop_local(worker).or_else(|| self.steal(worker)) {
+                task.run();
+                completed += 1;
+            }
+            completed
+        }
+
+        pub fn contended(&self) -> u64 {
+            self.contended.load(Ordering::Relaxed)
+        }
+
+        pub fn steals(&self) -> u64 {
+            self.steals.load(Ordering::Relaxed)
+        }
+
+        pub fn busiest_lock(&self) -> Duration {
+            let max = self.held_ns.iter().map(|ns| ns.load(Ordering::Relaxed)).max();
+            Duration::from_nanos(max.unwrap_or(0))
+        }
+    }
+}
+
+/// Timing and lock statistics for one run of a scheduler
+#[derive(Debug)]
+pub struct RunOutcome {
+    elapsed: Duration,
+    completed: usize,
+    contended: u64,
+    steals: u64,
+    /// Hold time of the most heavily used queue lock
+    busiest_lock: Duration,
+}
+
+impl RunOutcome {
+    fn tasks_per_sec(&self) -> f64 {
+        self.completed as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Fraction of the run the busiest queue lock was held
+    fn busiest_lock_utilization(&self) -> f64 {
+        self.busiest_lock.as_secs_f64() / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Lock statistics a scheduler reports after a run
+struct PoolStats<P> {
+    contended: fn(&P) -> u64,
+    steals: fn(&P) -> u64,
+    busiest_lock: fn(&P) -> Duration,
+}
+
+/// Start `workers` threads running `run_worker` against `pool`
+fn run_pool<P: Send + Sync + 'static>(
+    pool: Arc<P>,
+    workers: usize,
+    run_worker: fn(&P, usize) -> usize,
+    stats: PoolStats<P>,
+) -> RunOutcome {
+    let start = Instant::now();
+    let handles: Vec<_> = (0..workers)
+        .map(|worker| {
+            let pool = Arc::clone(&pool);
+            thread::spawn(move || run_worker(&pool, worker))
+        })
+        .collect();
+    let completed = handles.into_iter().map(|h| h.join().unwrap()).sum();
+
+    RunOutcome {
+        elapsed: start.elapsed(),
+        completed,
+        contended: (stats.contended)(&pool),
+        steals: (stats.steals)(&pool),
+        busiest_lock: (stats.busiest_lock)(&pool),
+    }
+}
+
+fn run_single_queue(workers: usize, tasks: usize) -> RunOutcome {
+    run_pool(
+        Arc::new(buggy::SharedQueue::new(make_tasks(tasks))),
+        workers,
+        buggy::SharedQueue::run_worker,
+        PoolStats {
+            contended: buggy::SharedQueue::contended,
+            steals: buggy::SharedQueue::steals,
+            busiest_lock: buggy::SharedQueue::busiest_lock,
+        },
+    )
+}
+
+fn run_work_stealing(workers: usize, tasks: usize) -> RunOutcome {
+    run_pool(
+        Arc::new(fixed::WorkStealingPool::new(make_tasks(tasks), workers)),
+        workers,
+        fixed::WorkStealingPool::run_worker,
+        PoolStats {
+            contended: fixed::WorkStealingPool::contended,
+            steals: fixed::WorkStealingPool::steals,
+            busiest_lock: fixed::WorkStealingPool::busiest_lock,
+        },
+    )
+}
+
+fn print_outcome(outcome: &RunOutcome, workers: usize, tasks: usize) {
+    println!("=== Results ===");
+    println!("Completed {} of {} tasks in {:?}", outcome.completed, tasks, outcome.elapsed);
+    println!("Contended lock acquisitions: {}", outcome.contended);
+    println!(
+        "Busiest queue lock held for {:.0}% of the run",
+        outcome.busiest_lock_utilization() * 100.0
+    );
+
+    println!("\n=== Metrics ===");
+    println!("workers: {}", workers);
+    println!("tasks: {}", tasks);
+    println!("elapsed_ms: {:.1}", outcome.elapsed.as_secs_f64() * 1000.0);
+    println!("tasks_per_sec: {:.0}", outcome.tasks_per_sec());
+    println!("contended_locks: {}", outcome.contended);
+    println!("steals: {}", outcome.steals);
+    println!("busiest_lock_utilization: {:.2}", outcome.busiest_lock_utilization());
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let parse_flag = |name: &str, default: usize| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+            .max(1)
+    };
+    let workers = parse_flag("--workers", DEFAULT_WORKERS);
+    let tasks = parse_flag("--tasks", DEFAULT_TASKS);
+    let iterations = parse_flag("--iterations", DEFAULT_ITERATIONS);
+
+    println!("=== Single-Queue Scheduler Contention ===\n");
+
+    if args.iter().any(|arg| arg == "--bench") {
+        println!("Benchmarking both schedulers, median of {} runs each...\n", iterations);
+        run_bench(workers, tasks, iterations);
+    } else if use_fixed {
+        println!("Running FIXED version (per-worker deques with stealing)...\n");
+        run_fixed_test(workers, tasks);
+    } else {
+        println!("Running BUGGY version (one shared Mutex<VecDeque>)...\n");
+        run_buggy_test(workers, tasks);
+    }
+}
+
+fn run_buggy_test(workers: usize, tasks: usize) {
+    println!("{} workers, {} tasks, {:?} dispatch cost under the queue lock\n", workers, tasks, DISPATCH_COST);
+
+    let outcome = run_single_queue(workers, tasks);
+    print_outcome(&outcome, workers, tasks);
+
+    assert_eq!(outcome.completed, tasks, "shared queue dropped tasks");
+
+    if outcome.busiest_lock_utilization() > 0.8 {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!(
+            "The queue lock was held for {:.0}% of the run; dispatch is serialized on it.",
+            outcome.busiest_lock_utilization() * 100.0
+        );
+        println!("Adding workers only adds waiters, so throughput stops growing.");
+        println!("\nRun with --fixed to see work stealing, or --bench to compare.");
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("The queue lock was not the bottleneck this run; try more workers with --workers.");
+    }
+}
+
+fn run_fixed_test(workers: usize, tasks: usize) {
+    println!("{} workers, {} tasks, {:?} dispatch cost under the deque lock\n", workers, tasks, DISPATCH_COST);
+
+    let outcome = run_work_stealing(workers, tasks);
+    print_outcome(&outcome, workers, tasks);
+
+    assert_eq!(outcome.completed, tasks, "work-stealing pool dropped tasks");
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Each worker dispatched from its own deque, so dispatches ran in parallel.");
+    println!("Idle workers stole {} batches from busier ones.", outcome.steals);
+}
+
+/// Median elapsed time over `iterations` runs
+fn median_run(run: fn(usize, usize) -> RunOutcome, workers: usize, tasks: usize, iterations: usize) -> Duration {
+    let mut times: Vec<Duration> = (0..iterations)
+        .map(|_| {
+            let outcome = run(workers, tasks);
+            assert_eq!(outcome.completed, tasks, "scheduler dropped tasks");
+            outcome.elapsed
+        })
+        .collect();
+    times.sort();
+    times[times.len() / 2]
+}
+
+fn run_bench(max_workers: usize, tasks: usize, iterations: usize) {
+    let mut counts: Vec<usize> = BENCH_WORKERS.iter().copied().filter(|&w| w < max_workers).collect();
+    counts.push(max_workers);
+
+    println!("  {:>7} {:>14} {:>14} {:>8}", "workers", "single_ms", "stealing_ms", "speedup");
+    let mut last = (Duration::ZERO, Duration::ZERO);
+    for &workers in &counts {
+        let single = median_run(run_single_queue, workers, tasks, iterations);
+        let stealing = median_run(run_work_stealing, workers, tasks, iterations);
+        println!(
+            "  {:>7} {:>14.1} {:>14.1} {:>7.2}x",
+            workers,
+            single.as_secs_f64() * 1000.0,
+            stealing.as_secs_f64() * 1000.0,
+            single.as_secs_f64() / stealing.as_secs_f64()
+        );
+        last = (single, stealing);
+    }
+
+    let (single, stealing) = last;
+    println!("\n=== Metrics ===");
+    println!("tasks: {}", tasks);
+    println!("max_workers: {}", max_workers);
+    println!("single_queue_ms: {:.1}", single.as_secs_f64() * 1000.0);
+    println!("work_stealing_ms: {:.1}", stealing.as_secs_f64() * 1000.0);
+
+    if max_workers >= 8 {
+        assert!(
+            stealing < single,
+            "work stealing ({:?}) should beat the shared queue ({:?}) at {} workers",
+            stealing,
+            single,
+            max_workers
+        );
+    }
+
+    println!("\n=== Results ===");
+    print_banner("[BUG DEMONSTRATED]");
+    println!("The shared queue stops scaling once dispatch is serialized on its lock.");
+    print_banner("[FIXED]");
+    println!(
+        "At {} workers work stealing finished {:.2}x faster.",
+        max_workers,
+        single.as_secs_f64() / stealing.as_secs_f64()
+    );
+}
@@ -5,14 +5,16 @@
 //!
 //! Original bug: https://github.com/MystenLabs/sui/issues/5201
 
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::env;
-use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const QUEUE_CAPACITY: usize = 10; // Small capacity to demonstrate bug quickly
+const DEFAULT_FETCH_LATENCY_MS: u64 = 10;
 
 #[derive(Debug, Clone)]
 struct Certificate {
@@ -20,6 +22,32 @@ struct Certificate {
     parent_id: Option<u64>, // Dependency on parent certificate
 }
 
+/// Wait-for graph snapshot, exported as Graphviz DOT with `--format dot`
+///
+/// Each crate builds on its own, so this is copied into ballista-deadlock-132,
+/// reth-deadlock-10842, sui-blocking-5201, sui-deadlock-335, sui-deadlock-960
+/// and sui-race-4990. Keep the copies identical; reth-deadlock-10842 also
+/// has `remove_edge`.
+#[derive(Debug, Default)]
+pub struct WaitForGraph {
+    edges: Vec<(String, String, String)>,
+}
+
+impl WaitForGraph {
+    fn add_edge(&mut self, from: &str, to: &str, label: &str) {
+        self.edges.push((from.to_string(), to.to_string(), label.to_string()));
+    }
+
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph {} {{\n", name);
+        for (from, to, label) in &self.edges {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", from, to, label));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
 /// Buggy version: Uses bounded synchronous channel
 mod buggy {
     use super::*;
@@ -29,16 +57,60 @@ mod buggy {
         sender: SyncSender<Certificate>,
         receiver: Arc<Mutex<Receiver<Certificate>>>,
         processed: Arc<Mutex<Vec<u64>>>,
+        // (cert, parent) pairs currently sitting in the queue / turned away
+        queued: Mutex<Vec<(u64, u64)>>,
+        rejected: Mutex<Vec<(u64, u64)>>,
+        stopped: AtomicBool,
+        fetch_latency: Duration,
     }
 
     impl CertificateWaiter {
-        pub fn new() -> Self {
-            let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        pub fn new(fetch_latency: Duration) -> Self {
+            Self::with_capacity(QUEUE_CAPACITY, fetch_latency)
+        }
+
+        pub fn with_capacity(capacity: usize, fetch_latency: Duration) -> Self {
+            let (sender, receiver) = mpsc::sync_channel(capacity);
             Self {
                 sender,
                 receiver: Arc::new(Mutex::new(receiver)),
                 processed: Arc::new(Mutex::new(Vec::new())),
+                queued: Mutex::new(Vec::new()),
+                rejected: Mutex::new(Vec::new()),
+                stopped: AtomicBool::new(false),
+                fetch_latency,
+            }
+        }
+
+        /// Number of times a cert was turned away because the queue was full
+        pub fn rejections(&self) -> usize {
+            self.rejected.lock().unwrap().len()
+        }
+
+        /// Ask the consumer to exit after its current certificate
+        pub fn stop(&self) {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+
+        /// Snapshot of unprocessed certs and the parents they are stuck on
+        pub fn wait_for_graph(&self) -> WaitForGraph {
+            let processed = self.processed.lock().unwrap();
+            let mut graph = WaitForGraph::default();
+            let mut seen = Vec::new();
+
+            for (edges, label) in [
+                (&self.queued, "waits for (queued)"),
+                (&self.rejected, "waits for (rejected: queue full)"),
+            ] {
+                for &(cert, parent) in edges.lock().unwrap().iter() {
+                    if processed.contains(&cert) || seen.contains(&cert) {
+                        continue;
+                    }
+                    seen.push(cert);
+                    graph.add_edge(&format!("cert {}", cert), &format!("cert {}", parent), label);
+                }
             }
+            graph
         }
 
         /// Process a certificate - may trigger recursive fetching
@@ -64,6 +136,7 @@ mod buggy {
                     // 3. New certs can't be queued -> blocked forever
                     match self.sender.try_send(cert.clone()) {
                         Ok(_) => {
+                            self.queued.lock().unwrap().push((cert.id, parent_id));
                             // Simulate fetching parent (triggers recursive processing)
                             self.fetch_parent(parent_id);
                         }
@@ -72,6 +145,7 @@ mod buggy {
                                 "[BUGGY] QUEUE FULL! Cannot queue cert {} - DEADLOCK!",
                                 cert.id
                             );
+                            self.rejected.lock().unwrap().push((cert.id, parent_id));
                             return false;
                         }
                         Err(TrySendError::Disconnected(_)) => return false,
@@ -92,7 +166,7 @@ mod buggy {
             println!("[BUGGY] Fetching parent certificate {}", parent_id);
 
             // Simulate network delay
-            thread::sleep(Duration::from_millis(10));
+            thread::sleep(self.fetch_latency);
 
             // Parent certificate may also have a parent (recursive!)
             let grandparent = if parent_id > 1 {
@@ -112,7 +186,7 @@ mod buggy {
 
         /// Consumer thread - processes waiting certificates
         pub fn run_consumer(&self) {
-            loop {
+            while !self.stopped.load(Ordering::SeqCst) {
                 let cert = {
                     let receiver = self.receiver.lock().unwrap();
                     match receiver.recv_timeout(Duration::from_millis(100)) {
@@ -120,6 +194,12 @@ mod buggy {
                         Err(_) => break,
                     }
                 };
+                {
+                    let mut queued = self.queued.lock().unwrap();
+                    if let Some(pos) = queued.iter().position(|&(id, _)| id == cert.id) {
+                        queued.remove(pos);
+                    }
+                }
 
                 // Re-process after parent should be ready
                 thread::sleep(Duration::from_millis(50)); // Slow consumer
@@ -138,17 +218,32 @@ mod fixed {
         queue: Arc<Mutex<VecDeque<Certificate>>>,
         processed: Arc<Mutex<Vec<u64>>>,
         pending_count: Arc<Mutex<usize>>,
+        max_queue_depth: Mutex<usize>,
+        fetch_latency: Duration,
     }
 
     impl CertificateWaiter {
-        pub fn new() -> Self {
+        pub fn new(fetch_latency: Duration) -> Self {
             Self {
                 queue: Arc::new(Mutex::new(VecDeque::new())),
                 processed: Arc::new(Mutex::new(Vec::new())),
                 pending_count: Arc::new(Mutex::new(0)),
+                max_queue_depth: Mutex::new(0),
+                fetch_latency,
             }
         }
 
+        /// Deepest the unbounded queue got during the run
+        pub fn max_queue_depth(&self) -> usize {
+            *self.max_queue_depth.lock().unwrap()
+        }
+
+        /// Whether every cert in `ids` has been processed
+        pub fn all_processed(&self, ids: impl IntoIterator<Item = u64>) -> bool {
+            let processed = self.processed.lock().unwrap();
+            ids.into_iter().all(|id| processed.contains(&id))
+        }
+
         pub fn process_certificate(&self, cert: Certificate) -> bool {
             println!("[FIXED] Processing certificate {}", cert.id);
 
@@ -168,6 +263,8 @@ mod fixed {
                         queue.push_back(cert.clone());
                         let mut count = self.pending_count.lock().unwrap();
                         *count += 1;
+                        let mut max_depth = self.max_queue_depth.lock().unwrap();
+                        *max_depth = (*max_depth).max(queue.len());
                         println!("[FIXED] Queue size: {}", queue.len());
                     }
 
@@ -185,7 +282,7 @@ mod fixed {
 
         fn fetch_parent(&self, parent_id: u64) {
             println!("[FIXED] Fetching parent certificate {}", parent_id);
-            thread::sleep(Duration::from_millis(10));
+            thread::sleep(self.fetch_latency);
 
             let grandparent = if parent_id > 1 {
                 Some(parent_id - 1)
@@ -210,6 +307,9 @@ mod fixed {
 
                 match cert {
                     Some(c) => {
+                        // The popped cert is no longer pending; it is counted
+                        // again if processing re-queues it
+                        *self.pending_count.lock().unwrap() -= 1;
                         thread::sleep(Duration::from_millis(50));
                         self.process_certificate(c);
                     }
@@ -226,23 +326,205 @@ mod fixed {
     }
 }
 
+/// Fixed version without recursion: walks the parent chain with an explicit
+/// worklist, so chain length is bounded by `max_depth` rather than the
+/// thread's stack
+mod fixed_iterative {
+    use super::*;
+
+    #[derive(Debug)]
+    pub enum FetchError {
+        /// The unresolved chain under `cert` grew past the configured limit
+        MaxDepthExceeded { cert: u64, depth: usize },
+    }
+
+    pub struct CertificateWaiter {
+        processed: Mutex<HashSet<u64>>,
+        max_depth: usize,
+        fetch_latency: Duration,
+    }
+
+    impl CertificateWaiter {
+        pub fn new(max_depth: usize, fetch_latency: Duration) -> Self {
+            Self {
+                processed: Mutex::new(HashSet::new()),
+                max_depth,
+                fetch_latency,
+            }
+        }
+
+        /// Process `cert` and every missing ancestor, oldest first. Returns
+        /// the deepest the worklist got.
+        pub fn process_certificate(&self, cert: Certificate) -> Result<usize, FetchError> {
+            let mut worklist = vec![cert];
+            let mut max_seen = 1;
+
+            while let Some(top) = worklist.last() {
+                let missing_parent = top
+                    .parent_id
+                    .filter(|parent| !self.processed.lock().unwrap().contains(parent));
+
+                match missing_parent {
+                    Some(parent_id) => {
+                        if worklist.len() >= self.max_depth {
+                            return Err(FetchError::MaxDepthExceeded {
+                                cert: worklist[0].id,
+                                depth: worklist.len(),
+                            });
+                        }
+                        worklist.push(self.fetch_parent(parent_id));
+                        max_seen = max_seen.max(worklist.len());
+                    }
+                    None => {
+                        let ready = worklist.pop().unwrap();
+                        self.processed.lock().unwrap().insert(ready.id);
+                    }
+                }
+            }
+            Ok(max_seen)
+        }
+
+        /// Fetch a parent without processing it; the caller pushes it onto
+        /// the worklist instead of recursing
+        fn fetch_parent(&self, parent_id: u64) -> Certificate {
+            if !self.fetch_latency.is_zero() {
+                thread::sleep(self.fetch_latency);
+            }
+            Certificate {
+                id: parent_id,
+                parent_id: if parent_id > 1 { Some(parent_id - 1) } else { None },
+            }
+        }
+
+        pub fn processed_count(&self) -> usize {
+            self.processed.lock().unwrap().len()
+        }
+    }
+}
+
+/// Consumer shutdown behaviour, independent of the bounded-queue deadlock:
+/// what happens to certs still sitting in the channel when the producer goes
+/// away and shutdown is requested
+mod shutdown {
+    use super::*;
+
+    fn process(cert: &Certificate, processed: &mut Vec<u64>, prefix: &str) {
+        thread::sleep(Duration::from_millis(20));
+        println!("{} Consumer processed cert {}", prefix, cert.id);
+        processed.push(cert.id);
+    }
+
+    /// BUG: checks the stop flag before every receive and bails out on any
+    /// receive error, abandoning whatever is still queued
+    pub fn buggy_consume(receiver: Receiver<Certificate>, stopped: &AtomicBool) -> Vec<u64> {
+        let mut processed = Vec::new();
+        while !stopped.load(Ordering::SeqCst) {
+            match receiver.recv_timeout(Duration::from_millis(100)) {
+                Ok(cert) => process(&cert, &mut processed, "[BUGGY]"),
+                Err(_) => break,
+            }
+        }
+        let abandoned = receiver.try_iter().count();
+        println!("[BUGGY] Consumer exited with {} certs still queued", abandoned);
+        processed
+    }
+
+    /// FIX: a stop request only ends the wait for new certs. Anything already
+    /// queued is drained, and the loop exits once the sender is gone.
+    pub fn draining_consume(receiver: Receiver<Certificate>, stopped: &AtomicBool) -> Vec<u64> {
+        let mut processed = Vec::new();
+        loop {
+            match receiver.recv_timeout(Duration::from_millis(100)) {
+                Ok(cert) => process(&cert, &mut processed, "[FIXED]"),
+                Err(RecvTimeoutError::Timeout) if !stopped.load(Ordering::SeqCst) => continue,
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        println!("[FIXED] Consumer drained the queue before exiting");
+        processed
+    }
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let use_csv = args.iter().any(|arg| arg == "--csv");
+    let format_dot = args
+        .iter()
+        .position(|arg| arg == "--format")
+        .and_then(|i| args.get(i + 1))
+        .is_some_and(|format| format == "dot");
+    let parse_flag = |name: &str| -> Option<u64> {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.parse().ok())
+    };
+    let fetch_latency_ms = parse_flag("--fetch-latency-ms");
+    let fetch_latency = Duration::from_millis(fetch_latency_ms.unwrap_or(DEFAULT_FETCH_LATENCY_MS));
 
     println!("=== Sui Issue #5201: Bounded Queue Deadlock ===\n");
 
-    if use_fixed {
+    if let Some(spec) = args.iter().position(|arg| arg == "--sweep").and_then(|i| args.get(i + 1)) {
+        let sweep = match Sweep::parse(spec) {
+            Some(sweep) if sweep.param == "capacity" => sweep,
+            _ => {
+                eprintln!("Invalid --sweep '{}': expected capacity=start..end[:step]", spec);
+                std::process::exit(2);
+            }
+        };
+        let chain_len = parse_flag("--chain-len").unwrap_or(DEFAULT_CHAIN_LEN);
+        println!("Sweeping BUGGY queue capacity {}..={} (chain of {})...\n", sweep.start, sweep.end, chain_len);
+        run_sweep_test(&sweep, chain_len, use_csv);
+    } else if args.iter().any(|arg| arg == "--shutdown") {
+        println!(
+            "Running {} consumer shutdown (producer dropped with certs queued)...\n",
+            if use_fixed { "FIXED" } else { "BUGGY" }
+        );
+        run_shutdown_test(use_fixed);
+    } else if args.iter().any(|arg| arg == "--iterative") {
+        let chain_len = parse_flag("--chain-len").unwrap_or(DEFAULT_CHAIN_LEN);
+        let max_depth = parse_flag("--max-depth").map_or(DEFAULT_MAX_DEPTH, |d| d as usize);
+        println!("Running FIXED_ITERATIVE version (worklist, no recursion)...\n");
+        // Long chains with the default 10ms fetch would take minutes, so the
+        // iterative run only sleeps when a latency is asked for explicitly
+        let latency = Duration::from_millis(fetch_latency_ms.unwrap_or(0));
+        run_iterative_test(chain_len, max_depth, latency);
+    } else if use_fixed {
         println!("Running FIXED version (unbounded queue)...\n");
-        run_fixed_test();
+        run_fixed_test(fetch_latency);
     } else {
         println!("Running BUGGY version (bounded queue, capacity={})...\n", QUEUE_CAPACITY);
-        run_buggy_test();
+        run_buggy_test(format_dot, fetch_latency);
     }
 }
 
-fn run_buggy_test() {
-    let waiter = Arc::new(buggy::CertificateWaiter::new());
+fn run_buggy_test(format_dot: bool, fetch_latency: Duration) {
+    let waiter = Arc::new(buggy::CertificateWaiter::new(fetch_latency));
     let waiter_consumer = Arc::clone(&waiter);
 
     // Start consumer
@@ -270,17 +552,29 @@ fn run_buggy_test() {
 
     // Wait a bit for processing
     thread::sleep(Duration::from_secs(1));
-    drop(waiter); // Close the channel
+    let wait_for = waiter.wait_for_graph();
+
+    // The consumer keeps re-queuing stalled certs, so stop it explicitly
+    waiter.stop();
     let _ = consumer_handle.join();
 
-    println!("\n[BUG DEMONSTRATED]");
+    println!();
+    print_banner("[BUG DEMONSTRATED]");
     println!("The bounded queue filled up, causing some certificates to be rejected.");
     println!("In the real system, this causes deadlock as the entire chain stalls.");
+    if format_dot {
+        println!("\n=== Wait-For Graph (DOT) ===");
+        print!("{}", wait_for.to_dot("sui_5201"));
+    }
+    println!("\n=== Metrics ===");
+    println!("fetch_latency_ms: {}", fetch_latency.as_millis());
+    println!("queue_capacity: {}", QUEUE_CAPACITY);
+    println!("rejections: {}", waiter.rejections());
     println!("\nRun with --fixed to see unbounded queue handling.");
 }
 
-fn run_fixed_test() {
-    let waiter = Arc::new(fixed::CertificateWaiter::new());
+fn run_fixed_test(fetch_latency: Duration) {
+    let waiter = Arc::new(fixed::CertificateWaiter::new(fetch_latency));
     let waiter_consumer = Arc::clone(&waiter);
 
     let consumer_handle = thread::spawn(move || {
@@ -303,7 +597,230 @@ fn run_fixed_test() {
     thread::sleep(Duration::from_secs(2));
     let _ = consumer_handle.join();
 
-    println!("\n[FIXED]");
+    assert!(
+        waiter.all_processed(1..20),
+        "fixed waiter should drain the whole chain"
+    );
+
+    println!();
+    print_banner("[FIXED]");
     println!("Unbounded queue allows all certificates to be queued.");
     println!("Processing continues without deadlock.");
+    println!("\n=== Metrics ===");
+    println!("fetch_latency_ms: {}", fetch_latency.as_millis());
+    println!("max_queue_depth: {}", waiter.max_queue_depth());
+}
+
+const SHUTDOWN_CERTS: u64 = 6;
+
+fn run_shutdown_test(use_fixed: bool) {
+    let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    println!("Enqueuing {} certs, then dropping the producer...\n", SHUTDOWN_CERTS);
+    for id in 1..=SHUTDOWN_CERTS {
+        sender.send(Certificate { id, parent_id: None }).unwrap();
+    }
+
+    let consumer_stopped = Arc::clone(&stopped);
+    let consumer = thread::spawn(move || {
+        if use_fixed {
+            shutdown::draining_consume(receiver, &consumer_stopped)
+        } else {
+            shutdown::buggy_consume(receiver, &consumer_stopped)
+        }
+    });
+
+    // Shutdown arrives shortly after the consumer starts working
+    thread::sleep(Duration::from_millis(30));
+    drop(sender);
+    stopped.store(true, Ordering::SeqCst);
+
+    let processed = consumer.join().unwrap();
+    let lost = SHUTDOWN_CERTS as usize - processed.len();
+
+    println!("\n=== Results ===");
+    println!("Certs enqueued: {}", SHUTDOWN_CERTS);
+    println!("Certs processed: {}", processed.len());
+    println!("Certs lost on shutdown: {}", lost);
+
+    if use_fixed {
+        assert_eq!(processed, (1..=SHUTDOWN_CERTS).collect::<Vec<_>>());
+        println!();
+        print_banner("[FIXED]");
+        println!("Consumer drained every queued cert before stopping.");
+    } else {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("Consumer stopped as soon as shutdown was requested,");
+        println!("silently dropping certs that were already queued.");
+        println!("\nRun with --shutdown --fixed to see the draining consumer.");
+    }
+}
+
+/// Default top of the chain for `--iterative`, matching the producer's 19
+const DEFAULT_CHAIN_LEN: u64 = 19;
+/// Worklist limit for `--iterative` unless `--max-depth` is given
+const DEFAULT_MAX_DEPTH: usize = 1_000_000;
+
+fn run_iterative_test(chain_len: u64, max_depth: usize, fetch_latency: Duration) {
+    let waiter = fixed_iterative::CertificateWaiter::new(max_depth, fetch_latency);
+
+    println!("Chain length: {} (cert {} depends on {} ... down to 1)", chain_len, chain_len, chain_len.saturating_sub(1));
+    println!("Max depth: {}\n", max_depth);
+
+    let start = Instant::now();
+    let result = waiter.process_certificate(Certificate {
+        id: chain_len,
+        parent_id: if chain_len > 1 { Some(chain_len - 1) } else { None },
+    });
+    let elapsed = start.elapsed();
+
+    println!("=== Results ===");
+    match result {
+        Ok(depth) => {
+            assert_eq!(waiter.processed_count() as u64, chain_len);
+            println!("Processed {} certificates in {:?}", waiter.processed_count(), elapsed);
+            println!("Deepest worklist: {}", depth);
+            println!();
+            print_banner("[FIXED]");
+            println!("The whole chain was resolved with an explicit worklist.");
+            println!("Stack usage is constant regardless of chain length.");
+        }
+        Err(fixed_iterative::FetchError::MaxDepthExceeded { cert, depth }) => {
+            assert_eq!(depth, max_depth);
+            println!("Cert {} exceeded max depth {} - returned an error", cert, depth);
+            println!();
+            print_banner("[NOTE]");
+            println!("The chain is deeper than --max-depth allows.");
+            println!("The iterative waiter refuses it instead of overflowing the stack.");
+        }
+    }
+}
+
+/// Range for `--sweep param=start..end:step`, inclusive of `end`
+///
+/// Each crate builds on its own, so this is copied into ballista-deadlock-132,
+/// sui-blocking-5201 and surrealdb-race-5042. Keep the copies identical.
+struct Sweep {
+    param: String,
+    start: u64,
+    end: u64,
+    step: u64,
+}
+
+impl Sweep {
+    fn parse(spec: &str) -> Option<Self> {
+        let (param, range) = spec.split_once('=')?;
+        let (range, step) = match range.split_once(':') {
+            Some((range, step)) => (range, step.parse().ok()?),
+            None => (range, 1),
+        };
+        let (start, end) = range.split_once("..")?;
+        let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+        (step > 0 && start <= end).then(|| Self { param: param.to_string(), start, end, step })
+    }
+
+    fn values(&self) -> impl Iterator<Item = u64> {
+        (self.start..=self.end).step_by(self.step as usize)
+    }
+}
+
+/// Resolve one chain on a buggy waiter with no consumer draining the queue.
+/// Every cert above 1 waits in the queue for its parent, so the chain needs
+/// `chain_len - 1` slots. Returns how many certs were turned away.
+fn run_capacity_probe(capacity: usize, chain_len: u64) -> usize {
+    let waiter = buggy::CertificateWaiter::with_capacity(capacity, Duration::ZERO);
+    waiter.process_certificate(Certificate {
+        id: chain_len,
+        parent_id: if chain_len > 1 { Some(chain_len - 1) } else { None },
+    });
+    waiter.rejections()
+}
+
+/// Print `--csv` output: a header, then one line per row. Fields containing a
+/// comma, quote or newline are quoted.
+///
+/// Each crate builds on its own, so this and `csv_field` are copied into
+/// ballista-deadlock-132, sui-blocking-5201, reth-race-12287 and
+/// surrealdb-race-5042. Keep the copies identical.
+fn print_csv(header: &[&str], rows: &[Vec<String>]) {
+    println!("\n=== CSV ===");
+    println!("{}", header.join(","));
+    for row in rows {
+        assert_eq!(row.len(), header.len(), "CSV row width must match the header");
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        println!("{}", fields.join(","));
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn run_sweep_test(sweep: &Sweep, chain_len: u64, use_csv: bool) {
+    let waiting = chain_len.saturating_sub(1);
+    let mut rows = Vec::new();
+    for capacity in sweep.values() {
+        let started = Instant::now();
+        let rejections = run_capacity_probe(capacity as usize, chain_len);
+        let elapsed = started.elapsed();
+        let deadlocked = rejections > 0;
+        // With no consumer the outcome depends only on the slot count
+        assert_eq!(
+            deadlocked,
+            capacity < waiting,
+            "capacity {} with {} waiting certs should {}deadlock",
+            capacity,
+            waiting,
+            if capacity < waiting { "" } else { "not " }
+        );
+        rows.push((capacity, rejections, deadlocked, elapsed));
+    }
+
+    println!();
+    for &(capacity, _, deadlocked, _) in &rows {
+        println!("[SWEEP] capacity={} {}", capacity, if deadlocked { "deadlock" } else { "ok" });
+    }
+
+    if use_csv {
+        let mut csv = Vec::new();
+        for &(capacity, rejections, deadlocked, elapsed) in &rows {
+            csv.push(vec![
+                capacity.to_string(),
+                deadlocked.to_string(),
+                elapsed.as_millis().to_string(),
+                chain_len.to_string(),
+                rejections.to_string(),
+            ]);
+        }
+        print_csv(&["capacity", "bug_observed", "elapsed_ms", "chain_len", "rejections"], &csv);
+    } else {
+        println!("\n=== Sweep (CSV) ===");
+        println!("capacity,chain_len,rejections,outcome");
+        for &(capacity, rejections, deadlocked, _) in &rows {
+            println!("{},{},{},{}", capacity, chain_len, rejections, if deadlocked { "deadlock" } else { "ok" });
+        }
+    }
+
+    println!("\n=== Results ===");
+    match rows.iter().find(|&&(_, _, deadlocked, _)| !deadlocked) {
+        Some(&(capacity, _, _, _)) => {
+            println!("Smallest capacity without deadlock: {}", capacity);
+            println!();
+            print_banner("[BUG DEMONSTRATED]");
+            println!("A chain of {} needs {} queue slots while parents are fetched.", chain_len, waiting);
+            println!("Any bounded queue smaller than the deepest chain can deadlock.");
+        }
+        None => {
+            println!("Every capacity in the sweep deadlocked.");
+            println!();
+            print_banner("[BUG DEMONSTRATED]");
+            println!("A chain of {} needs {} queue slots; sweep past it to see the threshold.", chain_len, waiting);
+        }
+    }
 }
@@ -8,6 +8,8 @@
 
 use std::collections::HashMap;
 use std::env;
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -24,6 +26,9 @@ pub struct BatchNotifier {
     next_expected: Mutex<SequenceNumber>,
     /// Condition variable for waiting on sequence numbers
     notify: Condvar,
+    /// Times a waiter was woken before its timeout, by a notification or not
+    #[cfg(test)]
+    wakeups: AtomicUsize,
 }
 
 impl BatchNotifier {
@@ -32,6 +37,8 @@ impl BatchNotifier {
             notified_sequences: Mutex::new(Vec::new()),
             next_expected: Mutex::new(1),
             notify: Condvar::new(),
+            #[cfg(test)]
+            wakeups: AtomicUsize::new(0),
         }
     }
 
@@ -43,55 +50,105 @@ impl BatchNotifier {
         self.notify.notify_all();
     }
 
+    /// First sequence up to target that has not been notified
+    /// Returns None if the chain is contiguous through target
+    fn first_gap(&self, target: SequenceNumber) -> Option<SequenceNumber> {
+        let sequences = self.notified_sequences.lock().unwrap();
+        self.gap_in(&sequences, target)
+    }
+
+    fn gap_in(&self, sequences: &[SequenceNumber], target: SequenceNumber) -> Option<SequenceNumber> {
+        let next_expected = self.next_expected.lock().unwrap();
+
+        let mut current = *next_expected;
+        for &seq in sequences.iter() {
+            if seq == current {
+                current += 1;
+            }
+        }
+
+        (current <= target).then_some(current)
+    }
+
     /// Wait for contiguous sequences up to target
     /// Returns false if there's a gap in the sequence
+    ///
+    /// The chain is re-checked after every wakeup, under the same lock the
+    /// wait releases, so a spurious wakeup goes back to waiting and a
+    /// notification between the check and the wait is not missed.
     fn wait_for_contiguous(&self, target: SequenceNumber, timeout: Duration) -> bool {
         let start = std::time::Instant::now();
+        let mut sequences = self.notified_sequences.lock().unwrap();
 
         loop {
-            {
-                let sequences = self.notified_sequences.lock().unwrap();
-                let next_expected = self.next_expected.lock().unwrap();
-
-                // Check if we have contiguous sequences up to target
-                let mut current = *next_expected;
-                for &seq in sequences.iter() {
-                    if seq == current {
-                        current += 1;
-                    }
-                }
-
-                if current > target {
-                    return true;
-                }
+            if self.gap_in(&sequences, target).is_none() {
+                return true;
             }
 
-            if start.elapsed() >= timeout {
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
                 return false;
             }
 
-            let sequences = self.notified_sequences.lock().unwrap();
-            let remaining = timeout.saturating_sub(start.elapsed());
-            let _ = self.notify.wait_timeout(sequences, remaining);
+            #[cfg_attr(not(test), allow(unused_variables))]
+            let (guard, wait) = self.notify.wait_timeout(sequences, remaining).unwrap();
+            sequences = guard;
+            #[cfg(test)]
+            if !wait.timed_out() {
+                self.wakeups.fetch_add(1, Ordering::SeqCst);
+            }
         }
     }
 
+    /// Wake every waiter without notifying a sequence, as a spurious
+    /// wakeup would
+    #[cfg(test)]
+    fn wake_spuriously(&self) {
+        let _sequences = self.notified_sequences.lock().unwrap();
+        self.notify.notify_all();
+    }
+
+    #[cfg(test)]
+    fn wakeups(&self) -> usize {
+        self.wakeups.load(Ordering::SeqCst)
+    }
+
     fn get_notified(&self) -> Vec<SequenceNumber> {
         self.notified_sequences.lock().unwrap().clone()
     }
+
+    /// Notifier contents as a JSON object body
+    fn dump_state(&self) -> String {
+        let sequences: Vec<String> = self.get_notified().iter().map(|s| s.to_string()).collect();
+        format!(
+            "\"notified_sequences\": [{}], \"next_expected\": {}",
+            sequences.join(", "),
+            *self.next_expected.lock().unwrap()
+        )
+    }
 }
 
 /// Database for storing committed transactions
 pub struct Database {
     committed: Mutex<HashMap<TxDigest, SequenceNumber>>,
     should_fail: Mutex<bool>,
+    /// Sequences whose commit fails regardless of `should_fail`
+    fail_seqs: Mutex<Vec<SequenceNumber>>,
+    /// How long each commit takes before it succeeds or fails
+    commit_latency: Duration,
 }
 
 impl Database {
     fn new() -> Self {
+        Self::with_commit_latency(Duration::ZERO)
+    }
+
+    fn with_commit_latency(commit_latency: Duration) -> Self {
         Self {
             committed: Mutex::new(HashMap::new()),
             should_fail: Mutex::new(false),
+            fail_seqs: Mutex::new(Vec::new()),
+            commit_latency,
         }
     }
 
@@ -99,8 +156,29 @@ impl Database {
         *self.should_fail.lock().unwrap() = fail;
     }
 
+    /// Fail the commit of these sequences, whichever thread commits them.
+    /// Unlike `set_fail`, this is safe with concurrent committers.
+    fn fail_sequences(&self, seqs: &[SequenceNumber]) {
+        *self.fail_seqs.lock().unwrap() = seqs.to_vec();
+    }
+
+    /// Committed transactions as a JSON object body, sorted by sequence
+    fn dump_state(&self) -> String {
+        let committed = self.committed.lock().unwrap();
+        let mut entries: Vec<_> = committed.iter().collect();
+        entries.sort_by_key(|(_, seq)| **seq);
+        let entries: Vec<String> = entries
+            .into_iter()
+            .map(|(digest, seq)| format!("\"{}\": {}", digest, seq))
+            .collect();
+        format!("\"committed\": {{{}}}", entries.join(", "))
+    }
+
     fn commit(&self, digest: &str, seq: SequenceNumber) -> Result<(), &'static str> {
-        if *self.should_fail.lock().unwrap() {
+        if !self.commit_latency.is_zero() {
+            thread::sleep(self.commit_latency);
+        }
+        if *self.should_fail.lock().unwrap() || self.fail_seqs.lock().unwrap().contains(&seq) {
             return Err("Database commit failed");
         }
         let mut committed = self.committed.lock().unwrap();
@@ -213,107 +291,255 @@ mod fixed {
     }
 }
 
+// The waiter thread blocks on the notifier while the authority commits, so
+// the notifier and the database it commits to must both stay `Send + Sync`
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<BatchNotifier>();
+    assert_send_sync::<Database>();
+};
+
+/// Final notifier and database contents as one JSON object
+fn dump_state(notifier: &BatchNotifier, database: &Database) -> String {
+    format!("{{{}, {}}}", notifier.dump_state(), database.dump_state())
+}
+
+/// Sequence positions whose commit fails unless `--fail-seqs` says otherwise
+const DEFAULT_FAIL_SEQS: [SequenceNumber; 1] = [2];
+
+/// Parse `--fail-seqs 2,4`, exiting with a usage error on bad input
+fn parse_fail_seqs(args: &[String]) -> Vec<SequenceNumber> {
+    let Some(list) = args.iter().position(|arg| arg == "--fail-seqs").and_then(|i| args.get(i + 1)) else {
+        return DEFAULT_FAIL_SEQS.to_vec();
+    };
+    let mut seqs: Vec<SequenceNumber> = list
+        .split(',')
+        .map(|v| match v.trim().parse() {
+            Ok(seq) if seq > 0 => seq,
+            _ => {
+                eprintln!("Invalid --fail-seqs '{}': expected positive sequence numbers like 2,4", list);
+                std::process::exit(2);
+            }
+        })
+        .collect();
+    seqs.sort();
+    seqs.dedup();
+    seqs
+}
+
+/// Commit enough transactions that the last one succeeds after every gap
+fn commit_count(fail_seqs: &[SequenceNumber]) -> SequenceNumber {
+    fail_seqs.iter().max().map_or(3, |&max| (max + 1).max(3))
+}
+
+/// Commit tx1..=txN, failing the database commit at each of `fail_seqs`
+fn commit_all(
+    commit: impl Fn(&str) -> Result<SequenceNumber, &'static str>,
+    database: &Database,
+    fail_seqs: &[SequenceNumber],
+) {
+    for seq in 1..=commit_count(fail_seqs) {
+        database.set_fail(fail_seqs.contains(&seq));
+        let _ = commit(&format!("tx{}", seq));
+    }
+    database.set_fail(false);
+}
+
+fn describe_scenario(fail_seqs: &[SequenceNumber]) -> String {
+    (1..=commit_count(fail_seqs))
+        .map(|seq| {
+            let outcome = if fail_seqs.contains(&seq) { "fail" } else { "success" };
+            format!("tx{} ({})", seq, outcome)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse a numeric flag such as `--committers 5`, exiting with a usage error
+/// on bad input
+fn parse_count(args: &[String], flag: &str, default: u64) -> u64 {
+    let Some(value) = args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1)) else {
+        return default;
+    };
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("Invalid {} '{}': expected a number", flag, value);
+        std::process::exit(2);
+    })
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let dump = args.iter().any(|arg| arg == "--dump-state");
+    let fail_seqs = parse_fail_seqs(&args);
 
     println!("=== Sui PR #5868: Batch Notifier Missing Notification ===\n");
 
-    if use_fixed {
+    if args.iter().any(|arg| arg == "--committers") {
+        let committers = parse_count(&args, "--committers", DEFAULT_COMMITTERS);
+        let batch_size = parse_count(&args, "--batch-size", DEFAULT_BATCH_SIZE);
+        let latency = Duration::from_millis(parse_count(&args, "--commit-latency-ms", DEFAULT_COMMIT_LATENCY_MS));
+        let max_seq = committers * batch_size;
+        if max_seq == 0 || fail_seqs.iter().any(|&seq| seq > max_seq) {
+            eprintln!(
+                "--fail-seqs {:?} must fall within the {} sequences assigned to {} committers x {}",
+                fail_seqs, max_seq, committers, batch_size
+            );
+            std::process::exit(2);
+        }
+        let variant = if use_fixed { "FIXED" } else { "BUGGY" };
+        println!("Running {} version with {} concurrent committers...\n", variant, committers);
+        run_concurrent_test(use_fixed, committers, batch_size, latency, &fail_seqs);
+    } else if use_fixed {
         println!("Running FIXED version (always notify)...\n");
-        run_fixed_test();
+        run_fixed_test(dump, &fail_seqs);
     } else {
         println!("Running BUGGY version (missing notification on failure)...\n");
-        run_buggy_test();
+        run_buggy_test(dump, &fail_seqs);
     }
 }
 
-fn run_buggy_test() {
+fn run_buggy_test(dump: bool, fail_seqs: &[SequenceNumber]) {
     let notifier = Arc::new(BatchNotifier::new());
     let database = Arc::new(Database::new());
     let authority = Arc::new(buggy::Authority::new(
         Arc::clone(&notifier),
         Arc::clone(&database),
     ));
+    let target = commit_count(fail_seqs);
 
-    println!("Scenario: Commit tx1 (success), tx2 (fail), tx3 (success)");
+    println!("Scenario: Commit {}", describe_scenario(fail_seqs));
     println!("Problem: Gap in sequence chain blocks contiguous consumption\n");
 
-    // First commit succeeds
-    let _ = authority.commit_certificate("tx1");
-
-    // Make database fail for next commit
-    database.set_fail(true);
+    // Failed commits get a sequence number but are never notified
+    commit_all(|digest| authority.commit_certificate(digest), &database, fail_seqs);
 
-    // Second commit fails - sequence 2 is assigned but not notified!
-    let _ = authority.commit_certificate("tx2");
+    let notified = notifier.get_notified();
+    println!("\nNotified sequences: {:?}", notified);
+    println!("(Missing sequences {:?} create gaps!)\n", fail_seqs);
 
-    // Re-enable database
-    database.set_fail(false);
-
-    // Third commit succeeds
-    let _ = authority.commit_certificate("tx3");
+    // Guard the reproduction: if the buggy path ever starts notifying failed
+    // commits, this demo no longer demonstrates anything.
+    let expected: Vec<SequenceNumber> = (1..=target).filter(|seq| !fail_seqs.contains(seq)).collect();
+    assert_eq!(notified, expected, "buggy authority should skip every failed sequence");
 
-    println!("\nNotified sequences: {:?}", notifier.get_notified());
-    println!("(Missing sequence 2 creates a gap!)\n");
-
-    // Now try to wait for contiguous sequences up to 3
-    println!("Waiting for contiguous sequences 1,2,3 (2 second timeout)...");
+    // Now try to wait for contiguous sequences up to the last commit
+    println!("Waiting for contiguous sequences 1..={} (2 second timeout)...", target);
     let notifier_clone = Arc::clone(&notifier);
     let handle = thread::spawn(move || {
-        notifier_clone.wait_for_contiguous(3, Duration::from_secs(2))
+        notifier_clone.wait_for_contiguous(target, Duration::from_secs(2))
     });
 
     let got_contiguous = handle.join().unwrap();
+    assert!(!got_contiguous, "buggy notifier should not reach a contiguous chain");
+
+    // The wait stalls on the lowest failed sequence; later gaps are behind it
+    let gap = notifier.first_gap(target);
+    assert_eq!(gap, fail_seqs.first().copied(), "first gap should be the lowest failed sequence");
 
     println!("\n=== Results ===");
+    println!("Notified: {:?}", notified);
+    println!("Missing: {:?}", fail_seqs);
+    println!("Contiguous up to {}: {}", target, got_contiguous);
+    if let Some(gap) = gap {
+        println!("First gap: {}", gap);
+    }
     if !got_contiguous {
-        println!("[BUG DEMONSTRATED]");
-        println!("Wait for contiguous sequences timed out!");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        if let Some(gap) = gap {
+            println!("Wait for contiguous sequences timed out, blocked waiting for sequence {}!", gap);
+        }
         println!("\nProblem:");
-        println!("  - tx2 was assigned sequence 2");
-        println!("  - tx2's commit failed");
-        println!("  - Notifier has [1, 3] but missing 2!");
+        for seq in fail_seqs {
+            println!("  - tx{} was assigned sequence {}, its commit failed", seq, seq);
+        }
+        println!("  - Notifier has {:?}, missing {:?}!", notified, fail_seqs);
         println!("  - Can't proceed without contiguous chain");
         println!("\nRun with --fixed to see proper notification.");
     } else {
         println!("Got contiguous sequences (unexpected in buggy version)");
     }
+
+    let state = dump_state(&notifier, &database);
+    let listed: Vec<String> = expected.iter().map(|s| s.to_string()).collect();
+    assert!(
+        state.contains(&format!("\"notified_sequences\": [{}]", listed.join(", "))),
+        "dumped state should show the gaps at {:?}",
+        fail_seqs
+    );
+    if dump {
+        println!("\n=== State (JSON) ===");
+        println!("{}", state);
+    }
 }
 
-fn run_fixed_test() {
+fn run_fixed_test(dump: bool, fail_seqs: &[SequenceNumber]) {
     let notifier = Arc::new(BatchNotifier::new());
     let database = Arc::new(Database::new());
     let authority = Arc::new(fixed::Authority::new(
         Arc::clone(&notifier),
         Arc::clone(&database),
     ));
+    let target = commit_count(fail_seqs);
 
-    println!("Scenario: Commit tx1 (success), tx2 (fail), tx3 (success)");
+    println!("Scenario: Commit {}", describe_scenario(fail_seqs));
     println!("Fix: Always notify sequence, even on failure\n");
 
-    let _ = authority.commit_certificate("tx1");
-
-    database.set_fail(true);
-    let _ = authority.commit_certificate("tx2");
+    commit_all(|digest| authority.commit_certificate(digest), &database, fail_seqs);
 
-    database.set_fail(false);
-    let _ = authority.commit_certificate("tx3");
+    let notified = notifier.get_notified();
+    println!("\nNotified sequences: {:?}", notified);
 
-    println!("\nNotified sequences: {:?}", notifier.get_notified());
+    // Guard the fix: every assigned sequence must be reported, failed or not.
+    assert_eq!(
+        notified,
+        (1..=target).collect::<Vec<_>>(),
+        "fixed authority must notify every assigned sequence"
+    );
 
-    println!("\nWaiting for contiguous sequences 1,2,3...");
+    println!("\nWaiting for contiguous sequences 1..={}...", target);
     let notifier_clone = Arc::clone(&notifier);
     let handle = thread::spawn(move || {
-        notifier_clone.wait_for_contiguous(3, Duration::from_secs(2))
+        notifier_clone.wait_for_contiguous(target, Duration::from_secs(2))
     });
 
     let got_contiguous = handle.join().unwrap();
+    assert!(got_contiguous, "fixed notifier must reach sequence {} without a gap", target);
+    assert_eq!(notifier.first_gap(target), None, "fixed notifier has no gap up to {}", target);
 
     println!("\n=== Results ===");
+    println!("Notified: {:?}", notified);
+    println!("Failed commits bridged: {:?}", fail_seqs);
+    println!("Contiguous up to {}: {}", target, got_contiguous);
     if got_contiguous {
-        println!("[FIXED]");
-        println!("Got contiguous sequences [1, 2, 3]!");
+        println!();
+        print_banner("[FIXED]");
+        println!("Got contiguous sequences {:?}!", notified);
         println!("\nFix: Notify batch notifier even on commit failure");
         println!("  - Sequence numbers are always reported");
         println!("  - No gaps in the sequence chain");
@@ -321,4 +547,208 @@ fn run_fixed_test() {
     } else {
         println!("Unexpected timeout");
     }
+
+    if dump {
+        println!("\n=== State (JSON) ===");
+        println!("{}", dump_state(&notifier, &database));
+    }
+}
+
+const DEFAULT_COMMITTERS: u64 = 5;
+const DEFAULT_BATCH_SIZE: u64 = 4;
+const DEFAULT_COMMIT_LATENCY_MS: u64 = 5;
+
+/// Start `committers` threads that each commit `batch_size` transactions
+/// through `commit`, all at once
+fn spawn_committers<F>(commit: F, committers: u64, batch_size: u64) -> Vec<thread::JoinHandle<()>>
+where
+    F: Fn(&str) -> Result<SequenceNumber, &'static str> + Send + Sync + 'static,
+{
+    let commit = Arc::new(commit);
+    (0..committers)
+        .map(|c| {
+            let commit = Arc::clone(&commit);
+            thread::spawn(move || {
+                for tx in 1..=batch_size {
+                    let _ = commit(&format!("c{}-tx{}", c, tx));
+                }
+            })
+        })
+        .collect()
+}
+
+fn run_concurrent_test(
+    use_fixed: bool,
+    committers: u64,
+    batch_size: u64,
+    latency: Duration,
+    fail_seqs: &[SequenceNumber],
+) {
+    let notifier = Arc::new(BatchNotifier::new());
+    let database = Arc::new(Database::with_commit_latency(latency));
+    database.fail_sequences(fail_seqs);
+    let max_seq = committers * batch_size;
+
+    println!(
+        "Scenario: {} committers x {} txs, {:?} per commit, sequences {:?} fail",
+        committers, batch_size, latency, fail_seqs
+    );
+    println!("A waiter watches for contiguous sequences 1..={} while they commit\n", max_seq);
+
+    // Every commit can take its full latency, and the committers share one
+    // sequence counter, so allow the slowest committer's whole batch
+    let timeout = Duration::from_secs(2) + latency * batch_size as u32;
+    let waiter = {
+        let notifier = Arc::clone(&notifier);
+        thread::spawn(move || notifier.wait_for_contiguous(max_seq, timeout))
+    };
+
+    let handles = if use_fixed {
+        let authority = fixed::Authority::new(Arc::clone(&notifier), Arc::clone(&database));
+        spawn_committers(move |digest| authority.commit_certificate(digest), committers, batch_size)
+    } else {
+        let authority = buggy::Authority::new(Arc::clone(&notifier), Arc::clone(&database));
+        spawn_committers(move |digest| authority.commit_certificate(digest), committers, batch_size)
+    };
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let got_contiguous = waiter.join().unwrap();
+
+    let notified = notifier.get_notified();
+    let gap = notifier.first_gap(max_seq);
+
+    println!("\n=== Results ===");
+    println!("Notified: {} of {} sequences", notified.len(), max_seq);
+    println!("Contiguous up to {}: {}", max_seq, got_contiguous);
+    if let Some(gap) = gap {
+        println!("First gap: {}", gap);
+    }
+
+    println!("\n=== Metrics ===");
+    println!("committers: {}", committers);
+    println!("batch_size: {}", batch_size);
+    println!("commit_latency_ms: {}", latency.as_millis());
+    println!("max_sequence: {}", max_seq);
+    println!("failed_commits: {}", fail_seqs.len());
+    println!("notified_sequences: {}", notified.len());
+
+    if use_fixed {
+        assert!(got_contiguous, "fixed notifier must reach sequence {} with concurrent committers", max_seq);
+        assert_eq!(notified, (1..=max_seq).collect::<Vec<_>>());
+        assert_eq!(gap, None);
+
+        println!();
+        print_banner("[FIXED]");
+        println!("Committers finished in whatever order they were scheduled and {} commits failed,", fail_seqs.len());
+        println!("but every assigned sequence was notified and the waiter reached {}.", max_seq);
+    } else {
+        // Failures are keyed by sequence, so the gap does not depend on timing
+        assert!(!got_contiguous, "buggy notifier should not reach a contiguous chain");
+        assert_eq!(gap, fail_seqs.first().copied(), "first gap should be the lowest failed sequence");
+        assert_eq!(notified.len() as u64, max_seq - fail_seqs.len() as u64);
+
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        if let Some(gap) = gap {
+            println!("The waiter for sequence {} timed out, blocked waiting for sequence {}!", max_seq, gap);
+        }
+        println!("Every later commit succeeded, but none of them can fill the gap.");
+        println!("\nRun with --fixed to see proper notification.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHORT: Duration = Duration::from_millis(50);
+
+    /// Commit tx1, tx2 and tx3 through `commit`, failing the sequences in
+    /// `fail_seqs`, and return each result
+    fn commit_three(
+        commit: impl Fn(&str) -> Result<SequenceNumber, &'static str>,
+        database: &Database,
+        fail_seqs: &[SequenceNumber],
+    ) -> Vec<Result<SequenceNumber, &'static str>> {
+        database.fail_sequences(fail_seqs);
+        ["tx1", "tx2", "tx3"].into_iter().map(commit).collect()
+    }
+
+    #[test]
+    fn fixed_notifies_a_failed_commit() {
+        let notifier = Arc::new(BatchNotifier::new());
+        let database = Arc::new(Database::new());
+        let authority = fixed::Authority::new(Arc::clone(&notifier), Arc::clone(&database));
+
+        let results = commit_three(|d| authority.commit_certificate(d), &database, &[2]);
+
+        assert_eq!(results, [Ok(1), Err("Database commit failed"), Ok(3)]);
+        assert_eq!(notifier.get_notified(), [1, 2, 3]);
+        assert_eq!(notifier.first_gap(3), None);
+        assert!(notifier.wait_for_contiguous(3, SHORT));
+    }
+
+    #[test]
+    fn buggy_leaves_a_gap_at_the_failed_commit() {
+        let notifier = Arc::new(BatchNotifier::new());
+        let database = Arc::new(Database::new());
+        let authority = buggy::Authority::new(Arc::clone(&notifier), Arc::clone(&database));
+
+        let results = commit_three(|d| authority.commit_certificate(d), &database, &[2]);
+
+        assert_eq!(results, [Ok(1), Err("Database commit failed"), Ok(3)]);
+        assert_eq!(notifier.get_notified(), [1, 3]);
+        assert_eq!(notifier.first_gap(3), Some(2));
+        assert!(!notifier.wait_for_contiguous(3, SHORT));
+    }
+
+    /// When the first commit fails, the fixed notifier still notifies it and
+    /// stays contiguous, while the buggy one skips it and leaves a gap at 1
+    #[test]
+    fn failed_first_commit_leaves_gap_only_in_buggy() {
+        let fixed_notifier = Arc::new(BatchNotifier::new());
+        let database = Arc::new(Database::new());
+        let authority = fixed::Authority::new(Arc::clone(&fixed_notifier), Arc::clone(&database));
+        commit_three(|d| authority.commit_certificate(d), &database, &[1]);
+        assert_eq!(fixed_notifier.get_notified(), [1, 2, 3]);
+        assert!(fixed_notifier.wait_for_contiguous(3, SHORT));
+
+        let buggy_notifier = Arc::new(BatchNotifier::new());
+        let database = Arc::new(Database::new());
+        let authority = buggy::Authority::new(Arc::clone(&buggy_notifier), Arc::clone(&database));
+        commit_three(|d| authority.commit_certificate(d), &database, &[1]);
+        assert_eq!(buggy_notifier.get_notified(), [2, 3]);
+        assert_eq!(buggy_notifier.first_gap(3), Some(1));
+        assert!(!buggy_notifier.wait_for_contiguous(3, SHORT));
+    }
+
+    /// Wakeups with sequence 2 still missing are counted and go back to
+    /// waiting; only notifying sequence 2 ends the wait
+    #[test]
+    fn spurious_wakeups_go_back_to_waiting() {
+        let notifier = Arc::new(BatchNotifier::new());
+        notifier.notify_sequence(1);
+        notifier.notify_sequence(3);
+        let waiter = {
+            let notifier = Arc::clone(&notifier);
+            thread::spawn(move || notifier.wait_for_contiguous(3, Duration::from_secs(5)))
+        };
+
+        for _ in 0..5 {
+            let before = notifier.wakeups();
+            let deadline = std::time::Instant::now() + Duration::from_secs(2);
+            // A wakeup sent before the waiter blocks is lost, so resend it
+            while notifier.wakeups() == before {
+                assert!(std::time::Instant::now() < deadline, "the waiter never woke");
+                notifier.wake_spuriously();
+                thread::sleep(Duration::from_millis(1));
+            }
+            assert_eq!(notifier.first_gap(3), Some(2));
+            assert!(!waiter.is_finished(), "a wakeup without sequence 2 ended the wait");
+        }
+
+        notifier.notify_sequence(2);
+        assert!(waiter.join().unwrap(), "notifying the missing sequence must end the wait");
+    }
 }
@@ -10,8 +10,10 @@
 //! blocking I/O inside a sync lock in async context.
 
 use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
 
 // State shared across async tasks
 struct ServerState {
@@ -153,6 +155,130 @@ async fn run_fixed_test() {
     println!("Should be ~500ms (serialized by mutex, but async-friendly)\n");
 }
 
+/// Deadline for one `--structured` batch: the five serialized requests need
+/// ~500ms, so some are still queued on the mutex when it expires
+const SCOPE_DEADLINE: Duration = Duration::from_millis(250);
+
+/// Counts spawned request tasks that have neither finished nor been cancelled
+#[derive(Clone, Default)]
+struct LiveTasks(Arc<AtomicUsize>);
+
+/// Decrements the live count when its task ends, including on abort
+struct LiveTaskGuard(Arc<AtomicUsize>);
+
+impl LiveTasks {
+    fn enter(&self) -> LiveTaskGuard {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        LiveTaskGuard(Arc::clone(&self.0))
+    }
+
+    fn count(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for LiveTaskGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// BUGGY: detached request tasks awaited under a deadline. Dropping the
+/// handles on timeout leaves the remaining requests running.
+async fn run_detached_batch(server: Arc<fixed::Server>, live: &LiveTasks) {
+    let mut handles = vec![];
+    for i in 0..5 {
+        let srv = Arc::clone(&server);
+        let guard = live.enter();
+        handles.push(tokio::spawn(async move {
+            let _guard = guard;
+            srv.handle_request(i).await
+        }));
+    }
+
+    let _ = tokio::time::timeout(SCOPE_DEADLINE, async {
+        for handle in handles {
+            let _ = handle.await;
+        }
+    })
+    .await;
+}
+
+/// FIXED: the JoinSet owns the request tasks, and `shutdown` cancels and
+/// awaits whatever has not finished before the batch returns
+async fn run_joinset_batch(server: Arc<fixed::Server>, live: &LiveTasks) {
+    let mut set = JoinSet::new();
+    for i in 0..5 {
+        let srv = Arc::clone(&server);
+        let guard = live.enter();
+        set.spawn(async move {
+            let _guard = guard;
+            srv.handle_request(i).await
+        });
+    }
+
+    let _ = tokio::time::timeout(SCOPE_DEADLINE, async {
+        while set.join_next().await.is_some() {}
+    })
+    .await;
+    set.shutdown().await;
+}
+
+async fn run_structured_test() {
+    println!("--- STRUCTURED CONCURRENCY (detached handles vs JoinSet) ---\n");
+    println!("5 requests, {:?} batch deadline\n", SCOPE_DEADLINE);
+
+    let detached = LiveTasks::default();
+    run_detached_batch(Arc::new(fixed::Server::new()), &detached).await;
+    let orphans = detached.count();
+    println!("Detached batch returned with {} request tasks still running\n", orphans);
+
+    let structured = LiveTasks::default();
+    run_joinset_batch(Arc::new(fixed::Server::new()), &structured).await;
+    let remaining = structured.count();
+    println!("JoinSet batch returned with {} request tasks still running\n", remaining);
+
+    assert_eq!(remaining, 0, "JoinSet batch must not leave tasks running");
+
+    println!("=== Metrics ===");
+    println!("detached_orphans: {}", orphans);
+    println!("joinset_orphans: {}\n", remaining);
+
+    if orphans > 0 {
+        print_banner("[BUG DEMONSTRATED]");
+        println!("Dropping a JoinHandle detaches the task; the late requests outlived their batch.");
+    } else {
+        print_banner("[NOTE]");
+        println!("Every detached request finished inside the deadline this run.");
+    }
+    print_banner("[FIXED]");
+    println!("JoinSet::shutdown cancelled and awaited the late requests before returning.");
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 #[tokio::main]
 async fn main() {
     let args: Vec<String> = env::args().collect();
@@ -160,6 +286,11 @@ async fn main() {
 
     println!("=== Sui Issue #828: Sync Mutex in Async Context ===\n");
 
+    if args.iter().any(|arg| arg == "--structured") {
+        run_structured_test().await;
+        return;
+    }
+
     if use_fixed {
         run_fixed_test().await;
     } else {
@@ -167,13 +298,145 @@ async fn main() {
     }
 
     if !use_fixed {
-        println!("[BUG DEMONSTRATED]");
+        print_banner("[BUG DEMONSTRATED]");
         println!("Using std::sync::Mutex with blocking operations in async code");
         println!("blocks the entire runtime thread, serializing all tasks.");
         println!("\nRun with --fixed to see proper async mutex behavior.");
     } else {
-        println!("[FIXED]");
+        print_banner("[FIXED]");
         println!("Using tokio::sync::Mutex with async operations");
         println!("allows the runtime to schedule other tasks while waiting.");
     }
 }
+
+/// Timing checks under a paused tokio clock. `start_paused` needs tokio's
+/// `test-util` feature, which is only enabled for tests.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Time each request spends holding the lock, in both versions
+    const REQUEST_TIME: Duration = Duration::from_millis(100);
+    const REQUESTS: u64 = 5;
+    /// Period of the heartbeat task in `heartbeats_during`
+    const HEARTBEAT: Duration = Duration::from_millis(10);
+
+    /// Yield until `completed` reaches `target`, or give up after enough yields
+    /// for every woken request to have been polled
+    async fn settle(completed: &AtomicUsize, target: usize) {
+        for _ in 0..100 {
+            if completed.load(Ordering::SeqCst) >= target {
+                return;
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// FIXED under a paused clock: step virtual time by hand and check exactly
+    /// when each request finishes
+    async fn step_fixed_requests() -> Duration {
+        let server = Arc::new(fixed::Server::new());
+        let completed = Arc::new(AtomicUsize::new(0));
+        let start = tokio::time::Instant::now();
+
+        for i in 0..REQUESTS {
+            let srv = Arc::clone(&server);
+            let completed = Arc::clone(&completed);
+            tokio::spawn(async move {
+                srv.handle_request(i).await;
+                completed.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        // Let every request run up to the lock, so the first one starts its
+        // sleep at t=0 and the rest queue behind it
+        for _ in 0..REQUESTS {
+            tokio::task::yield_now().await;
+        }
+
+        let just_short = REQUEST_TIME - Duration::from_millis(1);
+        for done in 1..=REQUESTS as usize {
+            tokio::time::advance(just_short).await;
+            settle(&completed, done).await;
+            assert_eq!(completed.load(Ordering::SeqCst), done - 1, "request {} finished early", done);
+
+            tokio::time::advance(REQUEST_TIME - just_short).await;
+            settle(&completed, done).await;
+            assert_eq!(completed.load(Ordering::SeqCst), done, "request {} did not finish on time", done);
+        }
+        start.elapsed()
+    }
+
+    /// Run the five requests next to a heartbeat task and count the heartbeats
+    /// that ran while any request was still in flight
+    async fn heartbeats_during<F, Fut>(handle_request: F) -> usize
+    where
+        F: Fn(u64) -> Fut,
+        Fut: std::future::Future<Output = u64> + Send + 'static,
+    {
+        let in_flight = Arc::new(AtomicUsize::new(REQUESTS as usize));
+        let beats = Arc::new(AtomicUsize::new(0));
+
+        let heartbeat = {
+            let in_flight = Arc::clone(&in_flight);
+            let beats = Arc::clone(&beats);
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(HEARTBEAT).await;
+                    if in_flight.load(Ordering::SeqCst) > 0 {
+                        beats.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            })
+        };
+
+        let mut requests = JoinSet::new();
+        for i in 0..REQUESTS {
+            let request = handle_request(i);
+            let in_flight = Arc::clone(&in_flight);
+            requests.spawn(async move {
+                request.await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+        while requests.join_next().await.is_some() {}
+        heartbeat.abort();
+        beats.load(Ordering::SeqCst)
+    }
+
+    /// Serialized by the async mutex: exactly one REQUEST_TIME per request,
+    /// without sleeping for real
+    #[tokio::test(start_paused = true)]
+    async fn fixed_requests_take_exact_virtual_time() {
+        let wall = Instant::now();
+        let elapsed = step_fixed_requests().await;
+
+        assert_eq!(elapsed, REQUEST_TIME * REQUESTS as u32);
+        assert!(wall.elapsed() < REQUEST_TIME, "virtual time should not sleep for real");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fixed_requests_leave_runtime_free() {
+        let server = Arc::new(fixed::Server::new());
+        let beats = heartbeats_during(|i| {
+            let srv = Arc::clone(&server);
+            async move { srv.handle_request(i).await }
+        })
+        .await;
+
+        assert!(beats > 0, "async sleeps must leave the runtime free for the heartbeat");
+    }
+
+    /// std::thread::sleep blocks the runtime's only thread for real, so the
+    /// paused clock never moves and nothing else runs until it returns
+    #[tokio::test(start_paused = true)]
+    async fn buggy_requests_starve_runtime() {
+        let server = Arc::new(buggy::Server::new());
+        let beats = heartbeats_during(|i| {
+            let srv = Arc::clone(&server);
+            async move { srv.handle_request(i).await }
+        })
+        .await;
+
+        assert_eq!(beats, 0, "blocking sleeps must starve the heartbeat");
+    }
+}
@@ -9,7 +9,7 @@
 use std::collections::hash_map::DefaultHasher;
 use std::env;
 use std::hash::{Hash, Hasher};
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -22,30 +22,81 @@ fn hash_object(id: ObjectId) -> u64 {
     hasher.finish()
 }
 
+/// Ticket lock: waiters are served strictly in arrival order, so a thread
+/// that releases and immediately re-locks cannot jump the queue the way it
+/// can with `std::sync::Mutex`
+#[derive(Default)]
+pub struct FairMutex {
+    /// (next ticket to hand out, ticket currently being served)
+    tickets: Mutex<(u64, u64)>,
+    turn: Condvar,
+}
+
+pub struct FairMutexGuard<'a> {
+    lock: &'a FairMutex,
+}
+
+impl FairMutex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lock(&self) -> FairMutexGuard<'_> {
+        let mut tickets = self.tickets.lock().unwrap();
+        let ticket = tickets.0;
+        tickets.0 += 1;
+        while tickets.1 != ticket {
+            tickets = self.turn.wait(tickets).unwrap();
+        }
+        FairMutexGuard { lock: self }
+    }
+}
+
+impl Drop for FairMutexGuard<'_> {
+    fn drop(&mut self) {
+        self.lock.tickets.lock().unwrap().1 += 1;
+        self.lock.turn.notify_all();
+    }
+}
+
 /// Buggy version - fixed-size lock table causes false contention
 mod buggy {
     use super::*;
 
-    const TABLE_SIZE: usize = 4; // Small table = lots of collisions
-
     pub struct LockTable {
         slots: Vec<Mutex<()>>,
     }
 
     impl LockTable {
-        pub fn new() -> Self {
-            let mut slots = Vec::with_capacity(TABLE_SIZE);
-            for _ in 0..TABLE_SIZE {
+        /// Table with `size` slots, fixed for its lifetime
+        pub fn new(size: usize) -> Self {
+            let mut slots = Vec::with_capacity(size);
+            for _ in 0..size {
                 slots.push(Mutex::new(()));
             }
             Self { slots }
         }
 
+        pub fn size(&self) -> usize {
+            self.slots.len()
+        }
+
+        pub fn slot_for(&self, object_id: ObjectId) -> usize {
+            (hash_object(object_id) as usize) % self.slots.len()
+        }
+
+        /// Objects in `objects` that share their slot with an earlier one
+        pub fn collisions(&self, objects: &[ObjectId]) -> usize {
+            let mut used: Vec<usize> = objects.iter().map(|&id| self.slot_for(id)).collect();
+            used.sort();
+            used.dedup();
+            objects.len() - used.len()
+        }
+
         /// BUG: Fixed-size table causes false contention
         /// Different objects can hash to the same slot!
         pub fn acquire(&self, object_id: ObjectId) -> std::sync::MutexGuard<()> {
-            let hash = hash_object(object_id);
-            let slot = (hash as usize) % TABLE_SIZE;
+            let slot = self.slot_for(object_id);
             println!(
                 "[BUGGY] Object {} -> slot {} (hash collision possible!)",
                 object_id, slot
@@ -125,94 +176,243 @@ mod fixed {
 
         start.elapsed()
     }
+
+    /// Same two-level layout as `ShardedLockTable`, with ticket-lock slots
+    pub struct FairShardedLockTable {
+        shards: Vec<Vec<FairMutex>>,
+    }
+
+    impl FairShardedLockTable {
+        pub fn new() -> Self {
+            let shards = (0..NUM_SHARDS)
+                .map(|_| (0..SHARD_SIZE).map(|_| FairMutex::new()).collect())
+                .collect();
+            Self { shards }
+        }
+
+        pub fn acquire(&self, object_id: ObjectId) -> FairMutexGuard<'_> {
+            let hash = hash_object(object_id);
+            let shard_idx = (hash as usize) % NUM_SHARDS;
+            let slot_idx = ((hash >> 16) as usize) % SHARD_SIZE;
+
+            println!(
+                "[FIXED] Object {} -> shard {}, slot {} (fair slot)",
+                object_id, shard_idx, slot_idx
+            );
+
+            self.shards[shard_idx][slot_idx].lock()
+        }
+    }
+
+    pub fn process_objects_fair(table: Arc<FairShardedLockTable>, objects: Vec<ObjectId>, thread_id: usize) -> Duration {
+        let start = Instant::now();
+
+        for obj_id in objects {
+            let _guard = table.acquire(obj_id);
+            thread::sleep(Duration::from_millis(10));
+            println!("[FIXED] Thread {} processed object {}", thread_id, obj_id);
+        }
+
+        start.elapsed()
+    }
+}
+
+/// How many times to run the workload: `warmup` discarded rounds, then
+/// `iterations` measured rounds whose median is reported
+#[derive(Clone, Copy, Debug)]
+pub struct Measurement {
+    warmup: usize,
+    iterations: usize,
+}
+
+/// Run the odd objects on thread 1 and the even objects on thread 2.
+/// Returns (thread 1 time, thread 2 time, total wall time).
+fn timed_round<T: Send + Sync + 'static>(
+    table: &Arc<T>,
+    objects: &[ObjectId],
+    process: fn(Arc<T>, Vec<ObjectId>, usize) -> Duration,
+) -> (Duration, Duration, Duration) {
+    let objects1: Vec<ObjectId> = objects.iter().cloned().filter(|x| x % 2 == 1).collect();
+    let objects2: Vec<ObjectId> = objects.iter().cloned().filter(|x| x % 2 == 0).collect();
+
+    let table1 = Arc::clone(table);
+    let table2 = Arc::clone(table);
+
+    let start = Instant::now();
+
+    let t1 = thread::spawn(move || process(table1, objects1, 1));
+    let t2 = thread::spawn(move || process(table2, objects2, 2));
+
+    let time1 = t1.join().unwrap();
+    let time2 = t2.join().unwrap();
+    (time1, time2, start.elapsed())
+}
+
+/// Discard the warmup rounds, then return the last measured round and the
+/// median total time across all measured rounds
+fn measure<T: Send + Sync + 'static>(
+    table: &Arc<T>,
+    objects: &[ObjectId],
+    process: fn(Arc<T>, Vec<ObjectId>, usize) -> Duration,
+    measurement: Measurement,
+) -> ((Duration, Duration, Duration), Duration) {
+    for _ in 0..measurement.warmup {
+        timed_round(table, objects, process);
+    }
+
+    let rounds: Vec<_> = (0..measurement.iterations.max(1))
+        .map(|_| timed_round(table, objects, process))
+        .collect();
+    let mut totals: Vec<Duration> = rounds.iter().map(|r| r.2).collect();
+    totals.sort();
+
+    (*rounds.last().unwrap(), totals[totals.len() / 2])
+}
+
+impl Measurement {
+    fn is_repeated(&self) -> bool {
+        self.warmup > 0 || self.iterations > 1
+    }
+}
+
+/// Metrics lines for a repeated measurement; nothing for a single round
+fn print_measurement(measurement: Measurement, median: Duration) {
+    if measurement.is_repeated() {
+        println!("warmup_rounds: {}", measurement.warmup);
+        println!("measured_rounds: {}", measurement.iterations.max(1));
+        println!("median_total_ms: {:.2}", median.as_secs_f64() * 1000.0);
+    }
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let use_fair = args.iter().any(|arg| arg == "--fair");
+    let parse_flag = |name: &str, default: usize| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+    let measurement = Measurement {
+        warmup: parse_flag("--warmup", 0),
+        iterations: parse_flag("--iterations", 1),
+    };
+    let table_size = match args.iter().position(|arg| arg == "--table-size").and_then(|i| args.get(i + 1)) {
+        Some(value) => match value.parse::<usize>() {
+            Ok(size) if size > 0 => size,
+            _ => {
+                eprintln!("Invalid --table-size '{}': expected a positive slot count", value);
+                std::process::exit(2);
+            }
+        },
+        None => DEFAULT_TABLE_SIZE,
+    };
 
     println!("=== Sui PR #3858: False Contention in Mutex Table ===\n");
 
-    if use_fixed {
-        println!("Running FIXED version (sharded lock table)...\n");
-        run_fixed_test();
+    if args.iter().any(|arg| arg == "--grow-table") {
+        println!("Running BUGGY version at {} and {} slots...\n", DEFAULT_TABLE_SIZE, GROWN_TABLE_SIZE);
+        run_grow_table_test();
+    } else if args.iter().any(|arg| arg == "--fairness") {
+        println!(
+            "Running FAIRNESS study ({} slot, two threads on one object)...\n",
+            if use_fair { "ticket-lock" } else { "std::sync::Mutex" }
+        );
+        run_fairness_test(use_fair);
+    } else if use_fixed {
+        if use_fair {
+            println!("Running FIXED version (sharded lock table, fair slots)...\n");
+        } else {
+            println!("Running FIXED version (sharded lock table)...\n");
+        }
+        run_fixed_test(measurement, use_fair);
     } else {
         println!("Running BUGGY version (fixed-size table with collisions)...\n");
-        run_buggy_test();
+        run_buggy_test(measurement, table_size);
     }
 }
 
-fn run_buggy_test() {
-    let table = Arc::new(buggy::LockTable::new());
+/// Buggy lock table size unless `--table-size` is given
+const DEFAULT_TABLE_SIZE: usize = 4; // Small table = lots of collisions
+
+fn run_buggy_test(measurement: Measurement, table_size: usize) {
+    let table = Arc::new(buggy::LockTable::new(table_size));
 
     // Create objects that will hash to different slots
     // But with only 4 slots, many will collide!
     let all_objects: Vec<ObjectId> = (1..=8).collect();
 
-    println!("Lock table size: 4 slots");
+    println!("Lock table size: {} slots", table.size());
     println!("Processing {} objects across 2 threads\n", all_objects.len());
 
     // Show which objects collide
     println!("Object -> Slot mapping:");
     for obj in &all_objects {
-        let hash = hash_object(*obj);
-        let slot = (hash as usize) % 4;
-        println!("  Object {} -> Slot {}", obj, slot);
+        println!("  Object {} -> Slot {}", obj, table.slot_for(*obj));
     }
     println!();
 
-    let objects1: Vec<ObjectId> = all_objects.iter().cloned().filter(|x| x % 2 == 1).collect();
-    let objects2: Vec<ObjectId> = all_objects.iter().cloned().filter(|x| x % 2 == 0).collect();
-
-    let table1 = Arc::clone(&table);
-    let table2 = Arc::clone(&table);
-
-    let start = Instant::now();
-
-    let t1 = thread::spawn(move || buggy::process_objects(table1, objects1, 1));
-    let t2 = thread::spawn(move || buggy::process_objects(table2, objects2, 2));
-
-    let time1 = t1.join().unwrap();
-    let time2 = t2.join().unwrap();
-    let total = start.elapsed();
+    let ((time1, time2, total), median) =
+        measure(&table, &all_objects, buggy::process_objects, measurement);
 
     println!("\n=== Results ===");
-    println!("[BUG DEMONSTRATED]");
+    print_banner("[BUG DEMONSTRATED]");
     println!("Thread 1 time: {:?}", time1);
     println!("Thread 2 time: {:?}", time2);
     println!("Total time: {:?}", total);
     println!("\nProblem: Different objects collide on same lock slot!");
-    println!("  - Objects with same (hash % 4) block each other");
+    println!("  - Objects with same (hash % {}) block each other", table.size());
     println!("  - False contention slows down parallel processing");
     println!("  - Gets worse with more concurrent transactions");
+    println!("\n=== Metrics ===");
+    println!("table_size: {}", table.size());
+    println!("collisions: {}", table.collisions(&all_objects));
+    print_measurement(measurement, median);
     println!("\nRun with --fixed to see sharded lock table.");
 }
 
-fn run_fixed_test() {
-    let table = Arc::new(fixed::ShardedLockTable::new());
-
+fn run_fixed_test(measurement: Measurement, fair: bool) {
     let all_objects: Vec<ObjectId> = (1..=8).collect();
 
     println!("Lock table: 16 shards x 16 slots = 256 possible locks");
     println!("Processing {} objects across 2 threads\n", all_objects.len());
 
-    let objects1: Vec<ObjectId> = all_objects.iter().cloned().filter(|x| x % 2 == 1).collect();
-    let objects2: Vec<ObjectId> = all_objects.iter().cloned().filter(|x| x % 2 == 0).collect();
-
-    let table1 = Arc::clone(&table);
-    let table2 = Arc::clone(&table);
-
-    let start = Instant::now();
-
-    let t1 = thread::spawn(move || fixed::process_objects(table1, objects1, 1));
-    let t2 = thread::spawn(move || fixed::process_objects(table2, objects2, 2));
-
-    let time1 = t1.join().unwrap();
-    let time2 = t2.join().unwrap();
-    let total = start.elapsed();
+    let ((time1, time2, total), median) = if fair {
+        let table = Arc::new(fixed::FairShardedLockTable::new());
+        measure(&table, &all_objects, fixed::process_objects_fair, measurement)
+    } else {
+        let table = Arc::new(fixed::ShardedLockTable::new());
+        measure(&table, &all_objects, fixed::process_objects, measurement)
+    };
 
     println!("\n=== Results ===");
-    println!("[FIXED]");
+    print_banner("[FIXED]");
     println!("Thread 1 time: {:?}", time1);
     println!("Thread 2 time: {:?}", time2);
     println!("Total time: {:?}", total);
@@ -220,4 +420,135 @@ fn run_fixed_test() {
     println!("  - 256 possible slots vs 4 in buggy version");
     println!("  - Different objects rarely collide");
     println!("  - Better parallelism under high load");
+    if measurement.is_repeated() {
+        println!("\n=== Metrics ===");
+    }
+    print_measurement(measurement, median);
+}
+
+/// Table size `--grow-table` compares against the default. Slots are picked
+/// by hash, so a table merely as large as the object count still collides
+/// (8 slots put 8 objects in 6); 32 is the smallest power of two that
+/// separates objects 1..=8.
+const GROWN_TABLE_SIZE: usize = 32;
+
+/// Run the buggy workload on the default table and on a grown one
+fn run_grow_table_test() {
+    let all_objects: Vec<ObjectId> = (1..=8).collect();
+    let measurement = Measurement { warmup: 1, iterations: 3 };
+
+    let mut rows = Vec::new();
+    for size in [DEFAULT_TABLE_SIZE, GROWN_TABLE_SIZE] {
+        println!("--- {} slots ---", size);
+        let table = Arc::new(buggy::LockTable::new(size));
+        let (_, median) = measure(&table, &all_objects, buggy::process_objects, measurement);
+        rows.push((size, table.collisions(&all_objects), median));
+        println!();
+    }
+
+    println!("=== Results ===");
+    for &(size, collisions, median) in &rows {
+        println!("[BUGGY] {} slots: {} collisions, median total {:?}", size, collisions, median);
+    }
+
+    println!("\n=== Metrics ===");
+    for &(size, collisions, median) in &rows {
+        println!("collisions_at_{}: {}", size, collisions);
+        println!("median_total_ms_at_{}: {:.2}", size, median.as_secs_f64() * 1000.0);
+    }
+
+    let (_, small_collisions, small_median) = rows[0];
+    let (_, grown_collisions, grown_median) = rows[1];
+    assert!(GROWN_TABLE_SIZE >= all_objects.len());
+    assert!(small_collisions > 0, "the default table should make objects share slots");
+    assert_eq!(grown_collisions, 0, "{} slots should give every object its own lock", GROWN_TABLE_SIZE);
+    assert!(
+        grown_median < small_median,
+        "growing the table should cut elapsed time ({:?} vs {:?})",
+        grown_median,
+        small_median
+    );
+
+    println!();
+    print_banner("[NOTE]");
+    println!("With a slot per object nothing collides and the threads no longer wait on each other.");
+    println!("A table can never be sized for every live object, which is why the fix shards per object.");
+}
+
+/// How long both threads hammer the shared slot in `--fairness`
+const FAIRNESS_DURATION: Duration = Duration::from_millis(200);
+/// Busy work done while holding the slot
+const FAIRNESS_HOLD: Duration = Duration::from_micros(20);
+
+/// Hold the current lock for `FAIRNESS_HOLD` without sleeping, so the
+/// releasing thread is still running when it tries to re-lock
+fn busy_hold() {
+    let start = Instant::now();
+    while start.elapsed() < FAIRNESS_HOLD {
+        std::hint::spin_loop();
+    }
+}
+
+fn run_fairness_test(fair: bool) {
+    let std_slot = Arc::new(Mutex::new(()));
+    let fair_slot = Arc::new(FairMutex::new());
+
+    println!("Two threads acquire the same slot in a tight loop for {:?}", FAIRNESS_DURATION);
+    println!("Each acquisition holds the slot for {:?}\n", FAIRNESS_HOLD);
+
+    let handles: Vec<_> = (1..=2)
+        .map(|thread_id| {
+            let std_slot = Arc::clone(&std_slot);
+            let fair_slot = Arc::clone(&fair_slot);
+            thread::spawn(move || {
+                let start = Instant::now();
+                let mut acquisitions = 0u64;
+                while start.elapsed() < FAIRNESS_DURATION {
+                    if fair {
+                        let _guard = fair_slot.lock();
+                        busy_hold();
+                    } else {
+                        let _guard: MutexGuard<()> = std_slot.lock().unwrap();
+                        busy_hold();
+                    }
+                    acquisitions += 1;
+                }
+                (thread_id, acquisitions)
+            })
+        })
+        .collect();
+
+    let counts: Vec<(usize, u64)> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    let max = counts.iter().map(|c| c.1).max().unwrap_or(0);
+    let min = counts.iter().map(|c| c.1).min().unwrap_or(0);
+    let skew = max as f64 / min.max(1) as f64;
+
+    println!("=== Results ===");
+    for (thread_id, acquisitions) in &counts {
+        println!("Thread {} acquisitions: {}", thread_id, acquisitions);
+    }
+
+    if fair {
+        // Strict alternation: each thread finishes its last turn once the
+        // deadline passes, so the counts differ by a few at most
+        assert!(skew < 1.2, "ticket lock should share the slot evenly (skew {:.2})", skew);
+        println!();
+        print_banner("[FIXED]");
+        println!("Ticket lock served the two threads in turn.");
+    } else if skew >= 1.2 {
+        println!();
+        print_banner("[NOTE]");
+        println!("std::sync::Mutex let one thread take the slot {:.1}x as often.", skew);
+        println!("Run with --fairness --fair to see the ticket lock.");
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("std::sync::Mutex happened to share the slot evenly this run.");
+    }
+
+    println!("\n=== Metrics ===");
+    println!("slot: {}", if fair { "ticket" } else { "std" });
+    println!("max_acquisitions: {}", max);
+    println!("min_acquisitions: {}", min);
+    println!("skew: {:.2}", skew);
 }
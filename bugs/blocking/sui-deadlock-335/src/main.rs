@@ -9,9 +9,10 @@
 
 use std::collections::HashMap;
 use std::env;
-use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock, Weak};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub type ObjectId = String;
 pub type TxDigest = String;
@@ -28,6 +29,7 @@ pub struct OwnedObject {
 #[derive(Clone, Debug)]
 pub struct Order {
     digest: TxDigest,
+    sender: String,
     input_objects: Vec<ObjectId>,
 }
 
@@ -39,6 +41,32 @@ pub enum OrderResult {
     Blocked,
 }
 
+/// Wait-for graph snapshot, exported as Graphviz DOT with `--format dot`
+///
+/// Each crate builds on its own, so this is copied into ballista-deadlock-132,
+/// reth-deadlock-10842, sui-blocking-5201, sui-deadlock-335, sui-deadlock-960
+/// and sui-race-4990. Keep the copies identical; reth-deadlock-10842 also
+/// has `remove_edge`.
+#[derive(Debug, Default)]
+pub struct WaitForGraph {
+    edges: Vec<(String, String, String)>,
+}
+
+impl WaitForGraph {
+    fn add_edge(&mut self, from: &str, to: &str, label: &str) {
+        self.edges.push((from.to_string(), to.to_string(), label.to_string()));
+    }
+
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph {} {{\n", name);
+        for (from, to, label) in &self.edges {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", from, to, label));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
 /// Buggy version - no locking, allows conflicting orders
 mod buggy {
     use super::*;
@@ -46,6 +74,7 @@ mod buggy {
     pub struct Authority {
         objects: RwLock<HashMap<ObjectId, OwnedObject>>,
         pending_orders: Mutex<HashMap<ObjectId, Vec<TxDigest>>>,
+        conflict_graph: Mutex<Option<WaitForGraph>>,
     }
 
     impl Authority {
@@ -53,6 +82,7 @@ mod buggy {
             Self {
                 objects: RwLock::new(HashMap::new()),
                 pending_orders: Mutex::new(HashMap::new()),
+                conflict_graph: Mutex::new(None),
             }
         }
 
@@ -69,8 +99,12 @@ mod buggy {
             {
                 let objects = self.objects.read().unwrap();
                 for obj_id in &order.input_objects {
-                    if !objects.contains_key(obj_id) {
-                        return OrderResult::Conflict(format!("Object {} not found", obj_id));
+                    match objects.get(obj_id) {
+                        None => return OrderResult::Conflict(format!("Object {} not found", obj_id)),
+                        Some(obj) if obj.owner != order.sender => {
+                            return OrderResult::Conflict("not owner".to_string());
+                        }
+                        Some(_) => {}
                     }
                 }
             }
@@ -100,6 +134,11 @@ mod buggy {
                             println!("[BUGGY] CONFLICT! Object {} has multiple orders: {:?}",
                                      obj_id, orders);
                             // BUG: We already started processing, now we have conflict
+                            let mut graph = WaitForGraph::default();
+                            for digest in orders {
+                                graph.add_edge(digest, obj_id, "contends for");
+                            }
+                            *self.conflict_graph.lock().unwrap() = Some(graph);
                         }
                     }
                 }
@@ -135,6 +174,14 @@ mod buggy {
                 .get(obj_id)
                 .and_then(|obj| obj.locked_by.clone())
         }
+
+        /// Orders that were in flight on the same object when the conflict
+        /// was detected, as DOT
+        pub fn conflict_dot(&self) -> Option<String> {
+            self.conflict_graph.lock().unwrap()
+                .as_ref()
+                .map(|graph| graph.to_dot("sui_335"))
+        }
     }
 }
 
@@ -142,22 +189,144 @@ mod buggy {
 mod fixed {
     use super::*;
 
+    /// How an order holds one of its input objects
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum LockMode {
+        /// Owned-object access: the only holder
+        #[default]
+        Exclusive,
+        /// Read access to a shared object: any number of shared holders,
+        /// but no exclusive holder alongside them
+        Shared,
+    }
+
     /// Lock entry for an object
     struct ObjectLock {
+        /// The exclusive holder
         locked_by: Option<TxDigest>,
+        /// Mode the object is currently held in
+        mode: LockMode,
+        /// Orders holding the object in `Shared` mode
+        shared_holders: Vec<TxDigest>,
+        /// When a leased lock stops being valid; None for plain locks
+        lease_expires: Option<Instant>,
         waiters: Vec<(TxDigest, Arc<(Mutex<bool>, Condvar)>)>,
     }
 
+    impl ObjectLock {
+        fn is_free(&self) -> bool {
+            self.locked_by.is_none() && self.shared_holders.is_empty()
+        }
+
+        /// Shared holders admit more shared holders; exclusive needs the
+        /// object free
+        fn can_grant(&self, mode: LockMode) -> bool {
+            match mode {
+                LockMode::Exclusive => self.is_free(),
+                LockMode::Shared => self.locked_by.is_none(),
+            }
+        }
+
+        fn grant(&mut self, digest: &str, mode: LockMode) {
+            match mode {
+                LockMode::Exclusive => self.locked_by = Some(digest.to_string()),
+                LockMode::Shared => self.shared_holders.push(digest.to_string()),
+            }
+            self.mode = mode;
+        }
+
+        fn lease_expired(&self, now: Instant) -> bool {
+            self.locked_by.is_some() && self.lease_expires.is_some_and(|expires| expires <= now)
+        }
+
+        /// Drop `digest`'s hold, exclusive or shared. Waiters are woken once
+        /// nobody holds the object. Returns false if `digest` held nothing.
+        fn release(&mut self, digest: &str) -> bool {
+            if self.locked_by.as_deref() == Some(digest) {
+                self.free();
+                return true;
+            }
+            let Some(i) = self.shared_holders.iter().position(|d| d == digest) else {
+                return false;
+            };
+            self.shared_holders.remove(i);
+            // The last reader out wakes any waiting writer
+            if self.shared_holders.is_empty() {
+                self.free();
+            }
+            true
+        }
+
+        /// Clear the holder and wake every waiter so it can retry
+        fn free(&mut self) {
+            self.locked_by = None;
+            self.mode = LockMode::Exclusive;
+            self.shared_holders.clear();
+            self.lease_expires = None;
+            for (_, waiter) in self.waiters.drain(..) {
+                let (lock, cvar) = &*waiter;
+                *lock.lock().unwrap() = true;
+                cvar.notify_one();
+            }
+        }
+    }
+
+    /// Longest randomized pause between `handle_order_try_all` attempts
+    pub const TRY_ALL_BACKOFF_MAX: Duration = Duration::from_millis(5);
+
+    /// Order in which an order's input object locks are taken
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum LockOrder {
+        /// As listed in `input_objects`; two orders listing shared objects
+        /// in opposite order can each hold one lock and wait for the other
+        AsGiven,
+        /// Sorted by `ObjectId`, so every order takes shared locks in the
+        /// same global order. The default for `Authority::new`.
+        #[default]
+        Sorted,
+    }
+
     pub struct Authority {
         objects: RwLock<HashMap<ObjectId, OwnedObject>>,
         object_locks: Mutex<HashMap<ObjectId, ObjectLock>>,
+        lock_order: LockOrder,
+        /// Pause after taking each lock, standing in for the object read
+        /// that follows it
+        acquire_step: Duration,
+        /// Leased locks reclaimed after their lease ran out
+        leases_reaped: AtomicUsize,
+        /// Failed `handle_order_try_all` passes that backed off and retried
+        try_all_retries: AtomicUsize,
+        /// xorshift state for the try-all backoff
+        backoff_state: AtomicU64,
+        /// Orders that found a lock held and queued for it, each counted once
+        lock_waits: AtomicU64,
+        /// Total time spent in those waits, in nanoseconds
+        lock_wait_nanos: AtomicU64,
+        /// Waiter wakeups before the timeout, whether the lock was released
+        /// or not
+        #[cfg(test)]
+        wakeups: AtomicUsize,
     }
 
     impl Authority {
         pub fn new() -> Self {
+            Self::with_lock_order(LockOrder::default(), Duration::ZERO)
+        }
+
+        pub fn with_lock_order(lock_order: LockOrder, acquire_step: Duration) -> Self {
             Self {
                 objects: RwLock::new(HashMap::new()),
                 object_locks: Mutex::new(HashMap::new()),
+                lock_order,
+                acquire_step,
+                leases_reaped: AtomicUsize::new(0),
+                try_all_retries: AtomicUsize::new(0),
+                backoff_state: AtomicU64::new(0x9E37_79B9_7F4A_7C15),
+                lock_waits: AtomicU64::new(0),
+                lock_wait_nanos: AtomicU64::new(0),
+                #[cfg(test)]
+                wakeups: AtomicUsize::new(0),
             }
         }
 
@@ -165,14 +334,27 @@ mod fixed {
             self.objects.write().unwrap().insert(obj.id.clone(), obj.clone());
             self.object_locks.lock().unwrap().insert(obj.id, ObjectLock {
                 locked_by: None,
+                mode: LockMode::Exclusive,
+                shared_holders: Vec::new(),
+                lease_expires: None,
                 waiters: Vec::new(),
             });
         }
 
-        /// FIX: Acquire locks before processing
-        pub fn handle_order(&self, order: &Order, wait_timeout: Duration) -> OrderResult {
+        /// FIX: Acquire locks before processing, in canonical `ObjectId`
+        /// order unless the authority was built with `LockOrder::AsGiven`.
+        /// `modes` gives each input object's lock mode; objects missing from
+        /// it are locked `Exclusive`. Shared objects are only read: they skip
+        /// the ownership check and are not written on execution.
+        pub fn handle_order(
+            &self,
+            order: &Order,
+            modes: &HashMap<ObjectId, LockMode>,
+            wait_timeout: Duration,
+        ) -> OrderResult {
             println!("[FIXED] Processing order {} for objects {:?}",
                      order.digest, order.input_objects);
+            let is_exclusive = |obj_id: &ObjectId| modes.get(obj_id).copied().unwrap_or_default() == LockMode::Exclusive;
 
             // Check if objects exist
             {
@@ -185,10 +367,231 @@ mod fixed {
             }
 
             // FIX: Try to acquire locks on all input objects
+            let acquired_locks = match self.acquire_locks(&order.digest, &order.input_objects, modes, wait_timeout) {
+                Some(acquired) => acquired,
+                None => return OrderResult::Blocked,
+            };
+
+            // Ownership is checked under the lock so a concurrent transfer
+            // cannot change the owner between the check and the execution
+            {
+                let objects = self.objects.read().unwrap();
+                let not_owned = order.input_objects.iter()
+                    .filter(|obj_id| is_exclusive(obj_id))
+                    .any(|obj_id| objects.get(obj_id).is_some_and(|obj| obj.owner != order.sender));
+                if not_owned {
+                    drop(objects);
+                    println!("[FIXED] Order {} rejected: {} is not the owner",
+                             order.digest, order.sender);
+                    self.release_locks(&order.digest, &acquired_locks);
+                    return OrderResult::Conflict("not owner".to_string());
+                }
+            }
+
+            // Simulate processing time
+            thread::sleep(Duration::from_millis(50));
+
+            // Execute the order
+            {
+                let mut objects = self.objects.write().unwrap();
+                for obj_id in &order.input_objects {
+                    if !is_exclusive(obj_id) {
+                        println!("[FIXED] Order {} read shared {}", order.digest, obj_id);
+                        continue;
+                    }
+                    if let Some(obj) = objects.get_mut(obj_id) {
+                        obj.locked_by = Some(order.digest.clone());
+                        println!("[FIXED] Order {} executed on {}", order.digest, obj_id);
+                    }
+                }
+            }
+
+            // Release locks
+            self.release_locks(&order.digest, &acquired_locks);
+
+            OrderResult::Success
+        }
+
+        /// FIX (try-all): Never wait while holding a lock. Each attempt takes
+        /// every input lock in one pass; if any is held, the locks taken so
+        /// far are released and the order backs off for a random pause
+        /// before the next attempt. Returns `Blocked` after `max_attempts`.
+        /// `modes` works as in `handle_order`.
+        pub fn handle_order_try_all(
+            &self,
+            order: &Order,
+            modes: &HashMap<ObjectId, LockMode>,
+            max_attempts: usize,
+        ) -> OrderResult {
+            println!("[FIXED] Processing order {} for objects {:?} (try-all)",
+                     order.digest, order.input_objects);
+            let is_exclusive = |obj_id: &ObjectId| modes.get(obj_id).copied().unwrap_or_default() == LockMode::Exclusive;
+
+            {
+                let objects = self.objects.read().unwrap();
+                for obj_id in &order.input_objects {
+                    if !objects.contains_key(obj_id) {
+                        return OrderResult::Conflict(format!("Object {} not found", obj_id));
+                    }
+                }
+            }
+
+            let mut acquired_locks = None;
+            for attempt in 1..=max_attempts {
+                match self.try_lock_all(&order.digest, &order.input_objects, modes) {
+                    Ok(acquired) => {
+                        println!("[FIXED] Order {} took all locks on attempt {}", order.digest, attempt);
+                        acquired_locks = Some(acquired);
+                        break;
+                    }
+                    Err(busy) => {
+                        if attempt == 1 {
+                            println!("[FIXED] Order {} found {} held, backing off", order.digest, busy);
+                        }
+                        if attempt < max_attempts {
+                            self.try_all_retries.fetch_add(1, Ordering::SeqCst);
+                            thread::sleep(self.backoff());
+                        }
+                    }
+                }
+            }
+            let Some(acquired_locks) = acquired_locks else {
+                println!("[FIXED] Order {} gave up after {} attempts", order.digest, max_attempts);
+                return OrderResult::Blocked;
+            };
+
+            {
+                let objects = self.objects.read().unwrap();
+                let not_owned = order.input_objects.iter()
+                    .filter(|obj_id| is_exclusive(obj_id))
+                    .any(|obj_id| objects.get(obj_id).is_some_and(|obj| obj.owner != order.sender));
+                if not_owned {
+                    drop(objects);
+                    self.release_locks(&order.digest, &acquired_locks);
+                    return OrderResult::Conflict("not owner".to_string());
+                }
+            }
+
+            // Simulate processing time
+            thread::sleep(Duration::from_millis(50));
+
+            {
+                let mut objects = self.objects.write().unwrap();
+                for obj_id in order.input_objects.iter().filter(|obj_id| is_exclusive(obj_id)) {
+                    if let Some(obj) = objects.get_mut(obj_id) {
+                        obj.locked_by = Some(order.digest.clone());
+                    }
+                }
+            }
+
+            self.release_locks(&order.digest, &acquired_locks);
+            OrderResult::Success
+        }
+
+        /// One pass over `obj_ids`, taking each lock that can be granted in
+        /// its mode. On the first that cannot, releases what this pass took
+        /// and returns that object's id.
+        fn try_lock_all(
+            &self,
+            digest: &str,
+            obj_ids: &[ObjectId],
+            modes: &HashMap<ObjectId, LockMode>,
+        ) -> Result<Vec<ObjectId>, ObjectId> {
+            let mut locks = self.object_locks.lock().unwrap();
+            let mut acquired_locks = Vec::new();
+            for obj_id in obj_ids {
+                let mode = modes.get(obj_id).copied().unwrap_or_default();
+                let lock_entry = locks.get_mut(obj_id).unwrap();
+                if !lock_entry.can_grant(mode) {
+                    for taken in &acquired_locks {
+                        locks.get_mut(taken).unwrap().release(digest);
+                    }
+                    return Err(obj_id.clone());
+                }
+                lock_entry.grant(digest, mode);
+                acquired_locks.push(obj_id.clone());
+            }
+            Ok(acquired_locks)
+        }
+
+        /// Random pause in `1ms..=TRY_ALL_BACKOFF_MAX`, so orders that
+        /// collided do not retry in lockstep
+        fn backoff(&self) -> Duration {
+            let mut x = 0;
+            let _ = self.backoff_state.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |mut s| {
+                s ^= s << 13;
+                s ^= s >> 7;
+                s ^= s << 17;
+                x = s;
+                Some(s)
+            });
+            let max_us = TRY_ALL_BACKOFF_MAX.as_micros() as u64;
+            Duration::from_micros(1_000 + x % (max_us - 1_000 + 1))
+        }
+
+        /// Orders that queued for a lock so far, and the total time spent
+        /// waiting
+        pub fn lock_stats(&self) -> (u64, Duration) {
+            (
+                self.lock_waits.load(Ordering::Relaxed),
+                Duration::from_nanos(self.lock_wait_nanos.load(Ordering::Relaxed)),
+            )
+        }
+
+        pub fn try_all_retries(&self) -> usize {
+            self.try_all_retries.load(Ordering::SeqCst)
+        }
+
+        /// FIX (ownership): transferring an object is itself a transaction on
+        /// it, so it must hold the object lock like any order
+        pub fn transfer(&self, obj_id: &str, new_owner: &str, wait_timeout: Duration) -> OrderResult {
+            let digest = format!("transfer_{}_to_{}", obj_id, new_owner);
+            let obj_ids = vec![obj_id.to_string()];
+            println!("[FIXED] Transfer {} requested", digest);
+
+            if !self.objects.read().unwrap().contains_key(obj_id) {
+                return OrderResult::Conflict(format!("Object {} not found", obj_id));
+            }
+
+            let acquired_locks = match self.acquire_locks(&digest, &obj_ids, &HashMap::new(), wait_timeout) {
+                Some(acquired) => acquired,
+                None => return OrderResult::Blocked,
+            };
+
+            // Simulate processing time
+            thread::sleep(Duration::from_millis(50));
+
+            if let Some(obj) = self.objects.write().unwrap().get_mut(obj_id) {
+                println!("[FIXED] Object {} transferred from {} to {}", obj_id, obj.owner, new_owner);
+                obj.owner = new_owner.to_string();
+            }
+
+            self.release_locks(&digest, &acquired_locks);
+            OrderResult::Success
+        }
+
+        /// Acquire the lock on every object in order, in its mode from `modes`,
+        /// waiting up to `wait_timeout` in total. Returns None (holding
+        /// nothing) on timeout.
+        fn acquire_locks(
+            &self,
+            digest: &str,
+            obj_ids: &[ObjectId],
+            modes: &HashMap<ObjectId, LockMode>,
+            wait_timeout: Duration,
+        ) -> Option<Vec<ObjectId>> {
             let mut acquired_locks = Vec::new();
             let start = std::time::Instant::now();
+            // Counted in `lock_waits` once, however many times this order
+            // goes back to waiting
+            let mut queued = false;
 
-            for obj_id in &order.input_objects {
+            let mut obj_ids = obj_ids.to_vec();
+            if self.lock_order == LockOrder::Sorted {
+                obj_ids.sort();
+            }
+
+            for obj_id in &obj_ids {
                 loop {
                     let should_wait;
                     let waiter;
@@ -196,47 +599,132 @@ mod fixed {
                     {
                         let mut locks = self.object_locks.lock().unwrap();
                         let lock_entry = locks.get_mut(obj_id).unwrap();
+                        let mode = modes.get(obj_id).copied().unwrap_or_default();
 
-                        if lock_entry.locked_by.is_none() {
+                        if lock_entry.can_grant(mode) {
                             // FIX: Acquire the lock
-                            lock_entry.locked_by = Some(order.digest.clone());
+                            lock_entry.grant(digest, mode);
                             acquired_locks.push(obj_id.clone());
-                            println!("[FIXED] Order {} acquired lock on {}",
-                                     order.digest, obj_id);
+                            match mode {
+                                LockMode::Exclusive => println!("[FIXED] Order {} acquired lock on {}",
+                                                                digest, obj_id),
+                                LockMode::Shared => println!("[FIXED] Order {} acquired shared lock on {} (holders {:?})",
+                                                             digest, obj_id, lock_entry.shared_holders),
+                            }
+                            drop(locks);
+                            thread::sleep(self.acquire_step);
                             break;
                         } else {
                             // Object is locked, need to wait
-                            println!("[FIXED] Order {} waiting for {} (locked by {:?})",
-                                     order.digest, obj_id, lock_entry.locked_by);
+                            if lock_entry.mode == LockMode::Shared {
+                                println!("[FIXED] Order {} waiting for {} (shared by {:?})",
+                                         digest, obj_id, lock_entry.shared_holders);
+                            } else {
+                                println!("[FIXED] Order {} waiting for {} (locked by {:?})",
+                                         digest, obj_id, lock_entry.locked_by);
+                            }
 
                             if start.elapsed() >= wait_timeout {
                                 // Timeout - release acquired locks and return
-                                self.release_locks(&order.digest, &acquired_locks);
-                                return OrderResult::Blocked;
+                                drop(locks);
+                                self.release_locks(digest, &acquired_locks);
+                                return None;
                             }
 
                             // Create waiter
                             let pair = Arc::new((Mutex::new(false), Condvar::new()));
-                            lock_entry.waiters.push((order.digest.clone(), Arc::clone(&pair)));
+                            lock_entry.waiters.push((digest.to_string(), Arc::clone(&pair)));
                             waiter = pair;
                             should_wait = true;
                         }
                     }
 
                     if should_wait {
-                        // Wait for lock to be released
+                        // Wait for lock to be released. The released flag is
+                        // the predicate: a wakeup that did not set it is
+                        // spurious and goes back to waiting, and a release
+                        // that landed before this wait is not missed.
+                        if !queued {
+                            queued = true;
+                            self.lock_waits.fetch_add(1, Ordering::Relaxed);
+                        }
+                        let wait_started = Instant::now();
                         let (lock, cvar) = &*waiter;
-                        let guard = lock.lock().unwrap();
-                        let remaining = wait_timeout.saturating_sub(start.elapsed());
-                        let _ = cvar.wait_timeout(guard, remaining);
+                        let mut released = lock.lock().unwrap();
+                        while !*released {
+                            let remaining = wait_timeout.saturating_sub(start.elapsed());
+                            if remaining.is_zero() {
+                                break;
+                            }
+                            #[cfg_attr(not(test), allow(unused_variables))]
+                            let (guard, wait) = cvar.wait_timeout(released, remaining).unwrap();
+                            released = guard;
+                            #[cfg(test)]
+                            if !wait.timed_out() {
+                                self.wakeups.fetch_add(1, Ordering::SeqCst);
+                            }
+                        }
+                        self.lock_wait_nanos
+                            .fetch_add(wait_started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                    }
+                }
+            }
+
+            Some(acquired_locks)
+        }
+
+        fn release_locks(&self, tx_digest: &str, obj_ids: &[ObjectId]) {
+            let mut locks = self.object_locks.lock().unwrap();
+            for obj_id in obj_ids {
+                if let Some(lock_entry) = locks.get_mut(obj_id) {
+                    let shared = lock_entry.shared_holders.iter().any(|d| d == tx_digest);
+                    if lock_entry.release(tx_digest) {
+                        println!("[FIXED] Order {} released {}lock on {}",
+                                 tx_digest, if shared { "shared " } else { "" }, obj_id);
                     }
                 }
             }
+        }
+
+        /// FIX (lease): Like `handle_order`, but each lock is only granted for
+        /// `lease`. A holder that has not released by then loses the lock to
+        /// the reaper (see `start_reaper`), so a holder that never finishes
+        /// cannot block others indefinitely. Waiting has no timeout of its
+        /// own; it ends when the holder releases or its lease is reaped.
+        ///
+        /// Leases are exclusive-only: every input is locked `Exclusive`. The
+        /// reaper reclaims an object from its single holder and has no way to
+        /// expire one reader among several.
+        pub fn handle_order_leased(&self, order: &Order, lease: Duration) -> OrderResult {
+            println!("[FIXED] Processing order {} for objects {:?} (lease {:?})",
+                     order.digest, order.input_objects, lease);
+
+            {
+                let objects = self.objects.read().unwrap();
+                for obj_id in &order.input_objects {
+                    match objects.get(obj_id) {
+                        None => return OrderResult::Conflict(format!("Object {} not found", obj_id)),
+                        Some(obj) if obj.owner != order.sender => {
+                            return OrderResult::Conflict("not owner".to_string());
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+
+            let acquired_locks = self.acquire_leased(&order.digest, &order.input_objects, lease);
 
             // Simulate processing time
             thread::sleep(Duration::from_millis(50));
 
-            // Execute the order
+            // An order that overran its lease may already have lost its
+            // objects to another order, so it must not execute
+            if !self.holds_leases(&order.digest, &acquired_locks) {
+                println!("[FIXED] Order {} outlived its lease, aborting", order.digest);
+                self.release_locks(&order.digest, &acquired_locks);
+                return OrderResult::Conflict("lease expired".to_string());
+            }
+
             {
                 let mut objects = self.objects.write().unwrap();
                 for obj_id in &order.input_objects {
@@ -247,55 +735,261 @@ mod fixed {
                 }
             }
 
-            // Release locks
             self.release_locks(&order.digest, &acquired_locks);
-
             OrderResult::Success
         }
 
-        fn release_locks(&self, tx_digest: &str, obj_ids: &[ObjectId]) {
-            let mut locks = self.object_locks.lock().unwrap();
-            for obj_id in obj_ids {
-                if let Some(lock_entry) = locks.get_mut(obj_id) {
-                    if lock_entry.locked_by.as_ref().map(|d| d == tx_digest).unwrap_or(false) {
-                        lock_entry.locked_by = None;
-                        println!("[FIXED] Order {} released lock on {}", tx_digest, obj_id);
-
-                        // Wake up waiters
-                        for (_, waiter) in lock_entry.waiters.drain(..) {
-                            let (lock, cvar) = &*waiter;
-                            *lock.lock().unwrap() = true;
-                            cvar.notify_one();
+        /// Take `order`'s locks and never release them, as a holder that
+        /// crashed mid-order would. With a lease the locks expire; without
+        /// one they are held for good.
+        pub fn crash_holding(&self, order: &Order, lease: Option<Duration>) {
+            match lease {
+                Some(lease) => {
+                    self.acquire_leased(&order.digest, &order.input_objects, lease);
+                }
+                None => {
+                    self.acquire_locks(&order.digest, &order.input_objects, &HashMap::new(), Duration::ZERO);
+                }
+            }
+            println!("[FIXED] Order {} crashed while holding its locks", order.digest);
+        }
+
+        /// Acquire every lock under a lease, waiting until each is released
+        /// or its lease is reaped
+        fn acquire_leased(&self, digest: &str, obj_ids: &[ObjectId], lease: Duration) -> Vec<ObjectId> {
+            let mut acquired_locks = Vec::new();
+            let mut obj_ids = obj_ids.to_vec();
+            if self.lock_order == LockOrder::Sorted {
+                obj_ids.sort();
+            }
+
+            for obj_id in &obj_ids {
+                loop {
+                    let waiter = {
+                        let mut locks = self.object_locks.lock().unwrap();
+                        let lock_entry = locks.get_mut(obj_id).unwrap();
+                        if lock_entry.lease_expired(Instant::now()) {
+                            println!("[FIXED] Lease of {:?} on {} expired, reclaiming",
+                                     lock_entry.locked_by, obj_id);
+                            self.leases_reaped.fetch_add(1, Ordering::SeqCst);
+                            lock_entry.free();
                         }
+
+                        if lock_entry.is_free() {
+                            lock_entry.locked_by = Some(digest.to_string());
+                            lock_entry.lease_expires = Some(Instant::now() + lease);
+                            acquired_locks.push(obj_id.clone());
+                            println!("[FIXED] Order {} acquired lease on {}", digest, obj_id);
+                            break;
+                        }
+
+                        println!("[FIXED] Order {} waiting for {} (leased by {:?})",
+                                 digest, obj_id, lock_entry.locked_by);
+                        let pair = Arc::new((Mutex::new(false), Condvar::new()));
+                        lock_entry.waiters.push((digest.to_string(), Arc::clone(&pair)));
+                        pair
+                    };
+
+                    let (lock, cvar) = &*waiter;
+                    let _released = cvar.wait_while(lock.lock().unwrap(), |released| !*released).unwrap();
+                }
+            }
+
+            acquired_locks
+        }
+
+        /// Whether `digest` still holds every lock in `obj_ids` with a lease
+        /// that has not run out
+        fn holds_leases(&self, digest: &str, obj_ids: &[ObjectId]) -> bool {
+            let locks = self.object_locks.lock().unwrap();
+            let now = Instant::now();
+            obj_ids.iter().all(|obj_id| {
+                locks.get(obj_id).is_some_and(|entry| {
+                    entry.locked_by.as_deref() == Some(digest) && !entry.lease_expired(now)
+                })
+            })
+        }
+
+        /// Free every lock whose lease has run out and wake its waiters.
+        /// Returns how many were reclaimed.
+        pub fn reap_expired_leases(&self) -> usize {
+            let mut locks = self.object_locks.lock().unwrap();
+            let now = Instant::now();
+            let mut reaped = 0;
+            for (obj_id, lock_entry) in locks.iter_mut() {
+                if lock_entry.lease_expired(now) {
+                    println!("[REAPER] Lease of {:?} on {} expired, reclaiming", lock_entry.locked_by, obj_id);
+                    lock_entry.free();
+                    reaped += 1;
+                }
+            }
+            self.leases_reaped.fetch_add(reaped, Ordering::SeqCst);
+            reaped
+        }
+
+        /// Reap expired leases every `interval` on a background thread. The
+        /// thread holds only a weak reference and exits once the authority
+        /// is dropped.
+        pub fn start_reaper(self: &Arc<Self>, interval: Duration) -> thread::JoinHandle<()> {
+            let authority: Weak<Self> = Arc::downgrade(self);
+            thread::spawn(move || loop {
+                thread::sleep(interval);
+                match authority.upgrade() {
+                    Some(authority) => {
+                        authority.reap_expired_leases();
                     }
+                    None => break,
+                }
+            })
+        }
+
+        pub fn leases_reaped(&self) -> usize {
+            self.leases_reaped.load(Ordering::SeqCst)
+        }
+
+        /// Orders holding `obj_id` in `Shared` mode
+        pub fn shared_holders(&self, obj_id: &str) -> Vec<TxDigest> {
+            self.object_locks.lock().unwrap().get(obj_id).map_or(Vec::new(), |entry| entry.shared_holders.clone())
+        }
+
+        /// The order holding `obj_id`'s lock, if any
+        pub fn lock_holder(&self, obj_id: &str) -> Option<TxDigest> {
+            self.object_locks.lock().unwrap().get(obj_id).and_then(|entry| entry.locked_by.clone())
+        }
+
+        /// Wake every order waiting on `obj_id` without releasing it, as a
+        /// spurious wakeup would
+        #[cfg(test)]
+        pub fn wake_waiters_spuriously(&self, obj_id: &str) {
+            let locks = self.object_locks.lock().unwrap();
+            if let Some(lock_entry) = locks.get(obj_id) {
+                for (_, waiter) in &lock_entry.waiters {
+                    let (lock, cvar) = &**waiter;
+                    let _released = lock.lock().unwrap();
+                    cvar.notify_all();
                 }
             }
         }
 
+        #[cfg(test)]
+        pub fn wakeups(&self) -> usize {
+            self.wakeups.load(Ordering::SeqCst)
+        }
+
+        /// Waiters registered on `obj_id`. A waiter that treats a spurious
+        /// wakeup as a release re-registers, so this grows.
+        pub fn waiter_count(&self, obj_id: &str) -> usize {
+            self.object_locks.lock().unwrap().get(obj_id).map_or(0, |entry| entry.waiters.len())
+        }
+
         pub fn get_object_holder(&self, obj_id: &str) -> Option<TxDigest> {
             self.objects.read().unwrap()
                 .get(obj_id)
                 .and_then(|obj| obj.locked_by.clone())
         }
+
+        pub fn get_owner(&self, obj_id: &str) -> Option<String> {
+            self.objects.read().unwrap()
+                .get(obj_id)
+                .map(|obj| obj.owner.clone())
+        }
+    }
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
     }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let use_ownership = args.iter().any(|arg| arg == "--ownership");
+    let format_dot = args
+        .iter()
+        .position(|arg| arg == "--format")
+        .and_then(|i| args.get(i + 1))
+        .is_some_and(|format| format == "dot");
+
+    let parse_objects = |name: &str, default: &str| -> Vec<ObjectId> {
+        let list = args
+            .iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .map_or(default, |v| v.as_str());
+        list.split(',').filter(|id| !id.is_empty()).map(str::to_string).collect()
+    };
 
     println!("=== Sui Issue #335: Absence of Proper Locking ===\n");
 
-    if use_fixed {
+    if args.iter().any(|arg| arg == "--order1" || arg == "--order2") {
+        let order1 = parse_objects("--order1", DEFAULT_ORDER1);
+        let order2 = parse_objects("--order2", DEFAULT_ORDER2);
+        if order1.is_empty() || order2.is_empty() {
+            eprintln!("--order1 and --order2 need at least one object id each");
+            std::process::exit(2);
+        }
+        println!("Running FIXED version with both lock orders (multi-object orders)...\n");
+        run_lock_order_test(order1, order2);
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "--shared") {
+        println!("Running FIXED version with shared readers and an exclusive writer...\n");
+        run_shared_access_test();
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "--contention") {
+        println!("Running FIXED version with orders queueing on one object...\n");
+        run_contention_test();
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "--try-all") {
+        println!("Running FIXED version with try-all-or-back-off locking under contention...\n");
+        run_try_all_stress_test();
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "--lease") {
+        println!("Running FIXED version with a crashed lock holder (wait timeout vs lease)...\n");
+        run_lease_test();
+        return;
+    }
+
+    if use_ownership {
+        println!("Running FIXED version (ownership and transfer)...\n");
+        run_ownership_test();
+    } else if use_fixed {
         println!("Running FIXED version (proper locking)...\n");
         run_fixed_test();
     } else {
         println!("Running BUGGY version (no locking, allows conflicts)...\n");
-        run_buggy_test();
+        run_buggy_test(format_dot);
     }
 }
 
-fn run_buggy_test() {
+fn run_buggy_test(format_dot: bool) {
     let authority = Arc::new(buggy::Authority::new());
 
     // Create a shared object
@@ -309,11 +1003,13 @@ fn run_buggy_test() {
 
     let order1 = Order {
         digest: "order_001".to_string(),
+        sender: "alice".to_string(),
         input_objects: vec!["obj_001".to_string()],
     };
 
     let order2 = Order {
         digest: "order_002".to_string(),
+        sender: "alice".to_string(),
         input_objects: vec!["obj_001".to_string()],
     };
 
@@ -344,12 +1040,19 @@ fn run_buggy_test() {
         && matches!(result2, OrderResult::Success);
 
     if both_succeeded {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Both conflicting orders succeeded!");
         println!("\nProblem:");
         println!("  - No locking mechanism to prevent conflicts");
         println!("  - Both orders executed on the same object");
         println!("  - Last writer wins (non-deterministic)");
+        if format_dot {
+            if let Some(dot) = authority.conflict_dot() {
+                println!("\n=== Contention Graph (DOT) ===");
+                print!("{}", dot);
+            }
+        }
         println!("\nRun with --fixed to see proper locking.");
     }
 }
@@ -367,11 +1070,13 @@ fn run_fixed_test() {
 
     let order1 = Order {
         digest: "order_001".to_string(),
+        sender: "alice".to_string(),
         input_objects: vec!["obj_001".to_string()],
     };
 
     let order2 = Order {
         digest: "order_002".to_string(),
+        sender: "alice".to_string(),
         input_objects: vec!["obj_001".to_string()],
     };
 
@@ -381,11 +1086,11 @@ fn run_fixed_test() {
     let timeout = Duration::from_secs(2);
 
     let h1 = thread::spawn(move || {
-        auth1.handle_order(&order1, timeout)
+        auth1.handle_order(&order1, &HashMap::new(), timeout)
     });
 
     let h2 = thread::spawn(move || {
-        auth2.handle_order(&order2, timeout)
+        auth2.handle_order(&order2, &HashMap::new(), timeout)
     });
 
     let result1 = h1.join().unwrap();
@@ -404,15 +1109,517 @@ fn run_fixed_test() {
         && matches!(result2, OrderResult::Success);
 
     if both_succeeded {
-        println!("\n[FIXED - Sequential Execution]");
+        println!();
+        print_banner("[FIXED - Sequential Execution]");
         println!("Both orders succeeded sequentially!");
         println!("\nFix: Proper locking ensures serial execution");
         println!("  - First order acquires lock");
         println!("  - Second order waits for lock");
         println!("  - Orders execute one at a time");
     } else if one_succeeded {
-        println!("\n[FIXED]");
+        println!();
+        print_banner("[FIXED]");
         println!("One order succeeded, one blocked/failed!");
         println!("\nFix: Proper locking prevents conflicts");
     }
 }
+
+/// Poll `condition` every millisecond, panicking if it does not hold within
+/// two seconds
+fn wait_until(what: &str, condition: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while !condition() {
+        assert!(Instant::now() < deadline, "timed out waiting until {}", what);
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn run_ownership_test() {
+    let authority = Arc::new(fixed::Authority::new());
+    let timeout = Duration::from_secs(2);
+
+    authority.add_object(OwnedObject {
+        id: "obj_001".to_string(),
+        owner: "alice".to_string(),
+        locked_by: None,
+    });
+
+    println!("Scenario 1: bob submits an order on alice's object");
+    let non_owner = authority.handle_order(&Order {
+        digest: "order_bob".to_string(),
+        sender: "bob".to_string(),
+        input_objects: vec!["obj_001".to_string()],
+    }, &HashMap::new(), timeout);
+
+    println!("\nScenario 2: alice submits an order on her own object");
+    let owner = authority.handle_order(&Order {
+        digest: "order_alice".to_string(),
+        sender: "alice".to_string(),
+        input_objects: vec!["obj_001".to_string()],
+    }, &HashMap::new(), timeout);
+
+    println!("\nScenario 3: alice transfers obj_001 to bob while another alice order arrives");
+    let auth1 = Arc::clone(&authority);
+    let auth2 = Arc::clone(&authority);
+    let transfer = thread::spawn(move || auth1.transfer("obj_001", "bob", timeout));
+    // Submit the order only once the transfer holds the object lock, and
+    // check that it queues behind it
+    wait_until("the transfer holds obj_001", || {
+        authority.lock_holder("obj_001").as_deref() == Some("transfer_obj_001_to_bob")
+    });
+    let racing_order = thread::spawn(move || {
+        auth2.handle_order(&Order {
+            digest: "order_alice_late".to_string(),
+            sender: "alice".to_string(),
+            input_objects: vec!["obj_001".to_string()],
+        }, &HashMap::new(), timeout)
+    });
+    wait_until("the racing order waits for obj_001", || authority.waiter_count("obj_001") == 1);
+    let transfer = transfer.join().unwrap();
+    let racing_order = racing_order.join().unwrap();
+
+    println!("\n=== Results ===");
+    println!("Non-owner order: {:?}", non_owner);
+    println!("Owner order: {:?}", owner);
+    println!("Transfer: {:?}", transfer);
+    println!("Order racing the transfer: {:?}", racing_order);
+    println!("Final owner: {:?}", authority.get_owner("obj_001"));
+
+    let rejected = |r: &OrderResult| matches!(r, OrderResult::Conflict(reason) if reason == "not owner");
+    assert!(rejected(&non_owner), "bob's order on alice's object was not rejected: {:?}", non_owner);
+    assert!(matches!(owner, OrderResult::Success), "alice's own order failed: {:?}", owner);
+    assert!(matches!(transfer, OrderResult::Success), "transfer failed: {:?}", transfer);
+    assert!(rejected(&racing_order), "order racing the transfer was not rejected: {:?}", racing_order);
+    assert_eq!(authority.get_owner("obj_001").as_deref(), Some("bob"));
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Ownership enforced under the object lock!");
+    println!("  - Non-owner orders are rejected");
+    println!("  - Transfer holds the lock, so the racing order waits");
+    println!("  - After the transfer, alice's stale order is no longer valid");
+}
+
+const DEFAULT_ORDER1: &str = "obj_a,obj_b";
+const DEFAULT_ORDER2: &str = "obj_b,obj_a";
+/// How long each order waits for its locks in the multi-object scenario
+const LOCK_ORDER_TIMEOUT: Duration = Duration::from_millis(500);
+/// Pause after each lock so the two orders interleave their acquisitions
+const LOCK_ORDER_STEP: Duration = Duration::from_millis(20);
+
+/// Whether some pair of objects appears in both lists in opposite order,
+/// which lets unsorted acquisition deadlock
+fn has_reversed_overlap(order1: &[ObjectId], order2: &[ObjectId]) -> bool {
+    let position = |list: &[ObjectId], id: &ObjectId| list.iter().position(|x| x == id);
+    order1.iter().enumerate().any(|(i, a)| {
+        order1[i + 1..].iter().any(|b| match (position(order2, a), position(order2, b)) {
+            (Some(pa), Some(pb)) => pb < pa,
+            _ => false,
+        })
+    })
+}
+
+/// Submit both orders at once against a fresh authority and return their
+/// results
+fn run_order_pair(
+    lock_order: fixed::LockOrder,
+    order1: &[ObjectId],
+    order2: &[ObjectId],
+) -> (OrderResult, OrderResult) {
+    let authority = Arc::new(fixed::Authority::with_lock_order(lock_order, LOCK_ORDER_STEP));
+    let mut objects: Vec<&ObjectId> = order1.iter().chain(order2).collect();
+    objects.sort();
+    objects.dedup();
+    for id in objects {
+        authority.add_object(OwnedObject {
+            id: id.clone(),
+            owner: "alice".to_string(),
+            locked_by: None,
+        });
+    }
+
+    let submit = |digest: &str, input_objects: &[ObjectId]| {
+        let authority = Arc::clone(&authority);
+        let order = Order {
+            digest: digest.to_string(),
+            sender: "alice".to_string(),
+            input_objects: input_objects.to_vec(),
+        };
+        thread::spawn(move || authority.handle_order(&order, &HashMap::new(), LOCK_ORDER_TIMEOUT))
+    };
+    let h1 = submit("order_001", order1);
+    let h2 = submit("order_002", order2);
+    (h1.join().unwrap(), h2.join().unwrap())
+}
+
+fn run_lock_order_test(order1: Vec<ObjectId>, order2: Vec<ObjectId>) {
+    println!("order_001 objects: {:?}", order1);
+    println!("order_002 objects: {:?}", order2);
+    println!("Lock wait timeout: {:?}\n", LOCK_ORDER_TIMEOUT);
+
+    println!("--- AS GIVEN (unsorted acquisition) ---");
+    let as_given = run_order_pair(fixed::LockOrder::AsGiven, &order1, &order2);
+    println!("\n--- SORTED (default, acquisition by ObjectId) ---");
+    assert_eq!(fixed::LockOrder::default(), fixed::LockOrder::Sorted);
+    let sorted = run_order_pair(fixed::LockOrder::default(), &order1, &order2);
+
+    let timeouts = |(r1, r2): &(OrderResult, OrderResult)| {
+        [r1, r2].iter().filter(|r| matches!(r, OrderResult::Blocked)).count()
+    };
+    let reversed = has_reversed_overlap(&order1, &order2);
+
+    println!("\n=== Results ===");
+    println!("[AS GIVEN] order_001: {:?}, order_002: {:?}", as_given.0, as_given.1);
+    println!("[SORTED]   order_001: {:?}, order_002: {:?}", sorted.0, sorted.1);
+
+    println!("\n=== Metrics ===");
+    println!("reversed_overlap: {}", reversed);
+    println!("as_given_timeouts: {}", timeouts(&as_given));
+    println!("sorted_timeouts: {}", timeouts(&sorted));
+
+    assert_eq!(timeouts(&sorted), 0, "sorted lock acquisition timed out");
+    assert!(
+        matches!(sorted, (OrderResult::Success, OrderResult::Success)),
+        "with the default lock order both orders should succeed, one after the other"
+    );
+    if reversed {
+        assert!(
+            timeouts(&as_given) > 0,
+            "reversed overlap should deadlock until the lock wait times out"
+        );
+    }
+
+    if timeouts(&as_given) > 0 {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("Each order held one object and waited for the other's until one timed out.");
+        print_banner("[FIXED]");
+        println!("Sorted acquisition takes shared objects in one global order, so the");
+        println!("second order waits on the first object and both complete.");
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("No object pair is listed in opposite order, so neither run can deadlock.");
+        println!("Try --order1 obj_a,obj_b --order2 obj_b,obj_a.");
+    }
+}
+
+/// Lease granted to the crashed holder and to the order waiting behind it
+const LEASE: Duration = Duration::from_millis(150);
+const REAPER_INTERVAL: Duration = Duration::from_millis(10);
+/// Wait timeout for the plain-lock order stuck behind a crashed holder
+const CRASH_WAIT_TIMEOUT: Duration = Duration::from_millis(200);
+
+fn run_lease_test() {
+    let authority = Arc::new(fixed::Authority::new());
+    for id in ["obj_001", "obj_002"] {
+        authority.add_object(OwnedObject {
+            id: id.to_string(),
+            owner: "alice".to_string(),
+            locked_by: None,
+        });
+    }
+    let reaper = authority.start_reaper(REAPER_INTERVAL);
+    let order = |digest: &str, obj_id: &str| Order {
+        digest: digest.to_string(),
+        sender: "alice".to_string(),
+        input_objects: vec![obj_id.to_string()],
+    };
+
+    println!("Scenario 1: plain lock, holder crashes, next order waits with a timeout");
+    authority.crash_holding(&order("order_crashed_1", "obj_001"), None);
+    let timed_out: Vec<OrderResult> = (0..2)
+        .map(|_| authority.handle_order(&order("order_002", "obj_001"), &HashMap::new(), CRASH_WAIT_TIMEOUT))
+        .collect();
+
+    println!("\nScenario 2: leased lock, holder crashes, next order waits for the lease");
+    authority.crash_holding(&order("order_crashed_2", "obj_002"), Some(LEASE));
+    let started = Instant::now();
+    let leased = authority.handle_order_leased(&order("order_003", "obj_002"), LEASE);
+    let waited = started.elapsed();
+
+    println!("\n=== Results ===");
+    println!("[WAIT TIMEOUT] order_002 attempts: {:?}", timed_out);
+    println!("[WAIT TIMEOUT] obj_001 still held by: {:?}", authority.lock_holder("obj_001"));
+    println!("[LEASE] order_003: {:?} after {:?}", leased, waited);
+    println!("[LEASE] obj_002 held by: {:?}", authority.lock_holder("obj_002"));
+
+    println!("\n=== Metrics ===");
+    println!("lease_ms: {}", LEASE.as_millis());
+    println!("wait_timeout_attempts_blocked: {}", timed_out.iter().filter(|r| matches!(r, OrderResult::Blocked)).count());
+    println!("leases_reaped: {}", authority.leases_reaped());
+    println!("leased_wait_ms: {}", waited.as_millis());
+
+    assert!(
+        timed_out.iter().all(|r| matches!(r, OrderResult::Blocked)),
+        "a crashed plain-lock holder should block every attempt"
+    );
+    assert_eq!(authority.lock_holder("obj_001").as_deref(), Some("order_crashed_1"));
+    assert!(matches!(leased, OrderResult::Success), "the waiter should acquire obj_002 after the lease");
+    assert!(waited >= LEASE.saturating_sub(Duration::from_millis(50)), "order_003 got obj_002 before the lease ran out");
+    assert_eq!(authority.leases_reaped(), 1, "exactly the crashed holder's lease should be reaped");
+    assert_eq!(authority.lock_holder("obj_002"), None);
+
+    drop(authority);
+    reaper.join().unwrap();
+
+    println!();
+    print_banner("[FIXED]");
+    println!("A wait timeout only turns a crashed holder into repeated Blocked results;");
+    println!("the lock itself is never recovered. A lease bounds how long any holder");
+    println!("can keep an object, so the reaper reclaimed obj_002 and order_003 ran.");
+}
+
+const STRESS_ORDERS: usize = 16;
+const STRESS_POOL: [&str; 4] = ["obj_a", "obj_b", "obj_c", "obj_d"];
+const TRY_ALL_ATTEMPTS: usize = 500;
+/// Every order must have returned by then; a thread still running is hung
+const STRESS_BOUND: Duration = Duration::from_secs(10);
+
+/// Many two-object orders over a four-object pool, half of them listing
+/// their pair in reverse, all submitted at once through `handle_order_try_all`
+fn run_try_all_stress_test() {
+    let authority = Arc::new(fixed::Authority::new());
+    for id in STRESS_POOL {
+        authority.add_object(OwnedObject {
+            id: id.to_string(),
+            owner: "alice".to_string(),
+            locked_by: None,
+        });
+    }
+
+    println!("{} orders over {:?}, up to {} attempts each\n", STRESS_ORDERS, STRESS_POOL, TRY_ALL_ATTEMPTS);
+
+    let started = Instant::now();
+    let mut handles: Vec<_> = (0..STRESS_ORDERS)
+        .map(|i| {
+            let first = STRESS_POOL[(i / 2) % STRESS_POOL.len()];
+            let second = STRESS_POOL[(i / 2 + 1) % STRESS_POOL.len()];
+            let input_objects = if i % 2 == 0 { [first, second] } else { [second, first] };
+            let order = Order {
+                digest: format!("order_{:03}", i),
+                sender: "alice".to_string(),
+                input_objects: input_objects.iter().map(|id| id.to_string()).collect(),
+            };
+            let authority = Arc::clone(&authority);
+            Some(thread::spawn(move || authority.handle_order_try_all(&order, &HashMap::new(), TRY_ALL_ATTEMPTS)))
+        })
+        .collect();
+
+    // Poll instead of joining so a hung order is reported, not waited on
+    let mut results = Vec::new();
+    while started.elapsed() < STRESS_BOUND && results.len() < STRESS_ORDERS {
+        for handle in handles.iter_mut() {
+            if handle.as_ref().is_some_and(|h| h.is_finished()) {
+                results.push(handle.take().unwrap().join().unwrap());
+            }
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    let elapsed = started.elapsed();
+    let hung = handles.iter().filter(|handle| handle.is_some()).count();
+
+    let succeeded = results.iter().filter(|r| matches!(r, OrderResult::Success)).count();
+    let blocked = results.iter().filter(|r| matches!(r, OrderResult::Blocked)).count();
+
+    println!("\n=== Results ===");
+    println!("Orders finished: {}/{}", results.len(), STRESS_ORDERS);
+    println!("Succeeded: {}, gave up: {}", succeeded, blocked);
+    println!("Locks still held: {:?}",
+             STRESS_POOL.iter().filter_map(|id| authority.lock_holder(id)).collect::<Vec<_>>());
+
+    println!("\n=== Metrics ===");
+    println!("orders: {}", STRESS_ORDERS);
+    println!("succeeded: {}", succeeded);
+    println!("blocked: {}", blocked);
+    println!("hung: {}", hung);
+    println!("retries: {}", authority.try_all_retries());
+    println!("elapsed_ms: {}", elapsed.as_millis());
+
+    assert_eq!(hung, 0, "{} orders still running after {:?}", hung, STRESS_BOUND);
+    assert_eq!(succeeded + blocked, STRESS_ORDERS, "unexpected order results: {:?}", results);
+    assert!(
+        STRESS_POOL.iter().all(|id| authority.lock_holder(id).is_none()),
+        "a finished order left a lock behind"
+    );
+
+    println!();
+    print_banner("[FIXED]");
+    println!("No order ever waited while holding a lock, so no wait cycle could form.");
+    println!("Randomized backoff kept colliding orders from retrying in lockstep.");
+}
+
+const CONTENTION_ORDERS: usize = 5;
+/// Head start for the first order, well inside its 50ms of processing, so
+/// every later order finds obj_001 held
+const CONTENTION_HEAD_START: Duration = Duration::from_millis(10);
+
+fn run_contention_test() {
+    let authority = Arc::new(fixed::Authority::new());
+    authority.add_object(OwnedObject {
+        id: "obj_001".to_string(),
+        owner: "alice".to_string(),
+        locked_by: None,
+    });
+    assert_eq!(authority.lock_stats(), (0, Duration::ZERO));
+
+    let submit = |i: usize| {
+        let authority = Arc::clone(&authority);
+        let order = Order {
+            digest: format!("order_{:03}", i),
+            sender: "alice".to_string(),
+            input_objects: vec!["obj_001".to_string()],
+        };
+        thread::spawn(move || authority.handle_order(&order, &HashMap::new(), Duration::from_secs(2)))
+    };
+
+    let mut handles = vec![submit(0)];
+    thread::sleep(CONTENTION_HEAD_START);
+    handles.extend((1..CONTENTION_ORDERS).map(submit));
+    let results: Vec<OrderResult> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    let queued = (CONTENTION_ORDERS - 1) as u64;
+    let (waits, wait_time) = authority.lock_stats();
+
+    println!("\n=== Results ===");
+    println!("Results: {:?}", results);
+
+    println!("\n=== Metrics ===");
+    println!("orders: {}", CONTENTION_ORDERS);
+    println!("queued_orders: {}", queued);
+    println!("lock_waits: {}", waits);
+    println!("lock_wait_ms: {}", wait_time.as_millis());
+
+    assert!(results.iter().all(|r| matches!(r, OrderResult::Success)), "an order failed: {:?}", results);
+    assert_eq!(waits, queued, "every queued order should be counted exactly once");
+    assert!(wait_time > Duration::ZERO, "queued orders recorded no wait time");
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Every order that queued behind obj_001's lock was counted once in lock_stats,");
+    println!("even if a release woke it and another waiter took the lock first.");
+}
+
+/// When the writer arrives, and when the holders are sampled; both fall
+/// inside the readers' 50ms of processing
+const WRITER_DELAY: Duration = Duration::from_millis(10);
+const SHARED_SAMPLE_AT: Duration = Duration::from_millis(25);
+
+/// Two readers take `obj_001` in `Shared` mode at once, and a writer asks for
+/// it `Exclusive` while they hold it
+fn run_shared_access_test() {
+    use fixed::LockMode;
+
+    let authority = Arc::new(fixed::Authority::new());
+    authority.add_object(OwnedObject {
+        id: "obj_001".to_string(),
+        owner: "alice".to_string(),
+        locked_by: None,
+    });
+
+    let submit = |digest: &str, mode: LockMode, delay: Duration| {
+        let authority = Arc::clone(&authority);
+        let order = Order {
+            digest: digest.to_string(),
+            sender: "alice".to_string(),
+            input_objects: vec!["obj_001".to_string()],
+        };
+        let modes: HashMap<ObjectId, LockMode> = [("obj_001".to_string(), mode)].into_iter().collect();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            let result = authority.handle_order(&order, &modes, Duration::from_secs(2));
+            (result, Instant::now())
+        })
+    };
+
+    let reader1 = submit("reader_1", LockMode::Shared, Duration::ZERO);
+    let reader2 = submit("reader_2", LockMode::Shared, Duration::ZERO);
+    let writer = submit("writer", LockMode::Exclusive, WRITER_DELAY);
+
+    thread::sleep(SHARED_SAMPLE_AT);
+    let mut readers_at_sample = authority.shared_holders("obj_001");
+    readers_at_sample.sort();
+    let writer_holding_at_sample = authority.lock_holder("obj_001");
+    let writer_waiting_at_sample = authority.waiter_count("obj_001");
+
+    let (reader1, reader1_done) = reader1.join().unwrap();
+    let (reader2, reader2_done) = reader2.join().unwrap();
+    let (writer, writer_done) = writer.join().unwrap();
+    let (waits, _) = authority.lock_stats();
+
+    println!("\n=== Results ===");
+    println!("reader_1: {:?}, reader_2: {:?}, writer: {:?}", reader1, reader2, writer);
+    println!("Shared holders at {:?}: {:?}", SHARED_SAMPLE_AT, readers_at_sample);
+    println!("Exclusive holder at {:?}: {:?}", SHARED_SAMPLE_AT, writer_holding_at_sample);
+
+    println!("\n=== Metrics ===");
+    println!("concurrent_readers: {}", readers_at_sample.len());
+    println!("writer_waiters: {}", writer_waiting_at_sample);
+    println!("lock_waits: {}", waits);
+
+    assert!(
+        [&reader1, &reader2, &writer].iter().all(|r| matches!(r, OrderResult::Success)),
+        "every order should succeed"
+    );
+    assert_eq!(readers_at_sample, ["reader_1", "reader_2"], "the readers did not hold obj_001 together");
+    assert_eq!(writer_holding_at_sample, None, "the writer took obj_001 while readers held it");
+    assert_eq!(writer_waiting_at_sample, 1, "the writer should be queued behind the readers");
+    assert!(
+        writer_done > reader1_done && writer_done > reader2_done,
+        "the writer finished before both readers released"
+    );
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Both readers held obj_001 at the same time in Shared mode.");
+    println!("The Exclusive writer waited until the last reader released, then ran.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// How long `order_001` holds `obj_001` while `order_002` waits for it
+    const HOLD: Duration = Duration::from_millis(300);
+    const SPURIOUS_WAKEUPS: usize = 5;
+
+    /// Spurious wakeups leave the waiter registered and blocked until the
+    /// holder really releases
+    #[test]
+    fn spurious_wakeups_go_back_to_waiting() {
+        let authority = Arc::new(fixed::Authority::with_lock_order(fixed::LockOrder::AsGiven, HOLD));
+        authority.add_object(OwnedObject {
+            id: "obj_001".to_string(),
+            owner: "alice".to_string(),
+            locked_by: None,
+        });
+        let submit = |digest: &str| {
+            let authority = Arc::clone(&authority);
+            let order = Order {
+                digest: digest.to_string(),
+                sender: "alice".to_string(),
+                input_objects: vec!["obj_001".to_string()],
+            };
+            thread::spawn(move || authority.handle_order(&order, &HashMap::new(), Duration::from_secs(2)))
+        };
+
+        let h1 = submit("order_001");
+        wait_until("order_001 holds obj_001", || authority.lock_holder("obj_001").as_deref() == Some("order_001"));
+        let h2 = submit("order_002");
+        wait_until("order_002 waits for obj_001", || authority.waiter_count("obj_001") == 1);
+
+        for _ in 0..SPURIOUS_WAKEUPS {
+            let before = authority.wakeups();
+            wait_until("order_002 counts a wakeup", || {
+                authority.wake_waiters_spuriously("obj_001");
+                authority.wakeups() > before
+            });
+            assert_eq!(authority.lock_holder("obj_001").as_deref(), Some("order_001"), "obj_001 was not released");
+            assert_eq!(authority.waiter_count("obj_001"), 1, "order_002 re-registered after a spurious wakeup");
+            assert!(!h2.is_finished(), "order_002 stopped waiting on a spurious wakeup");
+        }
+
+        assert!(matches!(h1.join().unwrap(), OrderResult::Success));
+        assert!(matches!(h2.join().unwrap(), OrderResult::Success), "order_002 must get the lock once it is released");
+    }
+}
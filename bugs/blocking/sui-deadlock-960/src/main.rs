@@ -25,6 +25,32 @@ pub enum TransactionError {
     ObjectLocked(ObjectId),
 }
 
+/// Wait-for graph snapshot, exported as Graphviz DOT with `--format dot`
+///
+/// Each crate builds on its own, so this is copied into ballista-deadlock-132,
+/// reth-deadlock-10842, sui-blocking-5201, sui-deadlock-335, sui-deadlock-960
+/// and sui-race-4990. Keep the copies identical; reth-deadlock-10842 also
+/// has `remove_edge`.
+#[derive(Debug, Default)]
+pub struct WaitForGraph {
+    edges: Vec<(String, String, String)>,
+}
+
+impl WaitForGraph {
+    fn add_edge(&mut self, from: &str, to: &str, label: &str) {
+        self.edges.push((from.to_string(), to.to_string(), label.to_string()));
+    }
+
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph {} {{\n", name);
+        for (from, to, label) in &self.edges {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", from, to, label));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
 /// Tracks which transaction has locked which objects
 pub struct ObjectLockManager {
     locked_objects: Mutex<HashMap<ObjectId, TransactionId>>,
@@ -77,6 +103,49 @@ impl ObjectLockManager {
         let locked = self.locked_objects.lock().unwrap();
         locked.contains_key(obj)
     }
+
+    /// Snapshot which of `objects` would block `waiter`, and who holds them
+    fn wait_for_graph(&self, waiter: &TransactionId, objects: &[ObjectId]) -> WaitForGraph {
+        let locked = self.locked_objects.lock().unwrap();
+        let mut graph = WaitForGraph::default();
+        for obj in objects {
+            if let Some(holder) = locked.get(obj) {
+                if holder != waiter {
+                    graph.add_edge(&waiter.0, &obj.0, "waits for");
+                    graph.add_edge(&obj.0, &holder.0, "held by");
+                }
+            }
+        }
+        graph
+    }
+}
+
+/// Runs a closure when dropped, like `scopeguard::defer!`. The closure runs on
+/// every exit from the enclosing scope: normal return, early return, `?` and
+/// unwinding panics.
+pub struct Defer<F: FnOnce()> {
+    action: Option<F>,
+}
+
+impl<F: FnOnce()> Defer<F> {
+    pub fn new(action: F) -> Self {
+        Self { action: Some(action) }
+    }
+}
+
+impl<F: FnOnce()> Drop for Defer<F> {
+    fn drop(&mut self) {
+        if let Some(action) = self.action.take() {
+            action();
+        }
+    }
+}
+
+/// `defer! { ... }` runs the block when the current scope ends
+macro_rules! defer {
+    ($($body:tt)*) => {
+        let _defer = Defer::new(|| { $($body)* });
+    };
 }
 
 /// Simulates network/execution failures
@@ -146,13 +215,37 @@ mod buggy {
 mod fixed {
     use super::*;
 
+    /// Invariant after every fixed transaction, checked in debug builds:
+    /// none of its objects is still locked
+    pub fn debug_assert_released(lock_manager: &ObjectLockManager, objects: &[ObjectId], tx_id: &TransactionId) {
+        debug_assert!(
+            objects.iter().all(|obj| !lock_manager.is_locked(obj)),
+            "fixed gateway left objects locked after transaction {:?}",
+            tx_id.0
+        );
+    }
+
     pub struct GatewayState {
         lock_manager: Arc<ObjectLockManager>,
+        /// `--inject-regression`: skip the unlock on the error path again
+        inject_regression: bool,
     }
 
     impl GatewayState {
         pub fn new(lock_manager: Arc<ObjectLockManager>) -> Self {
-            Self { lock_manager }
+            Self {
+                lock_manager,
+                inject_regression: false,
+            }
+        }
+
+        /// Fixed gateway with the original bug put back, to check that the
+        /// invariant checks notice it
+        pub fn with_injected_regression(lock_manager: Arc<ObjectLockManager>) -> Self {
+            Self {
+                lock_manager,
+                inject_regression: true,
+            }
         }
 
         /// FIX: Always unlock, even on error
@@ -169,8 +262,15 @@ mod fixed {
             // Execute transaction
             let result = simulate_transaction_execution(&tx_id);
 
+            if self.inject_regression && result.is_err() {
+                // REGRESSION: the buggy version's missing unlock
+                println!("[FIXED] Regression injected: skipping unlock on error");
+                return result;
+            }
+
             // FIX: Always unlock, regardless of result
             self.lock_manager.unlock_objects(&objects);
+            debug_assert_released(&self.lock_manager, &objects, &tx_id);
 
             match &result {
                 Ok(_) => println!("[FIXED] Transaction {:?} succeeded", tx_id.0),
@@ -185,22 +285,104 @@ mod fixed {
     }
 }
 
+/// Fixed gateway state - unlock registered with `defer!` right after locking
+mod fixed_defer {
+    use super::*;
+
+    pub struct GatewayState {
+        lock_manager: Arc<ObjectLockManager>,
+    }
+
+    impl GatewayState {
+        pub fn new(lock_manager: Arc<ObjectLockManager>) -> Self {
+            Self { lock_manager }
+        }
+
+        /// FIX: The deferred unlock runs however this function returns,
+        /// including the `?` on a failed execution
+        pub fn execute_transaction(
+            &self,
+            tx_id: TransactionId,
+            objects: Vec<ObjectId>,
+        ) -> Result<(), TransactionError> {
+            println!("[FIXED] Executing transaction {:?} (deferred unlock)", tx_id.0);
+
+            self.lock_manager.try_lock_objects(&objects, &tx_id)?;
+            defer! {
+                self.lock_manager.unlock_objects(&objects);
+            }
+
+            simulate_transaction_execution(&tx_id).inspect_err(|e| {
+                println!("[FIXED] Transaction {:?} failed: {:?}, returning early", tx_id.0, e);
+            })?;
+
+            println!("[FIXED] Transaction {:?} succeeded", tx_id.0);
+            Ok(())
+        }
+    }
+}
+
+// Every executor thread locks objects through one shared `ObjectLockManager`
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ObjectLockManager>();
+};
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let inject_regression = args.iter().any(|arg| arg == "--inject-regression");
+    let format_dot = args
+        .iter()
+        .position(|arg| arg == "--format")
+        .and_then(|i| args.get(i + 1))
+        .is_some_and(|format| format == "dot");
 
     println!("=== Sui Issue #960: Object Lock Deadlock ===\n");
 
-    if use_fixed {
+    if args.iter().any(|arg| arg == "--verify") {
+        println!("Checking that the fixed version's invariants catch an injected regression...\n");
+        run_verify();
+    } else if use_fixed && args.iter().any(|arg| arg == "--defer") {
+        println!("Running FIXED version (unlock deferred with defer!)...\n");
+        run_defer_test();
+    } else if use_fixed && inject_regression {
+        println!("Running FIXED version with the regression injected (no unlock on error)...\n");
+        run_fixed_test(true);
+    } else if use_fixed {
         println!("Running FIXED version (unlock on all paths)...\n");
-        run_fixed_test();
+        run_fixed_test(false);
     } else {
         println!("Running BUGGY version (missing unlock on error)...\n");
-        run_buggy_test();
+        run_buggy_test(format_dot);
     }
 }
 
-fn run_buggy_test() {
+fn run_buggy_test(format_dot: bool) {
     let lock_manager = Arc::new(ObjectLockManager::new());
     let gateway = Arc::new(buggy::GatewayState::new(Arc::clone(&lock_manager)));
 
@@ -223,6 +405,7 @@ fn run_buggy_test() {
     let tx2 = TransactionId("tx_2".to_string());
 
     println!("[BUGGY] Attempting transaction {:?}", tx2.0);
+    let wait_for = lock_manager.wait_for_graph(&tx2, std::slice::from_ref(&obj_a));
     match gateway.execute_transaction(tx2, vec![obj_a.clone()]) {
         Err(TransactionError::ObjectLocked(obj)) => {
             println!(
@@ -234,16 +417,27 @@ fn run_buggy_test() {
     }
 
     println!("\n=== Results ===");
-    println!("[BUG DEMONSTRATED]");
+    print_banner("[BUG DEMONSTRATED]");
     println!("First transaction failed and left object locked.");
     println!("Second transaction deadlocked trying to acquire the same lock.");
     println!("In production, this causes HTTP 424 errors: 'Client state has a different pending transaction'");
+    if format_dot {
+        println!("\n=== Wait-For Graph (DOT) ===");
+        print!("{}", wait_for.to_dot("sui_960"));
+    }
     println!("\nRun with --fixed to see proper unlock handling.");
 }
 
-fn run_fixed_test() {
+/// Run the fixed scenario and return the first invariant it broke, if any:
+/// the failed transaction must release its objects, and the next
+/// transaction on them must succeed
+fn run_fixed_scenario(inject_regression: bool) -> Option<String> {
     let lock_manager = Arc::new(ObjectLockManager::new());
-    let gateway = Arc::new(fixed::GatewayState::new(Arc::clone(&lock_manager)));
+    let gateway = if inject_regression {
+        fixed::GatewayState::with_injected_regression(Arc::clone(&lock_manager))
+    } else {
+        fixed::GatewayState::new(Arc::clone(&lock_manager))
+    };
 
     // Transaction 1: Will fail, but unlock properly
     let tx1 = TransactionId("tx_1_fail".to_string());
@@ -254,10 +448,11 @@ fn run_fixed_test() {
 
     thread::sleep(Duration::from_millis(100));
 
-    // Check if object is unlocked
-    if !lock_manager.is_locked(&obj_a) {
-        println!("\n[FIXED] Object {:?} properly unlocked", obj_a.0);
+    if lock_manager.is_locked(&obj_a) {
+        println!("\n[FIXED] ERROR: Object {:?} still locked after the failed transaction", obj_a.0);
+        return Some(format!("object {:?} still locked after the failed transaction", obj_a.0));
     }
+    println!("\n[FIXED] Object {:?} properly unlocked", obj_a.0);
 
     // Transaction 2: Should succeed now
     println!("\n--- Transaction 2 (should succeed) ---");
@@ -267,18 +462,100 @@ fn run_fixed_test() {
         Ok(_) => {
             println!("[FIXED] Transaction completed successfully!");
             println!("[FIXED] No deadlock - object was properly released");
-        }
-        Err(TransactionError::ObjectLocked(obj)) => {
-            println!("[FIXED] ERROR: Object {:?} still locked (should not happen)", obj.0);
+            None
         }
         Err(e) => {
             println!("[FIXED] Transaction failed with error: {:?}", e);
+            Some(format!("second transaction failed: {:?}", e))
         }
     }
+}
+
+fn run_fixed_test(inject_regression: bool) {
+    let violation = run_fixed_scenario(inject_regression);
+    assert!(violation.is_none(), "fixed version broke its invariant: {}", violation.unwrap());
 
     println!("\n=== Results ===");
-    println!("[FIXED]");
+    print_banner("[FIXED]");
     println!("First transaction failed but properly unlocked objects.");
     println!("Second transaction succeeded - no deadlock.");
     println!("Unlock is guaranteed on all code paths (success and error).");
 }
+
+/// Self-test of the unlock invariants: the fixed version must pass them, and
+/// the same version with the unlock skipped on error must not
+fn run_verify() {
+    println!("=== FIXED ===\n");
+    let clean = run_fixed_scenario(false);
+    println!("\n=== FIXED with --inject-regression ===\n");
+    let injected = run_fixed_scenario(true);
+
+    println!("\n=== Results ===");
+    println!("Without injection: {}", clean.as_deref().unwrap_or("invariants held"));
+    println!("With injection: {}", injected.as_deref().unwrap_or("invariants held"));
+
+    assert!(clean.is_none(), "fixed version broke its invariant without injection");
+    assert!(injected.is_some(), "injected regression went unnoticed by the invariants");
+
+    println!();
+    print_banner("[FIXED]");
+    println!("The invariants pass on the fixed version and catch the reintroduced missing unlock.");
+}
+
+/// The failed transaction leaves `execute_transaction` through `?` after
+/// locking, so only the deferred closure can release its objects
+fn run_defer_test() {
+    let lock_manager = Arc::new(ObjectLockManager::new());
+    let gateway = fixed_defer::GatewayState::new(Arc::clone(&lock_manager));
+    let obj_a = ObjectId("object_A".to_string());
+    let obj_b = ObjectId("object_B".to_string());
+
+    println!("--- Transaction 1 (will fail) ---");
+    let tx1 = TransactionId("tx_1_fail".to_string());
+    let result = gateway.execute_transaction(tx1, vec![obj_a.clone(), obj_b.clone()]);
+    assert!(
+        matches!(result, Err(TransactionError::NetworkError(_))),
+        "the execution error should propagate through `?`"
+    );
+    assert!(
+        !lock_manager.is_locked(&obj_a) && !lock_manager.is_locked(&obj_b),
+        "deferred unlock did not run on the `?` path"
+    );
+    println!("\n[FIXED] Objects {:?} and {:?} released by the deferred unlock", obj_a.0, obj_b.0);
+
+    println!("\n--- Transaction 2 (should succeed) ---");
+    let tx2 = TransactionId("tx_2".to_string());
+    let result = gateway.execute_transaction(tx2, vec![obj_a.clone()]);
+    assert!(result.is_ok(), "second transaction failed: {:?}", result);
+    assert!(!lock_manager.is_locked(&obj_a), "deferred unlock did not run on success");
+
+    println!("\n=== Results ===");
+    print_banner("[FIXED]");
+    println!("The failed transaction returned early with `?` and its objects were still released.");
+    println!("One defer! right after locking covers the success path and every early return.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_scenario_passes_its_debug_invariants() {
+        assert_eq!(run_fixed_scenario(false), None);
+    }
+
+    /// The original bug put back: the failed transaction skips its unlock,
+    /// and the invariant the fixed path checks fires
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "fixed gateway left objects locked")]
+    fn skipped_unlock_trips_the_debug_invariant() {
+        let lock_manager = Arc::new(ObjectLockManager::new());
+        let gateway = fixed::GatewayState::with_injected_regression(Arc::clone(&lock_manager));
+        let tx_id = TransactionId("tx_1_fail".to_string());
+        let objects = vec![ObjectId("object_A".to_string())];
+
+        assert!(gateway.execute_transaction(tx_id.clone(), objects.clone()).is_err());
+        fixed::debug_assert_released(&lock_manager, &objects, &tx_id);
+    }
+}
@@ -0,0 +1,438 @@
+//! ABA Problem: Lock-Free Stack Corruption
+//!
+//! This reproduces the ABA problem on a Treiber stack whose head is a single
+//! atomic word. A popper reads head A and its successor B, then stalls. In
+//! the meantime another thread pops A and B and pushes A back. The head is A
+//! again, so the stalled compare-and-swap succeeds and installs B, a node the
+//! other thread still owns.
+//!
+//! Pattern reproduction: not tied to a single upstream issue. The
+//! object-version races (sui-race-4597, sui-race-5754) avoid the same trap by
+//! comparing `ObjectRef { version }` rather than the value alone.
+
+use std::env;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+/// Marks the end of the stack
+const NIL: u32 = u32::MAX;
+const NODE_NAMES: [&str; 3] = ["A", "B", "C"];
+
+/// Fixed pool of nodes addressed by index, standing in for heap pointers.
+/// A popped index can be pushed again, just as a freed address gets reused.
+pub struct Arena {
+    next: Vec<AtomicU32>,
+}
+
+impl Arena {
+    fn new(len: usize) -> Self {
+        Self {
+            next: (0..len).map(|_| AtomicU32::new(NIL)).collect(),
+        }
+    }
+
+    fn next(&self, node: u32) -> u32 {
+        self.next[node as usize].load(Ordering::SeqCst)
+    }
+
+    fn set_next(&self, node: u32, next: u32) {
+        self.next[node as usize].store(next, Ordering::SeqCst);
+    }
+
+    fn len(&self) -> usize {
+        self.next.len()
+    }
+
+    /// Follow `next` links from `head`, stopping after `len` nodes in case
+    /// corruption produced a cycle
+    fn walk(&self, head: u32) -> Vec<u32> {
+        let mut nodes = Vec::new();
+        let mut node = head;
+        while node != NIL && nodes.len() < self.len() {
+            nodes.push(node);
+            node = self.next(node);
+        }
+        nodes
+    }
+}
+
+fn node_name(node: u32) -> &'static str {
+    NODE_NAMES.get(node as usize).copied().unwrap_or("nil")
+}
+
+fn names(nodes: &[u32]) -> Vec<&'static str> {
+    nodes.iter().map(|&node| node_name(node)).collect()
+}
+
+/// Stalls a pop between reading the head and its compare-and-swap, so the
+/// other thread's updates land inside that window every run
+pub struct RaceHook {
+    read_done: Barrier,
+    update_done: Barrier,
+}
+
+impl RaceHook {
+    fn new() -> Self {
+        Self {
+            read_done: Barrier::new(2),
+            update_done: Barrier::new(2),
+        }
+    }
+
+    /// Called by the popper once it has read head and next
+    fn after_read(&self) {
+        self.read_done.wait();
+        self.update_done.wait();
+    }
+
+    /// Run `update` while the popper is stalled before its CAS
+    fn run_update<T>(&self, update: impl FnOnce() -> T) -> T {
+        self.read_done.wait();
+        let result = update();
+        self.update_done.wait();
+        result
+    }
+}
+
+/// Buggy version - the head CAS compares the node index only
+mod buggy {
+    use super::*;
+
+    pub struct Stack {
+        arena: Arc<Arena>,
+        head: AtomicU32,
+        failed_cas: AtomicUsize,
+    }
+
+    impl Stack {
+        pub fn new(arena: Arc<Arena>) -> Self {
+            Self {
+                arena,
+                head: AtomicU32::new(NIL),
+                failed_cas: AtomicUsize::new(0),
+            }
+        }
+
+        pub fn push(&self, node: u32) {
+            loop {
+                let head = self.head.load(Ordering::SeqCst);
+                self.arena.set_next(node, head);
+                if self
+                    .head
+                    .compare_exchange(head, node, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+                {
+                    return;
+                }
+                self.failed_cas.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        /// BUG: if the head is popped and pushed back between the read and the
+        /// CAS, the CAS still matches and installs a stale `next`
+        pub fn pop(&self, mut hook: Option<&RaceHook>) -> Option<u32> {
+            loop {
+                let head = self.head.load(Ordering::SeqCst);
+                if head == NIL {
+                    return None;
+                }
+                let next = self.arena.next(head);
+
+                if let Some(hook) = hook.take() {
+                    println!(
+                        "[BUGGY] Popper read head={} next={}, stalling before CAS",
+                        node_name(head),
+                        node_name(next)
+                    );
+                    hook.after_read();
+                }
+
+                // BUG: only the index is compared, so A -> B -> A looks unchanged
+                match self
+                    .head
+                    .compare_exchange(head, next, Ordering::SeqCst, Ordering::SeqCst)
+                {
+                    Ok(_) => return Some(head),
+                    Err(_) => {
+                        self.failed_cas.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            }
+        }
+
+        pub fn snapshot(&self) -> Vec<u32> {
+            self.arena.walk(self.head.load(Ordering::SeqCst))
+        }
+
+        pub fn failed_cas(&self) -> usize {
+            self.failed_cas.load(Ordering::SeqCst)
+        }
+    }
+}
+
+/// Fixed version - the head carries a tag that every successful CAS bumps
+mod fixed {
+    use super::*;
+
+    /// Head word layout: tag in the high 32 bits, node index in the low 32
+    fn pack(node: u32, tag: u32) -> u64 {
+        ((tag as u64) << 32) | node as u64
+    }
+
+    fn unpack(word: u64) -> (u32, u32) {
+        (word as u32, (word >> 32) as u32)
+    }
+
+    pub struct Stack {
+        arena: Arc<Arena>,
+        head: AtomicU64,
+        failed_cas: AtomicUsize,
+    }
+
+    impl Stack {
+        pub fn new(arena: Arc<Arena>) -> Self {
+            Self {
+                arena,
+                head: AtomicU64::new(pack(NIL, 0)),
+                failed_cas: AtomicUsize::new(0),
+            }
+        }
+
+        pub fn push(&self, node: u32) {
+            loop {
+                let word = self.head.load(Ordering::SeqCst);
+                let (head, tag) = unpack(word);
+                self.arena.set_next(node, head);
+                let new_word = pack(node, tag.wrapping_add(1));
+                if self
+                    .head
+                    .compare_exchange(word, new_word, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+                {
+                    return;
+                }
+                self.failed_cas.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        /// FIX: the CAS compares index and tag, so a head that was popped and
+        /// pushed back in the meantime no longer matches
+        pub fn pop(&self, mut hook: Option<&RaceHook>) -> Option<u32> {
+            loop {
+                let word = self.head.load(Ordering::SeqCst);
+                let (head, tag) = unpack(word);
+                if head == NIL {
+                    return None;
+                }
+                let next = self.arena.next(head);
+
+                if let Some(hook) = hook.take() {
+                    println!(
+                        "[FIXED] Popper read head={} (tag {}) next={}, stalling before CAS",
+                        node_name(head),
+                        tag,
+                        node_name(next)
+                    );
+                    hook.after_read();
+                }
+
+                let new_word = pack(next, tag.wrapping_add(1));
+                match self
+                    .head
+                    .compare_exchange(word, new_word, Ordering::SeqCst, Ordering::SeqCst)
+                {
+                    Ok(_) => return Some(head),
+                    Err(current) => {
+                        let (current_head, current_tag) = unpack(current);
+                        println!(
+                            "[FIXED] Stale CAS rejected: expected {} (tag {}), found {} (tag {}), retrying",
+                            node_name(head),
+                            tag,
+                            node_name(current_head),
+                            current_tag
+                        );
+                        self.failed_cas.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            }
+        }
+
+        pub fn snapshot(&self) -> Vec<u32> {
+            self.arena.walk(unpack(self.head.load(Ordering::SeqCst)).0)
+        }
+
+        pub fn failed_cas(&self) -> usize {
+            self.failed_cas.load(Ordering::SeqCst)
+        }
+    }
+}
+
+/// What each thread ended up with after the interleaving
+#[derive(Debug)]
+pub struct AbaOutcome {
+    popped_by_stalled: u32,
+    held_by_other: u32,
+    stack: Vec<u32>,
+}
+
+impl AbaOutcome {
+    /// A node the other thread popped and still owns is reachable from head
+    fn corrupted(&self) -> bool {
+        self.stack.contains(&self.held_by_other)
+    }
+}
+
+/// Push C, B, A, then run the stalled pop against pop A, pop B, push A
+fn run_scenario<S: Send + Sync + 'static>(
+    stack: Arc<S>,
+    push: fn(&S, u32),
+    pop: fn(&S, Option<&RaceHook>) -> Option<u32>,
+    snapshot: fn(&S) -> Vec<u32>,
+    prefix: &'static str,
+) -> AbaOutcome {
+    for node in [2, 1, 0] {
+        push(&stack, node);
+    }
+    println!("Initial stack: {:?}\n", names(&snapshot(&stack)));
+
+    let hook = Arc::new(RaceHook::new());
+
+    let stalled = {
+        let stack = Arc::clone(&stack);
+        let hook = Arc::clone(&hook);
+        thread::spawn(move || pop(&stack, Some(&hook)).unwrap())
+    };
+
+    let other = {
+        let stack = Arc::clone(&stack);
+        let hook = Arc::clone(&hook);
+        thread::spawn(move || {
+            hook.run_update(|| {
+                let a = pop(&stack, None).unwrap();
+                let b = pop(&stack, None).unwrap();
+                println!("{} Other thread popped {} and {}", prefix, node_name(a), node_name(b));
+                push(&stack, a);
+                println!(
+                    "{} Other thread pushed {} back, keeps {}",
+                    prefix,
+                    node_name(a),
+                    node_name(b)
+                );
+                b
+            })
+        })
+    };
+
+    let popped_by_stalled = stalled.join().unwrap();
+    let held_by_other = other.join().unwrap();
+    println!("{} Stalled popper returned {}", prefix, node_name(popped_by_stalled));
+
+    AbaOutcome {
+        popped_by_stalled,
+        held_by_other,
+        stack: snapshot(&stack),
+    }
+}
+
+fn print_outcome(outcome: &AbaOutcome, failed_cas: usize) {
+    println!("\n=== Results ===");
+    println!("Stalled popper got: {}", node_name(outcome.popped_by_stalled));
+    println!("Other thread holds: {}", node_name(outcome.held_by_other));
+    println!("Final stack: {:?}", names(&outcome.stack));
+
+    println!("\n=== Metrics ===");
+    println!("failed_cas: {}", failed_cas);
+    println!("corrupted: {}", outcome.corrupted());
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let use_fixed = args.iter().any(|arg| arg == "--fixed");
+
+    println!("=== ABA Problem: Lock-Free Stack Corruption ===\n");
+
+    if use_fixed {
+        println!("Running FIXED version (tagged head CAS)...\n");
+        run_fixed_test();
+    } else {
+        println!("Running BUGGY version (untagged head CAS)...\n");
+        run_buggy_test();
+    }
+}
+
+fn run_buggy_test() {
+    let arena = Arc::new(Arena::new(NODE_NAMES.len()));
+    let stack = Arc::new(buggy::Stack::new(arena));
+
+    let outcome = run_scenario(
+        Arc::clone(&stack),
+        buggy::Stack::push,
+        buggy::Stack::pop,
+        buggy::Stack::snapshot,
+        "[BUGGY]",
+    );
+    print_outcome(&outcome, stack.failed_cas());
+
+    if outcome.corrupted() {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!(
+            "The head was {} again, so the stale CAS succeeded and installed {}.",
+            node_name(outcome.popped_by_stalled),
+            node_name(outcome.held_by_other)
+        );
+        println!(
+            "{} is now on the stack and owned by the other thread at the same time.",
+            node_name(outcome.held_by_other)
+        );
+        println!("\nRun with --fixed to see the tagged CAS reject the stale update.");
+    }
+}
+
+fn run_fixed_test() {
+    let arena = Arc::new(Arena::new(NODE_NAMES.len()));
+    let stack = Arc::new(fixed::Stack::new(arena));
+
+    let outcome = run_scenario(
+        Arc::clone(&stack),
+        fixed::Stack::push,
+        fixed::Stack::pop,
+        fixed::Stack::snapshot,
+        "[FIXED]",
+    );
+    print_outcome(&outcome, stack.failed_cas());
+
+    assert!(stack.failed_cas() >= 1, "tagged CAS should reject the stale update");
+    assert!(!outcome.corrupted());
+    assert_eq!(outcome.stack, vec![2]);
+
+    println!();
+    print_banner("[FIXED]");
+    println!("The tag changed on every push and pop, so the stale CAS failed.");
+    println!("The popper retried against the current head and the stack stayed consistent.");
+}
@@ -24,6 +24,45 @@ pub enum PipelineState {
     Failed(String),
 }
 
+/// Event that can move a pipeline to another state
+#[derive(Clone, Debug, PartialEq)]
+pub enum PipelineEvent {
+    /// `started` of the pipeline's `total` tasks have finished on_start
+    TasksStarted { started: usize, total: usize },
+    TaskFailed(String),
+}
+
+/// An event the current state does not accept
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidTransition {
+    pub from: PipelineState,
+    pub event: PipelineEvent,
+}
+
+impl PipelineState {
+    /// The only valid transitions:
+    ///
+    /// - `Scheduling` stays `Scheduling` until every task has started, then
+    ///   becomes `Running`
+    /// - `Scheduling` or `Running` becomes `Failed` on any task failure
+    /// - `Failed` is terminal; nothing leaves it, so a failure can never be
+    ///   overwritten by a later start
+    pub fn apply(&self, event: PipelineEvent) -> Result<PipelineState, InvalidTransition> {
+        match (self, &event) {
+            (PipelineState::Scheduling, PipelineEvent::TasksStarted { started, total }) if started < total => {
+                Ok(PipelineState::Scheduling)
+            }
+            (PipelineState::Scheduling, PipelineEvent::TasksStarted { started, total }) if started == total => {
+                Ok(PipelineState::Running)
+            }
+            (PipelineState::Scheduling | PipelineState::Running, PipelineEvent::TaskFailed(reason)) => {
+                Ok(PipelineState::Failed(reason.clone()))
+            }
+            _ => Err(InvalidTransition { from: self.clone(), event }),
+        }
+    }
+}
+
 /// Task notification types
 #[derive(Clone, Debug)]
 pub enum TaskNotification {
@@ -77,6 +116,7 @@ mod buggy {
                     let started = self.started_tasks.lock().unwrap();
                     if started.len() == tasks.len() {
                         println!("[BUGGY] All tasks started, transitioning to Running");
+                        // BUG: Writes the state directly, bypassing PipelineState::apply
                         *self.pipeline_state.lock().unwrap() = PipelineState::Running;
                     }
                 }
@@ -161,32 +201,44 @@ mod fixed {
             self.tasks.lock().unwrap().insert(task.id, task);
         }
 
+        /// Every state change goes through `PipelineState::apply`; an event
+        /// the current state rejects leaves it unchanged
+        fn apply(&self, event: PipelineEvent) {
+            let mut state = self.pipeline_state.lock().unwrap();
+            match state.apply(event) {
+                Ok(next) => {
+                    if next != *state {
+                        println!("[FIXED] Pipeline {:?} -> {:?}", *state, next);
+                    }
+                    *state = next;
+                }
+                Err(rejected) => println!("[FIXED] Rejected transition: {:?}", rejected),
+            }
+        }
+
         /// FIX: Handle TaskFailed during scheduling phase
         pub fn handle_notification(&self, notification: TaskNotification) {
             match notification {
                 TaskNotification::Started(task_id) => {
                     println!("[FIXED] Received TaskStarted for task {}", task_id);
-                    self.started_tasks.lock().unwrap().push(task_id);
-
-                    let tasks = self.tasks.lock().unwrap();
-                    let started = self.started_tasks.lock().unwrap();
-                    if started.len() == tasks.len() {
-                        println!("[FIXED] All tasks started, transitioning to Running");
-                        *self.pipeline_state.lock().unwrap() = PipelineState::Running;
-                    }
+                    let total = self.tasks.lock().unwrap().len();
+                    let started = {
+                        let mut started = self.started_tasks.lock().unwrap();
+                        started.push(task_id);
+                        started.len()
+                    };
+                    self.apply(PipelineEvent::TasksStarted { started, total });
                 }
                 TaskNotification::Failed(task_id, reason) => {
                     println!("[FIXED] Received TaskFailed for task {}: {}", task_id, reason);
 
                     // FIX: Handle failure during scheduling phase!
                     // Trigger rescheduling instead of ignoring
-                    let state = self.pipeline_state.lock().unwrap().clone();
-                    if state == PipelineState::Scheduling {
+                    if *self.pipeline_state.lock().unwrap() == PipelineState::Scheduling {
                         println!("[FIXED] Failure during scheduling - triggering reschedule");
                     }
 
-                    *self.pipeline_state.lock().unwrap() =
-                        PipelineState::Failed(reason);
+                    self.apply(PipelineEvent::TaskFailed(reason));
                 }
             }
         }
@@ -230,13 +282,202 @@ mod fixed {
     }
 }
 
+/// Actor-model version - the controller owns its state on a single thread
+/// and consumes TaskNotifications from an mpsc channel. There are no shared
+/// Mutexes. Buggy and fixed share the controller and differ only in when a
+/// worker enqueues TaskStarted, so the premature Running transition is purely
+/// a message-ordering bug.
+mod actor {
+    use super::*;
+    use std::sync::mpsc::{self, Receiver, Sender};
+
+    /// When a worker enqueues TaskStarted relative to on_start
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum StartedOrder {
+        /// BUG: before on_start has run
+        BeforeOnStart,
+        /// FIX: only after on_start has succeeded
+        AfterOnStart,
+    }
+
+    impl StartedOrder {
+        fn label(self) -> &'static str {
+            match self {
+                StartedOrder::BeforeOnStart => "[ACTOR-BUGGY]",
+                StartedOrder::AfterOnStart => "[ACTOR-FIXED]",
+            }
+        }
+    }
+
+    pub struct Controller {
+        label: &'static str,
+        num_tasks: usize,
+        pipeline_state: PipelineState,
+        started_tasks: Vec<TaskId>,
+        transitions: Vec<PipelineState>,
+    }
+
+    impl Controller {
+        /// Spawn the controller actor, returning the mailbox and a handle
+        /// that yields the controller once every sender has been dropped
+        pub fn spawn(
+            num_tasks: usize,
+            order: StartedOrder,
+        ) -> (Sender<TaskNotification>, thread::JoinHandle<Controller>) {
+            let (tx, rx) = mpsc::channel();
+            let handle = thread::spawn(move || {
+                let mut controller = Controller {
+                    label: order.label(),
+                    num_tasks,
+                    pipeline_state: PipelineState::Scheduling,
+                    started_tasks: Vec::new(),
+                    transitions: Vec::new(),
+                };
+                controller.run(rx);
+                controller
+            });
+            (tx, handle)
+        }
+
+        fn run(&mut self, mailbox: Receiver<TaskNotification>) {
+            for notification in mailbox {
+                self.handle_notification(notification);
+            }
+        }
+
+        /// Record a state change only if `PipelineState::apply` accepts it
+        fn apply(&mut self, event: PipelineEvent) {
+            match self.pipeline_state.apply(event) {
+                Ok(next) if next != self.pipeline_state => {
+                    println!("{} Pipeline {:?} -> {:?}", self.label, self.pipeline_state, next);
+                    self.pipeline_state = next.clone();
+                    self.transitions.push(next);
+                }
+                Ok(_) => {}
+                Err(rejected) => println!("{} Rejected transition: {:?}", self.label, rejected),
+            }
+        }
+
+        /// Messages are processed in arrival order on one thread. Running
+        /// is only trustworthy if every Started really follows on_start.
+        fn handle_notification(&mut self, notification: TaskNotification) {
+            match notification {
+                TaskNotification::Started(task_id) => {
+                    println!("{} Received TaskStarted for task {}", self.label, task_id);
+                    self.started_tasks.push(task_id);
+                    self.apply(PipelineEvent::TasksStarted {
+                        started: self.started_tasks.len(),
+                        total: self.num_tasks,
+                    });
+                }
+                TaskNotification::Failed(task_id, reason) => {
+                    println!("{} Received TaskFailed for task {}: {}", self.label, task_id, reason);
+
+                    // Failures during scheduling are never discarded
+                    if self.pipeline_state == PipelineState::Scheduling {
+                        println!("{} Failure during scheduling - triggering reschedule", self.label);
+                    }
+
+                    self.apply(PipelineEvent::TaskFailed(reason));
+                }
+            }
+        }
+
+        pub fn get_state(&self) -> PipelineState {
+            self.pipeline_state.clone()
+        }
+
+        /// Every state the pipeline was reported in, in order
+        pub fn transitions(&self) -> &[PipelineState] {
+            &self.transitions
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct Worker {
+        mailbox: Sender<TaskNotification>,
+        order: StartedOrder,
+    }
+
+    impl Worker {
+        pub fn new(mailbox: Sender<TaskNotification>, order: StartedOrder) -> Self {
+            Self { mailbox, order }
+        }
+
+        /// Run on_start, enqueueing TaskStarted before or after it
+        pub fn start_task(&self, task: Task) {
+            let task_id = task.id;
+            let label = self.order.label();
+
+            if self.order == StartedOrder::BeforeOnStart {
+                // BUG: Started is enqueued before on_start has run
+                println!("{} Task {} sending TaskStarted (before on_start)", label, task_id);
+                self.mailbox.send(TaskNotification::Started(task_id)).unwrap();
+            }
+
+            println!("{} Task {} executing on_start...", label, task_id);
+            thread::sleep(Duration::from_millis(50));
+
+            if task.should_panic {
+                println!("{} Task {} ({}) PANICKED during on_start!", label, task_id, task.name);
+                self.mailbox
+                    .send(TaskNotification::Failed(task_id, format!("{} panicked", task.name)))
+                    .unwrap();
+                return;
+            }
+
+            if self.order == StartedOrder::AfterOnStart {
+                // FIX: Only a task whose on_start succeeded sends Started
+                println!("{} Task {} on_start completed, sending TaskStarted", label, task_id);
+                self.mailbox.send(TaskNotification::Started(task_id)).unwrap();
+            } else {
+                println!("{} Task {} on_start completed successfully", label, task_id);
+            }
+        }
+    }
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let use_actor = args.iter().any(|arg| arg == "--actor");
 
     println!("=== Arroyo Issue #712: Task Startup Race Condition ===\n");
 
-    if use_fixed {
+    if args.iter().any(|arg| arg == "--state-machine") {
+        println!("Checking PipelineState::apply in isolation...\n");
+        run_state_machine_test();
+    } else if use_actor && use_fixed {
+        println!("Running ACTOR-FIXED version (message passing, TaskStarted after on_start)...\n");
+        run_actor_fixed_test();
+    } else if use_actor {
+        println!("Running ACTOR-BUGGY version (message passing, TaskStarted before on_start)...\n");
+        run_actor_buggy_test();
+    } else if use_fixed {
         println!("Running FIXED version (TaskStarted after on_start)...\n");
         run_fixed_test();
     } else {
@@ -283,7 +524,8 @@ fn run_buggy_test() {
     println!("Final pipeline state: {:?}", state);
 
     if state == PipelineState::Running {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Pipeline shows as 'Running' but task 2 failed!");
         println!("\nProblem:");
         println!("  - TaskStarted sent BEFORE on_start completes");
@@ -291,6 +533,11 @@ fn run_buggy_test() {
         println!("  - TaskFailed during scheduling was IGNORED");
         println!("  - Pipeline appears healthy but is broken");
         println!("\nRun with --fixed to see correct behavior.");
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("Race did not manifest this time (timing-dependent).");
+        println!("Run with --actor to see the failure ignored on every run.");
     }
 }
 
@@ -329,7 +576,8 @@ fn run_fixed_test() {
     println!("Final pipeline state: {:?}", state);
 
     if let PipelineState::Failed(reason) = state {
-        println!("\n[FIXED]");
+        println!();
+        print_banner("[FIXED]");
         println!("Pipeline correctly shows as Failed: {}", reason);
         println!("\nFix:");
         println!("  - TaskStarted sent AFTER on_start completes");
@@ -337,3 +585,120 @@ fn run_fixed_test() {
         println!("  - Failed tasks are properly detected");
     }
 }
+
+/// Start the three demo tasks on actor workers that enqueue TaskStarted in
+/// `order`, and return the controller once its mailbox has drained
+fn run_actor(order: actor::StartedOrder) -> actor::Controller {
+    let tasks = vec![
+        Task { id: 1, name: "source".to_string(), should_panic: false },
+        Task { id: 2, name: "transform".to_string(), should_panic: true },
+        Task { id: 3, name: "sink".to_string(), should_panic: false },
+    ];
+
+    println!("Scenario: Start 3 tasks, task 2 (transform) will panic during on_start");
+    println!("Controller is an actor: no Mutexes, one mailbox\n");
+
+    let (mailbox, controller_handle) = actor::Controller::spawn(tasks.len(), order);
+    let worker = actor::Worker::new(mailbox, order);
+
+    let mut handles = vec![];
+    for task in tasks {
+        let w = worker.clone();
+        handles.push(thread::spawn(move || {
+            w.start_task(task);
+        }));
+    }
+
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    // Closing the last sender lets the actor drain its mailbox and exit
+    drop(worker);
+    let controller = controller_handle.join().unwrap();
+
+    println!("\n=== Results ===");
+    println!("State transitions: {:?}", controller.transitions());
+    println!("Final pipeline state: {:?}", controller.get_state());
+    controller
+}
+
+fn run_actor_buggy_test() {
+    let controller = run_actor(actor::StartedOrder::BeforeOnStart);
+
+    assert!(
+        controller.transitions().contains(&PipelineState::Running),
+        "early TaskStarted messages should drive the pipeline to Running"
+    );
+
+    println!();
+    print_banner("[BUG DEMONSTRATED]");
+    println!("Pipeline was reported 'Running' although task 2 failed!");
+    println!("\nProblem:");
+    println!("  - No shared locks, yet the bug is still here");
+    println!("  - TaskStarted messages were enqueued before on_start");
+    println!("  - The actor faithfully processed them in order -> Running");
+    println!("  - Message passing reorders the problem, it does not remove it");
+    println!("\nRun with --actor --fixed to see correct behavior.");
+}
+
+fn run_actor_fixed_test() {
+    let controller = run_actor(actor::StartedOrder::AfterOnStart);
+
+    assert!(
+        !controller.transitions().contains(&PipelineState::Running),
+        "a pipeline with a failed task must never be reported Running"
+    );
+    let PipelineState::Failed(reason) = controller.get_state() else {
+        panic!("pipeline should end Failed, got {:?}", controller.get_state());
+    };
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Pipeline never reported Running; correctly Failed: {}", reason);
+    println!("\nFix:");
+    println!("  - TaskStarted enqueued only AFTER on_start completes");
+    println!("  - A failed task never sends TaskStarted");
+    println!("  - The message order now reflects the real task state");
+}
+
+fn run_state_machine_test() {
+    use PipelineEvent::{TaskFailed, TasksStarted};
+    use PipelineState::{Failed, Running, Scheduling};
+
+    let failed = || Failed("transform panicked".to_string());
+    let failure = || TaskFailed("transform panicked".to_string());
+    let all_started = TasksStarted { started: 3, total: 3 };
+
+    let cases = [
+        (Scheduling, TasksStarted { started: 1, total: 3 }, Some(Scheduling)),
+        (Scheduling, all_started.clone(), Some(Running)),
+        (Scheduling, failure(), Some(failed())),
+        (Running, failure(), Some(failed())),
+        (Running, all_started.clone(), None),
+        (failed(), all_started.clone(), None),
+        (failed(), failure(), None),
+    ];
+
+    for (from, event, expected) in cases {
+        let result = from.apply(event.clone());
+        match &result {
+            Ok(next) => println!("{:?} + {:?} -> {:?}", from, event, next),
+            Err(_) => println!("{:?} + {:?} -> rejected", from, event),
+        }
+        assert_eq!(result.ok(), expected, "{:?} + {:?}", from, event);
+    }
+
+    // The buggy interleaving: the failure lands first, the last Started after
+    let state = Scheduling.apply(failure()).unwrap();
+    let rejected = state.apply(all_started).unwrap_err();
+    assert_eq!(rejected.from, failed());
+
+    println!("\n=== Results ===");
+    println!("Started after a failure: {:?}", rejected);
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Failed is terminal: once a failure is applied, Running is rejected.");
+    println!("The fixed controllers route every event through apply; the buggy ones write the state directly.");
+}
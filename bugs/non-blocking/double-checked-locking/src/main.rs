@@ -0,0 +1,373 @@
+//! Double-Checked Locking Without Ordering
+//!
+//! This reproduces the double-checked locking anti-pattern. A lazily
+//! initialized config is read without the lock on the fast path, guarded only
+//! by a `Relaxed` ready flag. Nothing orders the flag store after the value
+//! write, so a reader can see `ready == true` and still read an empty slot.
+//!
+//! Pattern reproduction: not tied to a single upstream issue. sui-race-2894
+//! shows the higher-level once-init race (no second check at all); this is
+//! the lower-level version where both checks exist but publication is broken.
+//!
+//! x86 keeps the two stores in order, so the native run rarely shows the bug;
+//! the `--loom` mode explores every ordering the memory model allows and is
+//! the real check. loom is only compiled with `RUSTFLAGS="--cfg loom"`.
+
+use std::cell::UnsafeCell;
+use std::env;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+const READERS: usize = 6;
+/// How long building the config takes
+const INIT_DURATION: Duration = Duration::from_millis(20);
+
+#[derive(Debug)]
+pub struct Config {
+    endpoint: String,
+}
+
+/// Counts initializations and reads that found no config
+#[derive(Default)]
+pub struct InitStats {
+    init_runs: AtomicUsize,
+    empty_reads: AtomicUsize,
+}
+
+impl InitStats {
+    fn build_config(&self) -> Arc<Config> {
+        let run = self.init_runs.fetch_add(1, Ordering::SeqCst) + 1;
+        println!("  [INIT] Building config (run #{})", run);
+        thread::sleep(INIT_DURATION);
+        Arc::new(Config {
+            endpoint: "https://fullnode.example:9000".to_string(),
+        })
+    }
+
+    fn record(&self, config: &Option<Arc<Config>>) {
+        if config.is_none() {
+            self.empty_reads.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn init_runs(&self) -> usize {
+        self.init_runs.load(Ordering::SeqCst)
+    }
+
+    fn empty_reads(&self) -> usize {
+        self.empty_reads.load(Ordering::SeqCst)
+    }
+}
+
+/// Buggy version - hand-rolled DCL with a Relaxed flag and an UnsafeCell
+mod buggy {
+    use super::*;
+
+    pub const STORE_ORDERING: Ordering = Ordering::Relaxed;
+    pub const LOAD_ORDERING: Ordering = Ordering::Relaxed;
+
+    pub struct LazyConfig {
+        lock: Mutex<()>,
+        ready: AtomicBool,
+        value: UnsafeCell<Option<Arc<Config>>>,
+        stats: Arc<InitStats>,
+    }
+
+    // BUG: claims Sync, but the fast path reads `value` with no
+    // happens-before edge to the write
+    unsafe impl Sync for LazyConfig {}
+
+    impl LazyConfig {
+        pub fn new(stats: Arc<InitStats>) -> Self {
+            Self {
+                lock: Mutex::new(()),
+                ready: AtomicBool::new(false),
+                value: UnsafeCell::new(None),
+                stats,
+            }
+        }
+
+        pub fn get(&self) -> Option<Arc<Config>> {
+            // First check, without the lock
+            if self.ready.load(LOAD_ORDERING) {
+                // BUG: Relaxed gives no guarantee the value write is visible
+                return unsafe { (*self.value.get()).clone() };
+            }
+
+            let _guard = self.lock.lock().unwrap();
+            // Second check, under the lock
+            if !self.ready.load(Ordering::Relaxed) {
+                let config = self.stats.build_config();
+                unsafe { *self.value.get() = Some(config) };
+                // BUG: Relaxed lets this store become visible before the value
+                self.ready.store(true, STORE_ORDERING);
+            }
+            unsafe { (*self.value.get()).clone() }
+        }
+    }
+}
+
+/// Fixed version - OnceLock publishes the value with the right ordering
+mod fixed {
+    use super::*;
+
+    pub struct LazyConfig {
+        value: OnceLock<Arc<Config>>,
+        stats: Arc<InitStats>,
+    }
+
+    impl LazyConfig {
+        pub fn new(stats: Arc<InitStats>) -> Self {
+            Self {
+                value: OnceLock::new(),
+                stats,
+            }
+        }
+
+        /// FIX: readers either see the finished value or block until it is
+        /// ready; the initializer runs exactly once
+        pub fn get(&self) -> Option<Arc<Config>> {
+            Some(Arc::clone(self.value.get_or_init(|| self.stats.build_config())))
+        }
+    }
+}
+
+/// Exhaustive check of the double check under loom's memory model
+///
+/// The model performs the same steps as `buggy::LazyConfig::get` with the
+/// given orderings on the ready flag. `Release`/`Acquire` is the hand-rolled
+/// fix, and what `OnceLock` does internally. loom's `UnsafeCell` panics with
+/// a causality violation if a fast-path read is not ordered after the write.
+#[cfg(loom)]
+mod model {
+    use loom::cell::UnsafeCell;
+    use loom::sync::atomic::AtomicBool;
+    use loom::sync::{Arc, Mutex};
+    use loom::thread;
+    use std::panic;
+    use std::sync::atomic::Ordering;
+
+    struct LazyConfig {
+        lock: Mutex<()>,
+        ready: AtomicBool,
+        value: UnsafeCell<Option<u64>>,
+    }
+
+    // Only shared inside loom::model, which checks every access
+    unsafe impl Sync for LazyConfig {}
+
+    impl LazyConfig {
+        fn get(&self, store: Ordering, load: Ordering) -> Option<u64> {
+            if self.ready.load(load) {
+                return self.value.with(|v| unsafe { *v });
+            }
+            let _guard = self.lock.lock().unwrap();
+            if !self.ready.load(Ordering::Relaxed) {
+                self.value.with_mut(|v| unsafe { *v = Some(42) });
+                self.ready.store(true, store);
+            }
+            self.value.with(|v| unsafe { *v })
+        }
+    }
+
+    pub fn check(store: Ordering, load: Ordering) {
+        loom::model(move || {
+            let lazy = Arc::new(LazyConfig {
+                lock: Mutex::new(()),
+                ready: AtomicBool::new(false),
+                value: UnsafeCell::new(None),
+            });
+
+            let other = {
+                let lazy = Arc::clone(&lazy);
+                thread::spawn(move || lazy.get(store, load))
+            };
+
+            assert_eq!(lazy.get(store, load), Some(42), "reader saw ready but no config");
+            assert_eq!(other.join().unwrap(), Some(42), "reader saw ready but no config");
+        });
+    }
+
+    /// Run `check`, turning a failed model into the panic message
+    pub fn run(store: Ordering, load: Ordering) -> Result<(), String> {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = panic::catch_unwind(|| check(store, load));
+        panic::set_hook(hook);
+
+        result.map_err(|payload| {
+            payload
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_else(|| "model panicked".to_string())
+        })
+    }
+}
+
+/// Start one reader that triggers init, then the rest while init is running
+fn run_readers<F>(get: F, stats: Arc<InitStats>, prefix: &'static str)
+where
+    F: Fn() -> Option<Arc<Config>> + Send + Sync + 'static,
+{
+    let get = Arc::new(get);
+    let handles: Vec<_> = (0..READERS)
+        .map(|i| {
+            let get = Arc::clone(&get);
+            let stats = Arc::clone(&stats);
+            thread::spawn(move || {
+                if i > 0 {
+                    // Arrive while the first reader is still initializing
+                    thread::sleep(Duration::from_millis(5));
+                }
+                let config = get();
+                match &config {
+                    Some(config) => println!("{} Reader {} got {}", prefix, i, config.endpoint),
+                    None => println!("{} Reader {} saw ready=true but no config!", prefix, i),
+                }
+                stats.record(&config);
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+fn print_stats(stats: &InitStats) {
+    println!("\n=== Results ===");
+    println!("Readers: {}", READERS);
+    println!("Initializations: {}", stats.init_runs());
+    println!("Reads that found no config: {}", stats.empty_reads());
+
+    println!("\n=== Metrics ===");
+    println!("init_runs: {}", stats.init_runs());
+    println!("empty_reads: {}", stats.empty_reads());
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let use_loom = args.iter().any(|arg| arg == "--loom");
+
+    println!("=== Double-Checked Locking Without Ordering ===\n");
+
+    if use_loom {
+        println!("Checking both orderings under loom...\n");
+        run_loom_test();
+    } else if use_fixed {
+        println!("Running FIXED version (OnceLock)...\n");
+        run_fixed_test();
+    } else {
+        println!("Running BUGGY version (Relaxed flag + UnsafeCell)...\n");
+        run_buggy_test();
+    }
+}
+
+fn run_buggy_test() {
+    let stats = Arc::new(InitStats::default());
+    let lazy = Arc::new(buggy::LazyConfig::new(Arc::clone(&stats)));
+
+    run_readers(move || lazy.get(), Arc::clone(&stats), "[BUGGY]");
+    print_stats(&stats);
+
+    if stats.empty_reads() > 0 {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!(
+            "{} readers passed the unlocked check and read an empty config.",
+            stats.empty_reads()
+        );
+        println!("The ready flag was visible before the value it was meant to publish.");
+        println!("With a raw pointer instead of an Option this is a read of uninitialized memory.");
+        println!("\nRun with --fixed to see OnceLock publish the value safely.");
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("No reader saw ready=true before the config on this machine. x86 does");
+        println!("not reorder these stores, so the bug is latent here and shows up on");
+        println!("ARM and other weak models.");
+        println!("\nRun with --loom to check every ordering the memory model allows.");
+    }
+}
+
+fn run_fixed_test() {
+    let stats = Arc::new(InitStats::default());
+    let lazy = Arc::new(fixed::LazyConfig::new(Arc::clone(&stats)));
+
+    run_readers(move || lazy.get(), Arc::clone(&stats), "[FIXED]");
+    print_stats(&stats);
+
+    assert_eq!(stats.init_runs(), 1, "config must be initialized exactly once");
+    assert_eq!(stats.empty_reads(), 0, "no reader may observe a missing config");
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Initialization ran exactly once and every reader got the config.");
+    println!("Readers that arrived during init blocked in get_or_init until it finished.");
+}
+
+#[cfg(not(loom))]
+fn run_loom_test() {
+    eprintln!("The loom check is not compiled in. Rebuild with the loom cfg:");
+    eprintln!("  RUSTFLAGS=\"--cfg loom\" cargo run --release -- --loom");
+    std::process::exit(2);
+}
+
+#[cfg(loom)]
+fn run_loom_test() {
+    let orderings = [
+        ("BUGGY", buggy::STORE_ORDERING, buggy::LOAD_ORDERING),
+        ("FIXED", Ordering::Release, Ordering::Acquire),
+    ];
+    let [buggy, fixed] = orderings.map(|(label, store, load)| {
+        let result = model::run(store, load);
+        match &result {
+            Ok(()) => println!("[{}] {:?} store / {:?} load: no violation in any execution", label, store, load),
+            Err(message) => println!(
+                "[{}] {:?} store / {:?} load: FAILED: {}",
+                label,
+                store,
+                load,
+                message.lines().next().unwrap_or_default()
+            ),
+        }
+        result
+    });
+
+    assert!(buggy.is_err(), "loom must find the unordered fast-path read");
+    assert!(fixed.is_ok(), "loom found a violation in the Release/Acquire double check");
+
+    println!("\n=== Results ===");
+    print_banner("[BUG DEMONSTRATED]");
+    println!("loom found an execution where a fast-path read is not ordered after the init write.");
+    print_banner("[FIXED]");
+    println!("With Release/Acquire every reader that sees ready=true also sees the config.");
+}
@@ -8,16 +8,39 @@
 
 use std::collections::HashSet;
 use std::env;
+use std::sync::atomic::{AtomicIsize, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub type RegionId = u64;
 
+/// Counts live RAII guards: +1 on creation, -1 in `Drop`. A nonzero
+/// balance once every operation has finished means a guard was leaked.
+#[derive(Default)]
+pub struct GuardAudit {
+    live: AtomicIsize,
+}
+
+impl GuardAudit {
+    fn acquired(&self) {
+        self.live.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn released(&self) {
+        self.live.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn balance(&self) -> isize {
+        self.live.load(Ordering::SeqCst)
+    }
+}
+
 /// Tracks which regions are currently being operated on
 pub struct OperatingRegions {
     dropping: RwLock<HashSet<RegionId>>,
     creating: RwLock<HashSet<RegionId>>,
+    guard_audit: GuardAudit,
 }
 
 impl OperatingRegions {
@@ -25,9 +48,15 @@ impl OperatingRegions {
         Self {
             dropping: RwLock::new(HashSet::new()),
             creating: RwLock::new(HashSet::new()),
+            guard_audit: GuardAudit::default(),
         }
     }
 
+    /// Number of region guards created but never dropped
+    fn leaked_guards(&self) -> isize {
+        self.guard_audit.balance()
+    }
+
     fn is_dropping(&self, region_id: RegionId) -> bool {
         self.dropping.read().unwrap().contains(&region_id)
     }
@@ -39,6 +68,18 @@ impl OperatingRegions {
     fn unmark_dropping(&self, region_id: RegionId) {
         self.dropping.write().unwrap().remove(&region_id);
     }
+
+    fn is_creating(&self, region_id: RegionId) -> bool {
+        self.creating.read().unwrap().contains(&region_id)
+    }
+
+    fn mark_creating(&self, region_id: RegionId) {
+        self.creating.write().unwrap().insert(region_id);
+    }
+
+    fn unmark_creating(&self, region_id: RegionId) {
+        self.creating.write().unwrap().remove(&region_id);
+    }
 }
 
 /// Guard that should mark region as not-dropping when dropped
@@ -51,6 +92,7 @@ pub struct DroppingRegionGuard {
 impl DroppingRegionGuard {
     fn new(region_id: RegionId, operating_regions: Arc<OperatingRegions>) -> Self {
         operating_regions.mark_dropping(region_id);
+        operating_regions.guard_audit.acquired();
         Self {
             region_id,
             operating_regions,
@@ -72,6 +114,42 @@ impl Drop for DroppingRegionGuard {
         if !self.released {
             self.operating_regions.unmark_dropping(self.region_id);
         }
+        self.operating_regions.guard_audit.released();
+    }
+}
+
+/// Guard that marks a region as being created, mirroring `DroppingRegionGuard`
+pub struct CreatingRegionGuard {
+    region_id: RegionId,
+    operating_regions: Arc<OperatingRegions>,
+    released: bool,
+}
+
+impl CreatingRegionGuard {
+    fn new(region_id: RegionId, operating_regions: Arc<OperatingRegions>) -> Self {
+        operating_regions.mark_creating(region_id);
+        operating_regions.guard_audit.acquired();
+        Self {
+            region_id,
+            operating_regions,
+            released: false,
+        }
+    }
+
+    fn release(&mut self) {
+        if !self.released {
+            self.operating_regions.unmark_creating(self.region_id);
+            self.released = true;
+        }
+    }
+}
+
+impl Drop for CreatingRegionGuard {
+    fn drop(&mut self) {
+        if !self.released {
+            self.operating_regions.unmark_creating(self.region_id);
+        }
+        self.operating_regions.guard_audit.released();
     }
 }
 
@@ -110,6 +188,16 @@ impl RegionStore {
         regions.iter().any(|r| r.id == region_id && !r.dropped)
     }
 
+    fn create_region(&self, region_id: RegionId, data: Vec<u8>) {
+        let mut regions = self.regions.lock().unwrap();
+        if let Some(region) = regions.iter_mut().find(|r| r.id == region_id) {
+            region.dropped = false;
+            region.data = data;
+        } else {
+            regions.push(Region { id: region_id, data, dropped: false });
+        }
+    }
+
     fn read_region(&self, region_id: RegionId) -> Option<Vec<u8>> {
         let regions = self.regions.lock().unwrap();
         regions.iter()
@@ -118,6 +206,47 @@ impl RegionStore {
     }
 }
 
+/// Create procedure shared by both versions. Creation itself is correct; it
+/// refuses to start while the region is marked as dropping, so a leaked drop
+/// guard blocks it.
+pub struct CreateTableProcedure {
+    operating_regions: Arc<OperatingRegions>,
+    store: Arc<RegionStore>,
+}
+
+impl CreateTableProcedure {
+    fn new(operating_regions: Arc<OperatingRegions>, store: Arc<RegionStore>) -> Self {
+        Self { operating_regions, store }
+    }
+
+    fn try_execute(&self, region_id: RegionId) -> Result<(), &'static str> {
+        if self.operating_regions.is_dropping(region_id) {
+            return Err("region is being dropped");
+        }
+        if self.operating_regions.is_creating(region_id) {
+            return Err("region is already being created");
+        }
+
+        let mut guard = CreatingRegionGuard::new(region_id, Arc::clone(&self.operating_regions));
+        self.store.create_region(region_id, vec![7, 8, 9]);
+        guard.release();
+        Ok(())
+    }
+
+    /// Retry until the create succeeds or `deadline` passes. Returns how long
+    /// the create waited, or `None` if it never got through.
+    fn execute_with_retry(&self, region_id: RegionId, deadline: Duration) -> Option<Duration> {
+        let start = Instant::now();
+        loop {
+            match self.try_execute(region_id) {
+                Ok(()) => return Some(start.elapsed()),
+                Err(_) if start.elapsed() >= deadline => return None,
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+    }
+}
+
 /// Buggy version - guard not released when procedure completes
 mod buggy {
     use super::*;
@@ -246,13 +375,44 @@ mod fixed {
     }
 }
 
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let create_drop = args.iter().any(|arg| arg == "--create-drop");
 
     println!("=== GreptimeDB PR #3771: Region Guard Not Released ===\n");
 
-    if use_fixed {
+    if create_drop {
+        println!(
+            "Running {} version (create racing a drop of the same region)...\n",
+            if use_fixed { "FIXED" } else { "BUGGY" }
+        );
+        run_create_drop_test(use_fixed);
+    } else if use_fixed {
         println!("Running FIXED version (guard released on completion)...\n");
         run_fixed_test();
     } else {
@@ -261,6 +421,11 @@ fn main() {
     }
 }
 
+fn print_guard_metrics(operating_regions: &OperatingRegions) {
+    println!("\n=== Metrics ===");
+    println!("leaked_guards: {}", operating_regions.leaked_guards());
+}
+
 fn run_buggy_test() {
     let operating_regions = Arc::new(OperatingRegions::new());
     let store = Arc::new(RegionStore::new());
@@ -293,7 +458,8 @@ fn run_buggy_test() {
     println!("Region {} actually valid: {}", region_id, is_valid);
 
     if is_dropping && !is_valid {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Inconsistent state!");
         println!("  - Guard says region is being dropped (still held)");
         println!("  - But region was already dropped!");
@@ -309,6 +475,9 @@ fn run_buggy_test() {
         Ok(_) => println!("\nUnexpected: read succeeded"),
         Err(e) => println!("\nReader error: {}", e),
     }
+
+    assert_eq!(operating_regions.leaked_guards(), 1, "buggy drop should leak its guard");
+    print_guard_metrics(&operating_regions);
 }
 
 fn run_fixed_test() {
@@ -341,7 +510,8 @@ fn run_fixed_test() {
     println!("Region {} actually valid: {}", region_id, is_valid);
 
     if !is_dropping && !is_valid {
-        println!("\n[FIXED]");
+        println!();
+        print_banner("[FIXED]");
         println!("Consistent state!");
         println!("  - Guard properly released (not dropping)");
         println!("  - Region correctly marked as dropped");
@@ -352,4 +522,73 @@ fn run_fixed_test() {
         Ok(_) => println!("\nUnexpected: read succeeded"),
         Err(e) => println!("\nReader correctly reports: {}", e),
     }
+
+    assert_eq!(operating_regions.leaked_guards(), 0, "fixed drop must not leak its guard");
+    print_guard_metrics(&operating_regions);
+}
+
+/// How long a create keeps retrying before it is reported as blocked
+const CREATE_DEADLINE: Duration = Duration::from_millis(300);
+
+fn run_create_drop_test(use_fixed: bool) {
+    let operating_regions = Arc::new(OperatingRegions::new());
+    let store = Arc::new(RegionStore::new());
+
+    let region_id = 1;
+    let prefix = if use_fixed { "[FIXED]" } else { "[BUGGY]" };
+
+    println!("Scenario: drop region {} while a create for the same id retries\n", region_id);
+
+    let drop_regions = Arc::clone(&operating_regions);
+    let drop_store = Arc::clone(&store);
+    let dropper = thread::spawn(move || {
+        if use_fixed {
+            fixed::DropTableProcedure::new(drop_regions, drop_store).execute(region_id)
+        } else {
+            buggy::DropTableProcedure::new(drop_regions, drop_store).execute(region_id)
+        }
+    });
+
+    // Let the drop take its guard before the create starts
+    thread::sleep(Duration::from_millis(10));
+    println!("{} Create for region {} waiting on in-flight drop...", prefix, region_id);
+    let create = CreateTableProcedure::new(Arc::clone(&operating_regions), Arc::clone(&store));
+    let waited = create.execute_with_retry(region_id, CREATE_DEADLINE);
+
+    dropper.join().unwrap();
+
+    let is_dropping = operating_regions.is_dropping(region_id);
+    let is_creating = operating_regions.is_creating(region_id);
+    let is_valid = store.is_region_valid(region_id);
+
+    println!("\n=== Results ===");
+    println!("Region {} still marked as dropping: {}", region_id, is_dropping);
+    println!("Region {} still marked as creating: {}", region_id, is_creating);
+    println!("Region {} valid after create: {}", region_id, is_valid);
+    match waited {
+        Some(waited) => println!("Create proceeded after {:?}", waited),
+        None => println!("Create blocked for the full {:?} deadline", CREATE_DEADLINE),
+    }
+
+    if use_fixed {
+        assert!(waited.is_some(), "fixed create should proceed once the drop completes");
+        assert!(is_valid && !is_dropping && !is_creating);
+        println!();
+        print_banner("[FIXED]");
+        println!("Drop guard released on completion, so the create went through.");
+        println!("Both guards are cleared and the region is live again.");
+    } else {
+        assert!(waited.is_none(), "buggy create should be blocked by the leaked drop guard");
+        assert!(is_dropping && !is_valid);
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("The drop finished, but its leaked guard keeps the region marked");
+        println!("as dropping, so the create can never start.");
+        println!("\nRun with --create-drop --fixed to see the create proceed.");
+    }
+
+    // Only the drop guard can leak; the create guard is always dropped
+    let expected_leaks = if use_fixed { 0 } else { 1 };
+    assert_eq!(operating_regions.leaked_guards(), expected_leaks);
+    print_guard_metrics(&operating_regions);
 }
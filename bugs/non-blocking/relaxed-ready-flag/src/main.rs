@@ -0,0 +1,323 @@
+//! Relaxed Ready Flag: Missing Release/Acquire
+//!
+//! This reproduces a message handoff where a producer writes a payload and
+//! then sets a `ready` flag, and a consumer waits for the flag and then reads
+//! the payload. With `Ordering::Relaxed` on the flag, nothing makes the
+//! payload write visible before the flag, so on weakly ordered hardware the
+//! consumer can see `ready == true` and read a stale or half-written payload.
+//!
+//! Pattern reproduction: not tied to a single upstream issue. x86 keeps
+//! stores in order, so the native run rarely shows the bug; the `--loom` mode
+//! explores every ordering the memory model allows and is the real check.
+//! loom is only compiled with `RUSTFLAGS="--cfg loom"`.
+
+use std::cell::UnsafeCell;
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// Handoffs in the native run
+const ROUNDS: usize = 2000;
+const PAYLOAD_WORDS: usize = 4;
+
+/// A payload whose words must all agree; anything else is a torn read
+fn payload_for(round: usize) -> [u64; PAYLOAD_WORDS] {
+    [round as u64 + 1; PAYLOAD_WORDS]
+}
+
+fn is_torn(payload: &[u64; PAYLOAD_WORDS], round: usize) -> bool {
+    *payload != payload_for(round)
+}
+
+/// Buggy version - the ready flag is stored and loaded with Relaxed
+mod buggy {
+    use super::*;
+
+    pub const STORE_ORDERING: Ordering = Ordering::Relaxed;
+    pub const LOAD_ORDERING: Ordering = Ordering::Relaxed;
+
+    pub struct Mailbox {
+        ready: AtomicBool,
+        payload: UnsafeCell<[u64; PAYLOAD_WORDS]>,
+    }
+
+    // BUG: claims Sync, but the flag does not order the payload accesses
+    unsafe impl Sync for Mailbox {}
+
+    impl Mailbox {
+        pub fn new() -> Self {
+            Self {
+                ready: AtomicBool::new(false),
+                payload: UnsafeCell::new([0; PAYLOAD_WORDS]),
+            }
+        }
+
+        pub fn publish(&self, payload: [u64; PAYLOAD_WORDS]) {
+            unsafe { *self.payload.get() = payload };
+            // BUG: Relaxed lets this store become visible before the payload
+            self.ready.store(true, STORE_ORDERING);
+        }
+
+        pub fn receive(&self) -> [u64; PAYLOAD_WORDS] {
+            // BUG: Relaxed does not make the payload write visible here
+            while !self.ready.load(LOAD_ORDERING) {
+                thread::yield_now();
+            }
+            unsafe { *self.payload.get() }
+        }
+    }
+}
+
+/// Fixed version - Release on the store pairs with Acquire on the load
+mod fixed {
+    use super::*;
+
+    pub const STORE_ORDERING: Ordering = Ordering::Release;
+    pub const LOAD_ORDERING: Ordering = Ordering::Acquire;
+
+    pub struct Mailbox {
+        ready: AtomicBool,
+        payload: UnsafeCell<[u64; PAYLOAD_WORDS]>,
+    }
+
+    // Sound: the payload is written once before the Release store and read
+    // only after an Acquire load that saw it
+    unsafe impl Sync for Mailbox {}
+
+    impl Mailbox {
+        pub fn new() -> Self {
+            Self {
+                ready: AtomicBool::new(false),
+                payload: UnsafeCell::new([0; PAYLOAD_WORDS]),
+            }
+        }
+
+        pub fn publish(&self, payload: [u64; PAYLOAD_WORDS]) {
+            unsafe { *self.payload.get() = payload };
+            // FIX: everything written before a Release store is visible to
+            // a thread whose Acquire load reads that store
+            self.ready.store(true, STORE_ORDERING);
+        }
+
+        pub fn receive(&self) -> [u64; PAYLOAD_WORDS] {
+            while !self.ready.load(LOAD_ORDERING) {
+                thread::yield_now();
+            }
+            unsafe { *self.payload.get() }
+        }
+    }
+}
+
+/// Exhaustive check of the handoff under loom's memory model
+///
+/// The model performs the same steps as `publish` and `receive` with the
+/// orderings each version uses. loom's `UnsafeCell` panics with a causality
+/// violation if the payload read is not ordered after the payload write.
+#[cfg(loom)]
+mod model {
+    use loom::cell::UnsafeCell;
+    use loom::sync::atomic::AtomicBool;
+    use loom::sync::Arc;
+    use loom::thread;
+    use std::panic;
+    use std::sync::atomic::Ordering;
+
+    struct Mailbox {
+        ready: AtomicBool,
+        payload: UnsafeCell<u64>,
+    }
+
+    // Only shared inside loom::model, which checks every access
+    unsafe impl Sync for Mailbox {}
+
+    pub fn check(store: Ordering, load: Ordering) {
+        loom::model(move || {
+            let mailbox = Arc::new(Mailbox {
+                ready: AtomicBool::new(false),
+                payload: UnsafeCell::new(0),
+            });
+
+            let producer = {
+                let mailbox = Arc::clone(&mailbox);
+                thread::spawn(move || {
+                    mailbox.payload.with_mut(|p| unsafe { *p = 42 });
+                    mailbox.ready.store(true, store);
+                })
+            };
+
+            if mailbox.ready.load(load) {
+                let value = mailbox.payload.with(|p| unsafe { *p });
+                assert_eq!(value, 42, "consumer saw ready but a stale payload");
+            }
+
+            producer.join().unwrap();
+        });
+    }
+
+    /// Run `check`, turning a failed model into the panic message
+    pub fn run(store: Ordering, load: Ordering) -> Result<(), String> {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = panic::catch_unwind(|| check(store, load));
+        panic::set_hook(hook);
+
+        result.map_err(|payload| {
+            payload
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_else(|| "model panicked".to_string())
+        })
+    }
+}
+
+/// Hand off `ROUNDS` payloads, one fresh mailbox and thread pair per round.
+/// Returns how many the consumer read torn.
+fn run_handoffs<M: Send + Sync + 'static>(
+    new: fn() -> M,
+    publish: fn(&M, [u64; PAYLOAD_WORDS]),
+    receive: fn(&M) -> [u64; PAYLOAD_WORDS],
+) -> usize {
+    (0..ROUNDS)
+        .filter(|&round| {
+            let mailbox = Arc::new(new());
+            let consumer = {
+                let mailbox = Arc::clone(&mailbox);
+                thread::spawn(move || receive(&mailbox))
+            };
+            publish(&mailbox, payload_for(round));
+            is_torn(&consumer.join().unwrap(), round)
+        })
+        .count()
+}
+
+fn print_native(torn: usize) {
+    println!("=== Results ===");
+    println!("Handoffs: {}", ROUNDS);
+    println!("Torn reads: {}", torn);
+
+    println!("\n=== Metrics ===");
+    println!("rounds: {}", ROUNDS);
+    println!("torn_reads: {}", torn);
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let use_loom = args.iter().any(|arg| arg == "--loom");
+
+    println!("=== Relaxed Ready Flag: Missing Release/Acquire ===\n");
+
+    if use_loom {
+        println!("Checking both versions under loom...\n");
+        run_loom_test();
+    } else if use_fixed {
+        println!("Running FIXED version (Release store, Acquire load)...\n");
+        run_fixed_test();
+    } else {
+        println!("Running BUGGY version (Relaxed store, Relaxed load)...\n");
+        run_buggy_test();
+    }
+}
+
+fn run_buggy_test() {
+    let torn = run_handoffs(
+        buggy::Mailbox::new,
+        buggy::Mailbox::publish,
+        buggy::Mailbox::receive,
+    );
+    print_native(torn);
+
+    if torn > 0 {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("{} consumers saw ready=true and read a payload that was not there yet.", torn);
+        println!("The Relaxed flag store became visible before the payload writes.");
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("No torn reads on this machine. x86 does not reorder these stores,");
+        println!("so the bug is latent here and shows up on ARM and other weak models.");
+        println!("\nRun with --loom to check every ordering the memory model allows.");
+    }
+}
+
+fn run_fixed_test() {
+    let torn = run_handoffs(
+        fixed::Mailbox::new,
+        fixed::Mailbox::publish,
+        fixed::Mailbox::receive,
+    );
+    print_native(torn);
+
+    assert_eq!(torn, 0, "Release/Acquire handoff produced a torn read");
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Every consumer read the full payload.");
+    println!("The Acquire load that sees ready=true also sees the writes before the Release store.");
+}
+
+#[cfg(not(loom))]
+fn run_loom_test() {
+    eprintln!("The loom check is not compiled in. Rebuild with the loom cfg:");
+    eprintln!("  RUSTFLAGS=\"--cfg loom\" cargo run --release -- --loom");
+    std::process::exit(2);
+}
+
+#[cfg(loom)]
+fn run_loom_test() {
+    let buggy = model::run(buggy::STORE_ORDERING, buggy::LOAD_ORDERING);
+    let fixed = model::run(fixed::STORE_ORDERING, fixed::LOAD_ORDERING);
+
+    let describe = |result: &Result<(), String>| match result {
+        Ok(()) => "no violation in any execution".to_string(),
+        Err(message) => format!("FAILED: {}", message.lines().next().unwrap_or_default()),
+    };
+    println!(
+        "[BUGGY] {:?} store / {:?} load: {}",
+        buggy::STORE_ORDERING,
+        buggy::LOAD_ORDERING,
+        describe(&buggy)
+    );
+    println!(
+        "[FIXED] {:?} store / {:?} load: {}",
+        fixed::STORE_ORDERING,
+        fixed::LOAD_ORDERING,
+        describe(&fixed)
+    );
+
+    assert!(buggy.is_err(), "loom must find the unordered payload read");
+    assert!(fixed.is_ok(), "loom found a violation in the Release/Acquire handoff");
+
+    println!("\n=== Results ===");
+    print_banner("[BUG DEMONSTRATED]");
+    println!("loom found an execution where the payload read is not ordered after its write.");
+    print_banner("[FIXED]");
+    println!("With Release/Acquire every execution orders the payload write before the read.");
+}
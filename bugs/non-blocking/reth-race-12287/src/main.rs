@@ -9,9 +9,9 @@
 use std::collections::HashMap;
 use std::env;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Barrier, Mutex, RwLock, RwLockReadGuard};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum SubPool {
@@ -19,6 +19,33 @@ enum SubPool {
     Queued,  // Waiting for nonce gap to be filled
 }
 
+/// One classification decision: the nonce the pool acted on, and the
+/// account's actual nonce at the moment the decision was made
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    tx: String,
+    seen_nonce: u64,
+    actual_nonce: u64,
+    pool: SubPool,
+}
+
+impl AuditEntry {
+    fn is_mismatch(&self) -> bool {
+        self.seen_nonce != self.actual_nonce
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"tx\": \"{}\", \"seen_nonce\": {}, \"actual_nonce\": {}, \"pool\": \"{:?}\", \"mismatch\": {}}}",
+            self.tx,
+            self.seen_nonce,
+            self.actual_nonce,
+            self.pool,
+            self.is_mismatch()
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Transaction {
     pub from: String,
@@ -46,6 +73,11 @@ impl AccountState {
         *nonces.get(account).unwrap_or(&0)
     }
 
+    /// Hold the nonce table so no block can be mined until the guard drops
+    fn read_nonces(&self) -> RwLockReadGuard<'_, HashMap<String, u64>> {
+        self.nonces.read().unwrap()
+    }
+
     fn increment_nonce(&self, account: &str) {
         let mut nonces = self.nonces.write().unwrap();
         if let Some(nonce) = nonces.get_mut(account) {
@@ -63,15 +95,33 @@ mod buggy {
         pending: Mutex<Vec<Transaction>>,
         queued: Mutex<Vec<Transaction>>,
         misclassified: Arc<AtomicU64>,
+        audit: Mutex<Vec<AuditEntry>>,
+        race_window: Duration,
+        /// Rendezvous with the miner after validation, and again once it
+        /// has mined
+        mined: Option<Arc<Barrier>>,
     }
 
     impl TxPool {
-        pub fn new(state: Arc<AccountState>) -> Self {
+        pub fn new(state: Arc<AccountState>, race_window: Duration) -> Self {
             Self {
                 state,
                 pending: Mutex::new(Vec::new()),
                 queued: Mutex::new(Vec::new()),
                 misclassified: Arc::new(AtomicU64::new(0)),
+                audit: Mutex::new(Vec::new()),
+                race_window,
+                mined: None,
+            }
+        }
+
+        /// Wait on `barrier` twice between validation and classification.
+        /// A miner that waits on it before and after each block mines that
+        /// block inside the race window, whatever the scheduler does.
+        pub fn with_barrier(state: Arc<AccountState>, race_window: Duration, barrier: Arc<Barrier>) -> Self {
+            Self {
+                mined: Some(barrier),
+                ..Self::new(state, race_window)
             }
         }
 
@@ -86,6 +136,10 @@ mod buggy {
         pub fn add_transaction(&self, tx: Transaction) -> SubPool {
             // Step 1: Validate against current state
             let (valid, expected_nonce) = self.validate(&tx);
+            if let Some(barrier) = &self.mined {
+                barrier.wait();
+                barrier.wait();
+            }
 
             if !valid {
                 println!(
@@ -100,14 +154,21 @@ mod buggy {
             // that changes the expected_nonce
 
             // Simulate some processing delay
-            thread::sleep(Duration::from_micros(100));
+            thread::sleep(self.race_window);
 
             // Step 2: Determine pool based on STALE nonce info
+            let actual_nonce = self.state.get_nonce(&tx.from);
             let pool = if tx.nonce == expected_nonce {
                 SubPool::Pending
             } else {
                 SubPool::Queued // Nonce gap detected
             };
+            self.audit.lock().unwrap().push(AuditEntry {
+                tx: tx.data.clone(),
+                seen_nonce: expected_nonce,
+                actual_nonce,
+                pool,
+            });
 
             // Add to appropriate pool
             match pool {
@@ -145,6 +206,10 @@ mod buggy {
         pub fn get_misclassified(&self) -> u64 {
             self.misclassified.load(Ordering::SeqCst)
         }
+
+        pub fn audit(&self) -> Vec<AuditEntry> {
+            self.audit.lock().unwrap().clone()
+        }
     }
 }
 
@@ -158,6 +223,7 @@ mod fixed {
         queued: Mutex<Vec<Transaction>>,
         // Lock to ensure atomic validate-and-add
         add_lock: Mutex<()>,
+        audit: Mutex<Vec<AuditEntry>>,
     }
 
     impl TxPool {
@@ -167,16 +233,24 @@ mod fixed {
                 pending: Mutex::new(Vec::new()),
                 queued: Mutex::new(Vec::new()),
                 add_lock: Mutex::new(()),
+                audit: Mutex::new(Vec::new()),
             }
         }
 
+        pub fn audit(&self) -> Vec<AuditEntry> {
+            self.audit.lock().unwrap().clone()
+        }
+
         /// FIX: Atomic validate and add
         pub fn add_transaction(&self, tx: Transaction) -> SubPool {
             // Hold lock during entire validate-and-add sequence
             let _guard = self.add_lock.lock().unwrap();
+            // Holding the nonce table also keeps blocks from being mined
+            // between reading the nonce and classifying against it
+            let nonces = self.state.read_nonces();
 
             // Validate and determine pool atomically
-            let expected_nonce = self.state.get_nonce(&tx.from);
+            let expected_nonce = *nonces.get(&tx.from).unwrap_or(&0);
 
             if tx.nonce < expected_nonce {
                 println!(
@@ -192,6 +266,13 @@ mod fixed {
                 SubPool::Queued
             };
 
+            self.audit.lock().unwrap().push(AuditEntry {
+                tx: tx.data.clone(),
+                seen_nonce: expected_nonce,
+                actual_nonce: *nonces.get(&tx.from).unwrap_or(&0),
+                pool,
+            });
+
             // Add to pool while still holding lock
             match pool {
                 SubPool::Pending => {
@@ -210,24 +291,97 @@ mod fixed {
     }
 }
 
+/// Default delay between validation and classification in the buggy pool
+const DEFAULT_WINDOW_US: u64 = 100;
+/// A window this wide spans the whole mining thread, so the race is certain
+const GUARANTEED_WINDOW_US: u64 = 1000;
+/// Runs per coordination mode in `--barrier-study`
+const DEFAULT_STUDY_ITERATIONS: usize = 20;
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let audit_json = args.iter().any(|arg| arg == "--audit-json");
+    let use_barrier = !args.iter().any(|arg| arg == "--sleep");
+    let use_csv = args.iter().any(|arg| arg == "--csv");
+    let window_us = args
+        .iter()
+        .position(|arg| arg == "--window-us")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_WINDOW_US);
 
     println!("=== Reth Issue #12287: Transaction Pool Nonce Race ===\n");
 
-    if use_fixed {
+    if args.iter().any(|arg| arg == "--barrier-study") {
+        let iterations = args
+            .iter()
+            .position(|arg| arg == "--iterations")
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_STUDY_ITERATIONS);
+        println!("Running BUGGY version {} times per coordination mode...\n", iterations);
+        run_barrier_study(window_us, iterations, use_csv);
+    } else if use_fixed {
         println!("Running FIXED version (atomic validate-and-add)...\n");
-        run_fixed_test();
+        run_fixed_test(audit_json, use_barrier);
     } else {
         println!("Running BUGGY version (racy validate then add)...\n");
-        run_buggy_test();
+        run_buggy_test(window_us, audit_json, use_barrier);
     }
 }
 
-fn run_buggy_test() {
+/// Print the classification audit as metrics, and optionally as JSON lines
+fn print_audit(audit: &[AuditEntry], audit_json: bool) -> usize {
+    let mismatches = audit.iter().filter(|e| e.is_mismatch()).count();
+    println!("\n=== Metrics ===");
+    println!("audit_entries: {}", audit.len());
+    println!("audit_mismatches: {}", mismatches);
+    if audit_json {
+        println!("\n=== Audit (JSON) ===");
+        for entry in audit {
+            println!("{}", entry.to_json());
+        }
+    }
+    mismatches
+}
+
+/// Submit 10 transactions while a miner bumps the nonce 10 times. With a
+/// barrier, each block is mined while the matching transaction sits between
+/// validate and classify. Without one (`--sleep`) the two threads are only
+/// staggered with sleeps.
+fn run_buggy_workload(window_us: u64, use_barrier: bool) -> Arc<buggy::TxPool> {
     let state = Arc::new(AccountState::new());
-    let pool = Arc::new(buggy::TxPool::new(Arc::clone(&state)));
+    let window = Duration::from_micros(window_us);
+    let barrier = use_barrier.then(|| Arc::new(Barrier::new(2)));
+    let pool = Arc::new(match &barrier {
+        Some(barrier) => buggy::TxPool::with_barrier(Arc::clone(&state), window, Arc::clone(barrier)),
+        None => buggy::TxPool::new(Arc::clone(&state), window),
+    });
 
     let mut handles = vec![];
 
@@ -249,36 +403,162 @@ fn run_buggy_test() {
     let state2 = Arc::clone(&state);
     handles.push(thread::spawn(move || {
         for _ in 0..10 {
-            thread::sleep(Duration::from_micros(50));
-            state2.increment_nonce("alice");
+            match &barrier {
+                Some(barrier) => {
+                    // Mine only after the pool validated, and let it
+                    // classify only after the block is in
+                    barrier.wait();
+                    state2.increment_nonce("alice");
+                    barrier.wait();
+                }
+                None => {
+                    thread::sleep(Duration::from_micros(50));
+                    state2.increment_nonce("alice");
+                }
+            }
         }
     }));
 
     for handle in handles {
         handle.join().unwrap();
     }
+    pool
+}
+
+fn run_buggy_test(window_us: u64, audit_json: bool, use_barrier: bool) {
+    let pool = run_buggy_workload(window_us, use_barrier);
 
     let misclassified = pool.get_misclassified();
     println!("\n=== Results ===");
     println!("Misclassified transactions: {}", misclassified);
 
     if misclassified > 0 {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Transactions were placed in wrong pools due to TOCTOU race.");
     } else {
-        println!("\n[NOTE]");
-        println!("No misclassification this run (timing-dependent).");
+        println!();
+        print_banner("[NOTE]");
+        println!("No misclassification this run. With --sleep, hitting the window depends on timing.");
+    }
+
+    let mismatches = print_audit(&pool.audit(), audit_json);
+    if use_barrier {
+        assert!(misclassified >= 1, "every barrier-driven run should misclassify a transaction");
+        assert!(mismatches > 0, "barrier-driven blocks should expose the stale nonce");
+    } else if window_us >= GUARANTEED_WINDOW_US {
+        assert!(mismatches > 0, "a {}µs window should expose the stale nonce", window_us);
     }
     println!("\nRun with --fixed to see atomic version.");
 }
 
-fn run_fixed_test() {
+/// Print `--csv` output: a header, then one line per row. Fields containing a
+/// comma, quote or newline are quoted.
+///
+/// Each crate builds on its own, so this and `csv_field` are copied into
+/// ballista-deadlock-132, sui-blocking-5201, reth-race-12287 and
+/// surrealdb-race-5042. Keep the copies identical.
+fn print_csv(header: &[&str], rows: &[Vec<String>]) {
+    println!("\n=== CSV ===");
+    println!("{}", header.join(","));
+    for row in rows {
+        assert_eq!(row.len(), header.len(), "CSV row width must match the header");
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        println!("{}", fields.join(","));
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Run the buggy workload `iterations` times with sleeps and with a barrier,
+/// and compare how often each run exposed the race
+fn run_barrier_study(window_us: u64, iterations: usize, use_csv: bool) {
+    const TXS_PER_RUN: usize = 10;
+    let mut csv = Vec::new();
+    // (runs with any misclassification, total misclassified transactions)
+    let mut study = |use_barrier: bool| {
+        let coordination = if use_barrier { "barrier" } else { "sleep" };
+        (0..iterations)
+            .map(|iteration| {
+                let started = Instant::now();
+                let misclassified = run_buggy_workload(window_us, use_barrier).get_misclassified() as usize;
+                csv.push(vec![
+                    iteration.to_string(),
+                    (misclassified > 0).to_string(),
+                    started.elapsed().as_millis().to_string(),
+                    coordination.to_string(),
+                    misclassified.to_string(),
+                ]);
+                misclassified
+            })
+            .fold((0, 0), |(runs, txs), misclassified| {
+                (runs + usize::from(misclassified > 0), txs + misclassified)
+            })
+    };
+    let (sleep_runs, sleep_txs) = study(false);
+    let (barrier_runs, barrier_txs) = study(true);
+    // The fixed pool under the same barrier schedule, which must never act
+    // on a stale nonce
+    let fixed_mismatches: Vec<usize> = (0..iterations)
+        .map(|iteration| {
+            let started = Instant::now();
+            let mismatches = run_fixed_workload(true).audit().iter().filter(|e| e.is_mismatch()).count();
+            csv.push(vec![
+                iteration.to_string(),
+                (mismatches > 0).to_string(),
+                started.elapsed().as_millis().to_string(),
+                "barrier-fixed".to_string(),
+                mismatches.to_string(),
+            ]);
+            mismatches
+        })
+        .collect();
+    let total_txs = iterations * TXS_PER_RUN;
+    if use_csv {
+        print_csv(&["iteration", "bug_observed", "elapsed_ms", "coordination", "misclassified"], &csv);
+    }
+
+    println!("\n=== Results ===");
+    println!("Sleep-staggered: {}/{} runs, {}/{} txs misclassified", sleep_runs, iterations, sleep_txs, total_txs);
+    println!("Barrier-coordinated: {}/{} runs, {}/{} txs misclassified", barrier_runs, iterations, barrier_txs, total_txs);
+    println!("Barrier-coordinated (fixed): {}/{} runs with a stale classification", fixed_mismatches.iter().filter(|&&m| m > 0).count(), iterations);
+
+    println!("\n=== Metrics ===");
+    println!("window_us: {}", window_us);
+    println!("iterations: {}", iterations);
+    println!("sleep_bug_rate: {:.2}", sleep_txs as f64 / total_txs as f64);
+    println!("barrier_bug_rate: {:.2}", barrier_txs as f64 / total_txs as f64);
+
+    assert_eq!(barrier_runs, iterations, "every barrier-coordinated run should misclassify");
+    assert_eq!(barrier_txs, total_txs, "every barrier-coordinated block should land in the window");
+    assert!(fixed_mismatches.iter().all(|&m| m == 0), "the fixed pool classified against a stale nonce");
+    assert!(barrier_txs >= sleep_txs);
+
+    println!();
+    print_banner("[BUG DEMONSTRATED]");
+    println!("The barrier puts every block inside the validate/classify window.");
+    println!("The sleep-staggered runs only hit it when the scheduler lines them up.");
+}
+
+/// Run the fixed pool's workload, with the miner either meeting the submitter
+/// at a barrier before each block or staggered with sleeps
+fn run_fixed_workload(use_barrier: bool) -> Arc<fixed::TxPool> {
     let state = Arc::new(AccountState::new());
     let pool = Arc::new(fixed::TxPool::new(Arc::clone(&state)));
+    // The fixed pool has no window to wait in, so both threads rendezvous
+    // just before submitting and mining instead
+    let barrier = use_barrier.then(|| Arc::new(Barrier::new(2)));
 
     let mut handles = vec![];
 
     let pool1 = Arc::clone(&pool);
+    let barrier1 = barrier.clone();
     handles.push(thread::spawn(move || {
         for i in 0..10 {
             let tx = Transaction {
@@ -286,6 +566,9 @@ fn run_fixed_test() {
                 nonce: i,
                 data: format!("tx_{}", i),
             };
+            if let Some(barrier) = &barrier1 {
+                barrier.wait();
+            }
             pool1.add_transaction(tx);
             thread::sleep(Duration::from_millis(1));
         }
@@ -294,7 +577,12 @@ fn run_fixed_test() {
     let state2 = Arc::clone(&state);
     handles.push(thread::spawn(move || {
         for _ in 0..10 {
-            thread::sleep(Duration::from_micros(50));
+            match &barrier {
+                Some(barrier) => {
+                    barrier.wait();
+                }
+                None => thread::sleep(Duration::from_micros(50)),
+            }
             state2.increment_nonce("alice");
         }
     }));
@@ -302,8 +590,16 @@ fn run_fixed_test() {
     for handle in handles {
         handle.join().unwrap();
     }
+    pool
+}
+
+fn run_fixed_test(audit_json: bool, use_barrier: bool) {
+    let pool = run_fixed_workload(use_barrier);
 
     println!("\n=== Results ===");
-    println!("[FIXED]");
+    print_banner("[FIXED]");
     println!("Atomic validate-and-add prevents race condition.");
+
+    let mismatches = print_audit(&pool.audit(), audit_json);
+    assert_eq!(mismatches, 0, "fixed pool must classify against the current nonce");
 }
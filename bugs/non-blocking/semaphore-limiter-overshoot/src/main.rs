@@ -0,0 +1,279 @@
+//! Semaphore Limiter Overshoot
+//!
+//! This reproduces a bounded concurrency limiter that admits more tasks than
+//! its limit. The buggy limiter checks an `AtomicUsize` against the limit and
+//! increments it in a separate step, so several tasks can pass the check
+//! before any of them increments.
+//!
+//! Pattern reproduction: not tied to a single upstream issue. It is the
+//! limiter counterpart of the bounded executors in sui-blocking-5204, where
+//! the channel capacity is what bounds the work in flight.
+
+use std::cell::Cell;
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MAX_CONCURRENT: usize = 3;
+const TASKS: usize = 8;
+const TASK_DURATION: Duration = Duration::from_millis(20);
+
+/// Tracks how many tasks are inside the limited section right now, and the
+/// most there ever were
+#[derive(Default)]
+pub struct ConcurrencyProbe {
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl ConcurrencyProbe {
+    fn enter(&self) -> usize {
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        now
+    }
+
+    fn exit(&self) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+/// Buggy version - check-then-increment on an atomic counter
+mod buggy {
+    use super::*;
+
+    pub struct Limiter {
+        active: AtomicUsize,
+        max_concurrent: usize,
+    }
+
+    impl Limiter {
+        pub fn new(max_concurrent: usize) -> Self {
+            Self {
+                active: AtomicUsize::new(0),
+                max_concurrent,
+            }
+        }
+
+        /// BUG: the check and the increment are two separate operations
+        pub fn acquire(&self) {
+            loop {
+                if self.active.load(Ordering::SeqCst) < self.max_concurrent {
+                    // BUG: Race window! Other tasks can pass the same check here
+                    thread::sleep(Duration::from_micros(10));
+                    self.active.fetch_add(1, Ordering::SeqCst);
+                    return;
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        pub fn release(&self) {
+            self.active.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Fixed version - counting semaphore built on Mutex + Condvar
+mod fixed {
+    use super::*;
+
+    pub struct Semaphore {
+        permits: Mutex<usize>,
+        released: Condvar,
+    }
+
+    /// Returns its permit to the semaphore when dropped
+    pub struct SemaphorePermit<'a> {
+        semaphore: &'a Semaphore,
+    }
+
+    impl Semaphore {
+        pub fn new(max_concurrent: usize) -> Self {
+            Self {
+                permits: Mutex::new(max_concurrent),
+                released: Condvar::new(),
+            }
+        }
+
+        /// FIX: check and decrement happen under the same lock
+        pub fn acquire(&self) -> SemaphorePermit<'_> {
+            let mut permits = self.permits.lock().unwrap();
+            while *permits == 0 {
+                permits = self.released.wait(permits).unwrap();
+            }
+            *permits -= 1;
+            SemaphorePermit { semaphore: self }
+        }
+    }
+
+    impl Drop for SemaphorePermit<'_> {
+        fn drop(&mut self) {
+            *self.semaphore.permits.lock().unwrap() += 1;
+            self.semaphore.released.notify_one();
+        }
+    }
+}
+
+/// Peak concurrency and timing for one batch of tasks
+#[derive(Debug)]
+pub struct LimiterOutcome {
+    peak: usize,
+    max_wait: Duration,
+    elapsed: Duration,
+}
+
+/// Spawn `TASKS` tasks that each run `task` around a probed critical section
+fn run_tasks<F>(task: F, prefix: &'static str) -> LimiterOutcome
+where
+    F: Fn(&dyn Fn()) + Send + Sync + 'static,
+{
+    let probe = Arc::new(ConcurrencyProbe::default());
+    let task = Arc::new(task);
+    let start = Instant::now();
+
+    let handles: Vec<_> = (0..TASKS)
+        .map(|i| {
+            let probe = Arc::clone(&probe);
+            let task = Arc::clone(&task);
+            thread::spawn(move || {
+                let queued = Instant::now();
+                let waited = Cell::new(Duration::ZERO);
+                task(&|| {
+                    waited.set(queued.elapsed());
+                    let now = probe.enter();
+                    println!("{} Task {} admitted, {} in flight", prefix, i, now);
+                    thread::sleep(TASK_DURATION);
+                    probe.exit();
+                });
+                waited.get()
+            })
+        })
+        .collect();
+
+    let max_wait = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .max()
+        .unwrap_or_default();
+
+    LimiterOutcome {
+        peak: probe.peak(),
+        max_wait,
+        elapsed: start.elapsed(),
+    }
+}
+
+fn print_outcome(outcome: &LimiterOutcome) {
+    println!("\n=== Results ===");
+    println!("Limit: {}", MAX_CONCURRENT);
+    println!("Peak concurrency: {}", outcome.peak);
+
+    println!("\n=== Metrics ===");
+    println!("tasks: {}", TASKS);
+    println!("peak_concurrency: {}", outcome.peak);
+    println!("max_wait_ms: {}", outcome.max_wait.as_millis());
+    println!("elapsed_ms: {}", outcome.elapsed.as_millis());
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let use_fixed = args.iter().any(|arg| arg == "--fixed");
+
+    println!("=== Semaphore Limiter Overshoot ===\n");
+
+    if use_fixed {
+        println!("Running FIXED version (Mutex + Condvar semaphore)...\n");
+        run_fixed_test();
+    } else {
+        println!("Running BUGGY version (AtomicUsize check-then-increment)...\n");
+        run_buggy_test();
+    }
+}
+
+fn run_buggy_test() {
+    println!("Limit: {}, tasks: {}, each holds a slot for {:?}\n", MAX_CONCURRENT, TASKS, TASK_DURATION);
+
+    let limiter = Arc::new(buggy::Limiter::new(MAX_CONCURRENT));
+    let outcome = run_tasks(
+        move |work| {
+            limiter.acquire();
+            work();
+            limiter.release();
+        },
+        "[BUGGY]",
+    );
+    print_outcome(&outcome);
+
+    if outcome.peak > MAX_CONCURRENT {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!(
+            "{} tasks ran at once against a limit of {}.",
+            outcome.peak, MAX_CONCURRENT
+        );
+        println!("They all saw active < limit before any of them incremented it.");
+        println!("\nRun with --fixed to see the semaphore hold the limit.");
+    } else {
+        println!();
+        print_banner("[NOTE]");
+        println!("The limit held this run (timing-dependent race).");
+        println!("Try running multiple times to see the bug.");
+    }
+}
+
+fn run_fixed_test() {
+    println!("Limit: {}, tasks: {}, each holds a slot for {:?}\n", MAX_CONCURRENT, TASKS, TASK_DURATION);
+
+    let semaphore = Arc::new(fixed::Semaphore::new(MAX_CONCURRENT));
+    let outcome = run_tasks(
+        move |work| {
+            let _permit = semaphore.acquire();
+            work();
+        },
+        "[FIXED]",
+    );
+    print_outcome(&outcome);
+
+    assert!(
+        outcome.peak <= MAX_CONCURRENT,
+        "semaphore admitted {} tasks against a limit of {}",
+        outcome.peak,
+        MAX_CONCURRENT
+    );
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Peak concurrency never exceeded the limit.");
+    println!("Admission and the permit count change under one lock.");
+}
@@ -172,6 +172,30 @@ mod fixed_once {
     }
 }
 
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
@@ -218,7 +242,8 @@ fn run_buggy_test() {
     println!("Total storage loads: {}", load_count);
 
     if load_count > 1 {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Configuration was loaded {} times instead of once!", load_count);
         println!("Multiple threads raced to load the same configuration.");
         println!("This causes:");
@@ -226,7 +251,8 @@ fn run_buggy_test() {
         println!("  - Potential inconsistent state");
         println!("  - Unnecessary resource usage");
     } else {
-        println!("\n[NOTE]");
+        println!();
+        print_banner("[NOTE]");
         println!("Race did not manifest this time (timing-dependent).");
         println!("Try running multiple times.");
     }
@@ -260,7 +286,8 @@ fn run_fixed_test() {
     let load_count = storage.get_load_count();
     println!("\n=== Results ===");
     println!("Total storage loads: {}", load_count);
-    println!("\n[FIXED]");
+    println!();
+    print_banner("[FIXED]");
     println!("Configuration loaded exactly once during app initialization.");
     println!("All components reuse the cached value.");
 }
@@ -290,7 +317,8 @@ fn run_fixed_once_test() {
     let load_count = storage.get_load_count();
     println!("\n=== Results ===");
     println!("Total storage loads: {}", load_count);
-    println!("\n[FIXED-ONCE]");
+    println!();
+    print_banner("[FIXED-ONCE]");
     println!("std::sync::Once ensures exactly-once initialization.");
     println!("First thread loads, others wait for completion.");
 }
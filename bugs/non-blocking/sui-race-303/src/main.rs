@@ -9,7 +9,7 @@
 use std::collections::HashMap;
 use std::env;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Barrier, Mutex, RwLock};
 use std::thread;
 use std::time::Duration;
 
@@ -22,6 +22,110 @@ pub struct Order {
     amount: u64,
 }
 
+/// One recorded operation, spanning two ticks of the log's clock
+#[derive(Clone, Debug)]
+pub struct Event {
+    name: String,
+    begin: u64,
+    end: u64,
+}
+
+/// Ordered record of what each thread did, for asserting interleavings.
+/// `a` happens before `b` if `a` ended before `b` began; the two are
+/// concurrent if neither happens before the other.
+#[derive(Default)]
+pub struct EventLog {
+    clock: AtomicU64,
+    events: Mutex<Vec<Event>>,
+}
+
+impl EventLog {
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Run `op` and record it as `name`, spanning the whole call
+    pub fn record<T>(&self, name: &str, op: impl FnOnce() -> T) -> T {
+        let begin = self.tick();
+        let result = op();
+        let end = self.tick();
+        self.events.lock().unwrap().push(Event {
+            name: name.to_string(),
+            begin,
+            end,
+        });
+        result
+    }
+
+    fn find(&self, name: &str) -> Event {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|event| event.name == name)
+            .cloned()
+            .unwrap_or_else(|| panic!("no event {:?} in the log:\n{}", name, self.render()))
+    }
+
+    pub fn happens_before(&self, a: &str, b: &str) -> bool {
+        self.find(a).end < self.find(b).begin
+    }
+
+    pub fn assert_happens_before(&self, a: &str, b: &str) {
+        assert!(
+            self.happens_before(a, b),
+            "expected {} to happen before {}:\n{}",
+            a,
+            b,
+            self.render()
+        );
+    }
+
+    pub fn assert_concurrent(&self, a: &str, b: &str) {
+        assert!(
+            !self.happens_before(a, b) && !self.happens_before(b, a),
+            "expected {} and {} to be concurrent:\n{}",
+            a,
+            b,
+            self.render()
+        );
+    }
+
+    /// Events in the order they began, one `[begin, end] name` per line
+    pub fn render(&self) -> String {
+        let mut events = self.events.lock().unwrap().clone();
+        events.sort_by_key(|event| event.begin);
+        events
+            .iter()
+            .map(|event| format!("  [{:>2}, {:>2}] {}\n", event.begin, event.end, event.name))
+            .collect()
+    }
+}
+
+/// Name of the current thread, used to prefix logged events
+fn thread_label() -> String {
+    thread::current().name().unwrap_or("main").to_string()
+}
+
+/// Forces the lost-update interleaving for `--interleave`: every thread
+/// waits after its read until all of them have read
+pub struct ReadHook {
+    reads_done: Barrier,
+}
+
+impl ReadHook {
+    fn new(threads: usize) -> Self {
+        Self {
+            reads_done: Barrier::new(threads),
+        }
+    }
+
+    /// Called by the buggy version between its read and its write
+    fn after_read(&self) {
+        self.reads_done.wait();
+    }
+}
+
 /// Buggy client API - non-atomic read-modify-write
 mod buggy {
     use super::*;
@@ -30,6 +134,10 @@ mod buggy {
         // BUG: Using RwLock but doing non-atomic read-modify-write
         pending_orders: RwLock<HashMap<String, u64>>,
         lost_updates: AtomicU64,
+        /// `--overhead`: no logging and no simulated processing delay
+        quiet: bool,
+        hook: Option<Arc<ReadHook>>,
+        log: Option<Arc<EventLog>>,
     }
 
     impl ClientAPI {
@@ -37,30 +145,68 @@ mod buggy {
             Self {
                 pending_orders: RwLock::new(HashMap::new()),
                 lost_updates: AtomicU64::new(0),
+                quiet: false,
+                hook: None,
+                log: None,
+            }
+        }
+
+        /// API that pauses at `hook` after each read and records its reads
+        /// and writes in `log`
+        pub fn with_hook(hook: Arc<ReadHook>, log: Arc<EventLog>) -> Self {
+            Self {
+                hook: Some(hook),
+                log: Some(log),
+                ..Self::new()
+            }
+        }
+
+        /// Run `op`, logged as `<thread>.<step>` when there is a log
+        fn step<T>(&self, step: &str, op: impl FnOnce() -> T) -> T {
+            match &self.log {
+                Some(log) => log.record(&format!("{}.{}", thread_label(), step), op),
+                None => op(),
+            }
+        }
+
+        /// API that only does the locking, for timing the uncontended path
+        pub fn quiet() -> Self {
+            Self {
+                quiet: true,
+                ..Self::new()
             }
         }
 
         /// BUG: Non-atomic read-modify-write sequence
         pub fn add_pending_order(&self, account: &str, amount: u64) {
             // Step 1: Read current value
-            let current = {
+            let current = self.step("read", || {
                 let orders = self.pending_orders.read().unwrap();
                 *orders.get(account).unwrap_or(&0)
-            };
+            });
             // Lock is released here!
 
+            if let Some(hook) = &self.hook {
+                hook.after_read();
+            }
+
             // BUG: Race window! Another thread can modify the value here
-            thread::sleep(Duration::from_micros(10)); // Simulate processing
+            if !self.quiet {
+                thread::sleep(Duration::from_micros(10)); // Simulate processing
+            }
 
             // Step 2: Compute new value
             let new_value = current + amount;
 
             // Step 3: Write new value
-            {
+            self.step("write", || {
                 let mut orders = self.pending_orders.write().unwrap();
                 orders.insert(account.to_string(), new_value);
-            }
+            });
 
+            if self.quiet {
+                return;
+            }
             println!(
                 "[BUGGY] Added {} to account '{}' (read: {}, wrote: {})",
                 amount, account, current, new_value
@@ -101,30 +247,92 @@ mod fixed {
     pub struct ClientAPI {
         // FIX: Keep write lock during entire read-modify-write sequence
         pending_orders: Mutex<HashMap<String, u64>>,
+        /// `--inject-regression`: drop the lock between the read and the write
+        inject_regression: bool,
+        /// `--overhead`: no logging
+        quiet: bool,
+        log: Option<Arc<EventLog>>,
     }
 
     impl ClientAPI {
         pub fn new() -> Self {
             Self {
                 pending_orders: Mutex::new(HashMap::new()),
+                inject_regression: false,
+                quiet: false,
+                log: None,
+            }
+        }
+
+        /// API that records each locked read-modify-write in `log`
+        pub fn with_log(log: Arc<EventLog>) -> Self {
+            Self {
+                log: Some(log),
+                ..Self::new()
+            }
+        }
+
+        /// API that only does the locking, for timing the uncontended path
+        pub fn quiet() -> Self {
+            Self {
+                quiet: true,
+                ..Self::new()
+            }
+        }
+
+        /// Fixed API with the original bug put back, to check that the
+        /// invariant checks notice it
+        pub fn with_injected_regression() -> Self {
+            Self {
+                inject_regression: true,
+                ..Self::new()
             }
         }
 
         /// FIX: Atomic read-modify-write with single lock acquisition
         pub fn add_pending_order(&self, account: &str, amount: u64) {
+            if self.inject_regression {
+                self.add_pending_order_unlocked(account, amount);
+                return;
+            }
+
             let mut orders = self.pending_orders.lock().unwrap();
 
             // Perform read-modify-write atomically under lock
-            let current = *orders.get(account).unwrap_or(&0);
-            let new_value = current + amount;
-            orders.insert(account.to_string(), new_value);
+            let mut rmw = || {
+                let current = *orders.get(account).unwrap_or(&0);
+                let new_value = current + amount;
+                orders.insert(account.to_string(), new_value);
+                (current, new_value)
+            };
+            let (current, new_value) = match &self.log {
+                Some(log) => log.record(&format!("{}.rmw", thread_label()), rmw),
+                None => rmw(),
+            };
 
+            if self.quiet {
+                return;
+            }
             println!(
                 "[FIXED] Added {} to account '{}' (read: {}, wrote: {})",
                 amount, account, current, new_value
             );
         }
 
+        /// REGRESSION: the buggy read-modify-write, with the lock released
+        /// between the read and the write
+        fn add_pending_order_unlocked(&self, account: &str, amount: u64) {
+            let current = *self.pending_orders.lock().unwrap().get(account).unwrap_or(&0);
+            thread::sleep(Duration::from_micros(10));
+            let new_value = current + amount;
+            self.pending_orders.lock().unwrap().insert(account.to_string(), new_value);
+
+            println!(
+                "[FIXED] Added {} to account '{}' (read: {}, wrote: {}, regression injected)",
+                amount, account, current, new_value
+            );
+        }
+
         pub fn get_pending(&self, account: &str) -> u64 {
             let orders = self.pending_orders.lock().unwrap();
             *orders.get(account).unwrap_or(&0)
@@ -183,26 +391,257 @@ mod fixed_atomic {
     }
 }
 
+/// Alternative fix using a compare-and-swap retry loop
+mod fixed_cas {
+    use super::*;
+
+    pub struct ClientAPI {
+        pending_orders: RwLock<HashMap<String, AtomicU64>>,
+        cas_retries: AtomicU64,
+    }
+
+    impl ClientAPI {
+        pub fn new() -> Self {
+            Self {
+                pending_orders: RwLock::new(HashMap::new()),
+                cas_retries: AtomicU64::new(0),
+            }
+        }
+
+        /// FIX: read, compute, then CAS; retry from a fresh read if another
+        /// thread changed the value in between
+        pub fn add_pending_order(&self, account: &str, amount: u64) {
+            if !self.pending_orders.read().unwrap().contains_key(account) {
+                self.pending_orders
+                    .write()
+                    .unwrap()
+                    .entry(account.to_string())
+                    .or_insert(AtomicU64::new(0));
+            }
+
+            let orders = self.pending_orders.read().unwrap();
+            let counter = orders.get(account).unwrap();
+            let mut current = counter.load(Ordering::SeqCst);
+            loop {
+                // Same race window as the buggy version
+                thread::sleep(Duration::from_micros(10));
+                let new_value = current + amount;
+
+                match counter.compare_exchange(current, new_value, Ordering::SeqCst, Ordering::SeqCst) {
+                    Ok(_) => return,
+                    Err(actual) => {
+                        // Someone else wrote first: recompute from their value
+                        self.cas_retries.fetch_add(1, Ordering::SeqCst);
+                        current = actual;
+                    }
+                }
+            }
+        }
+
+        pub fn get_pending(&self, account: &str) -> u64 {
+            let orders = self.pending_orders.read().unwrap();
+            orders
+                .get(account)
+                .map(|v| v.load(Ordering::SeqCst))
+                .unwrap_or(0)
+        }
+
+        pub fn cas_retries(&self) -> u64 {
+            self.cas_retries.load(Ordering::SeqCst)
+        }
+    }
+}
+
+/// Exhaustive interleaving explorer over the coarse steps of add_pending_order
+///
+/// Each thread's call is modelled as the same labeled steps the real code
+/// performs (buggy: read, then write; fixed: one locked read-modify-write).
+/// Every interleaving of those steps is replayed against a plain counter.
+mod explore {
+    #[derive(Clone, Copy, Debug)]
+    pub enum Step {
+        Read,
+        Write,
+        ReadModifyWrite,
+    }
+
+    pub fn thread_steps(fixed: bool) -> Vec<Step> {
+        if fixed {
+            vec![Step::ReadModifyWrite]
+        } else {
+            vec![Step::Read, Step::Write]
+        }
+    }
+
+    /// All orderings of thread ids where thread `t` appears `steps` times
+    pub fn interleavings(threads: usize, steps: usize) -> Vec<Vec<usize>> {
+        fn extend(remaining: &mut [usize], current: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+            if remaining.iter().all(|&r| r == 0) {
+                out.push(current.clone());
+                return;
+            }
+            for t in 0..remaining.len() {
+                if remaining[t] > 0 {
+                    remaining[t] -= 1;
+                    current.push(t);
+                    extend(remaining, current, out);
+                    current.pop();
+                    remaining[t] += 1;
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        extend(&mut vec![steps; threads], &mut Vec::new(), &mut out);
+        out
+    }
+
+    /// Replay one schedule and return the final pending total
+    pub fn run_schedule(schedule: &[usize], steps: &[Step], amount: u64, threads: usize) -> u64 {
+        let mut shared = 0;
+        let mut local = vec![0; threads];
+        let mut pc = vec![0; threads];
+
+        for &t in schedule {
+            match steps[pc[t]] {
+                Step::Read => local[t] = shared,
+                Step::Write => shared = local[t] + amount,
+                Step::ReadModifyWrite => shared += amount,
+            }
+            pc[t] += 1;
+        }
+        shared
+    }
+
+    pub fn describe(schedule: &[usize], steps: &[Step]) -> String {
+        let mut pc = vec![0; schedule.len()];
+        schedule
+            .iter()
+            .map(|&t| {
+                let step = steps[pc[t]];
+                pc[t] += 1;
+                format!("T{}.{:?}", t, step)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Which fix `--fixed-variant` selects
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FixedVariant {
+    Mutex,
+    Atomic,
+    Cas,
+}
+
+impl FixedVariant {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "mutex" => Some(Self::Mutex),
+            "atomic" => Some(Self::Atomic),
+            "cas" => Some(Self::Cas),
+            _ => None,
+        }
+    }
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let use_fixed = args.iter().any(|arg| arg == "--fixed");
-    let use_atomic = args.iter().any(|arg| arg == "--atomic");
+    let variant = match args.iter().position(|arg| arg == "--fixed-variant").and_then(|i| args.get(i + 1)) {
+        Some(name) => match FixedVariant::parse(name) {
+            Some(variant) => Some(variant),
+            None => {
+                eprintln!("Unknown --fixed-variant '{}': expected 'mutex', 'atomic' or 'cas'", name);
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+    let use_fixed = args.iter().any(|arg| arg == "--fixed") || variant == Some(FixedVariant::Mutex);
+    let use_atomic = args.iter().any(|arg| arg == "--atomic") || variant == Some(FixedVariant::Atomic);
+    let use_cas = variant == Some(FixedVariant::Cas);
+    let use_explore = args.iter().any(|arg| arg == "--explore");
+    let use_compare = args.iter().any(|arg| arg == "--compare");
+    let use_barrier = args.iter().any(|arg| arg == "--barrier");
+    let inject_regression = args.iter().any(|arg| arg == "--inject-regression");
+    let use_verify = args.iter().any(|arg| arg == "--verify");
+    let use_overhead = args.iter().any(|arg| arg == "--overhead");
+    let use_interleave = args.iter().any(|arg| arg == "--interleave");
+    let threads = args
+        .iter()
+        .position(|arg| arg == "--threads")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2);
 
     println!("=== Sui Issue #303: Non-Atomic Read-Modify-Write (Lost Update) ===\n");
 
-    if use_atomic {
+    if use_interleave {
+        println!("Forcing both reads before either write and checking the event log...\n");
+        run_interleave();
+    } else if use_overhead {
+        println!("Timing BUGGY and FIXED versions on an uncontended single thread...\n");
+        run_overhead();
+    } else if use_verify {
+        println!("Checking that the fixed version's invariant catches an injected regression...\n");
+        run_verify();
+    } else if use_compare {
+        println!("Running BUGGY and FIXED versions on the same workload...\n");
+        run_compare();
+    } else if use_explore {
+        println!("Exploring all interleavings of {} threads ({} version)...\n",
+                 threads, if use_fixed { "FIXED" } else { "BUGGY" });
+        run_explore(use_fixed, threads);
+    } else if use_cas {
+        println!("Running FIXED-CAS version (compare_exchange retry loop)...\n");
+        run_fixed_cas_test();
+    } else if use_atomic {
         println!("Running FIXED-ATOMIC version (atomic operations)...\n");
         run_fixed_atomic_test();
+    } else if use_fixed && inject_regression {
+        println!("Running FIXED version with the regression injected (lock dropped before the write)...\n");
+        run_fixed_test(fixed::ClientAPI::with_injected_regression(), use_barrier);
     } else if use_fixed {
         println!("Running FIXED version (atomic with mutex)...\n");
-        run_fixed_test();
+        run_fixed_test(fixed::ClientAPI::new(), use_barrier);
     } else {
         println!("Running BUGGY version (non-atomic read-modify-write)...\n");
-        run_buggy_test();
+        run_buggy_test(use_barrier);
     }
 }
 
-fn run_buggy_test() {
+/// With `--barrier`, every worker waits here so they all enter
+/// add_pending_order together instead of in spawn order
+fn start_barrier(use_barrier: bool, workers: usize) -> Option<Arc<Barrier>> {
+    use_barrier.then(|| Arc::new(Barrier::new(workers)))
+}
+
+fn run_buggy_test(use_barrier: bool) {
     let api = Arc::new(buggy::ClientAPI::new());
     let mut handles = vec![];
 
@@ -211,10 +650,16 @@ fn run_buggy_test() {
     let num_threads = 10;
     let amount_per_thread = 100;
 
+    let start = start_barrier(use_barrier, num_threads as usize);
+
     for i in 0..num_threads {
         let api = Arc::clone(&api);
+        let start = start.clone();
         let handle = thread::spawn(move || {
             println!("[BUGGY] Thread {} adding order...", i);
+            if let Some(start) = start {
+                start.wait();
+            }
             api.add_pending_order(account, amount_per_thread);
         });
         handles.push(handle);
@@ -234,15 +679,28 @@ fn run_buggy_test() {
     println!("Actual total: {}", actual);
 
     api.check_lost_updates(account, expected);
-    let lost = api.get_lost_updates();
+    let outcome = Outcome::from_counts(actual, expected);
+    debug_assert_eq!(api.get_lost_updates(), outcome.lost());
+    outcome.print_metrics();
 
-    if lost > 0 {
-        println!("\n[BUG DEMONSTRATED]");
-        println!("Lost {} units due to non-atomic read-modify-write!", lost);
+    if use_barrier {
+        // Every thread reads before any writes, so at most one add survives
+        assert!(
+            matches!(outcome, Outcome::DataLoss { lost, total: 1000 } if lost > 0),
+            "contended buggy run should lose updates, got {:?}",
+            outcome
+        );
+    }
+
+    if let Outcome::DataLoss { lost, total } = outcome {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("Lost {} of {} units due to non-atomic read-modify-write!", lost, total);
         println!("This is a classic 'lost update' atomicity violation.");
         println!("In Sui, this could enable double-spending attacks.");
     } else {
-        println!("\n[NOTE]");
+        println!();
+        print_banner("[NOTE]");
         println!("No lost updates this run (timing-dependent race).");
         println!("Try running multiple times to see the bug.");
     }
@@ -251,18 +709,25 @@ fn run_buggy_test() {
     println!("Run with --atomic to see atomic operations version.");
 }
 
-fn run_fixed_test() {
-    let api = Arc::new(fixed::ClientAPI::new());
+/// Run the 10 x 100 workload against a fixed `ClientAPI`
+fn run_fixed_workload(api: fixed::ClientAPI, use_barrier: bool) -> RunSummary {
+    let api = Arc::new(api);
     let mut handles = vec![];
 
     let account = "alice";
     let num_threads = 10;
     let amount_per_thread = 100;
 
+    let start = start_barrier(use_barrier, num_threads as usize);
+
     for i in 0..num_threads {
         let api = Arc::clone(&api);
+        let start = start.clone();
         let handle = thread::spawn(move || {
             println!("[FIXED] Thread {} adding order...", i);
+            if let Some(start) = start {
+                start.wait();
+            }
             api.add_pending_order(account, amount_per_thread);
         });
         handles.push(handle);
@@ -272,21 +737,49 @@ fn run_fixed_test() {
         handle.join().unwrap();
     }
 
-    let expected = num_threads * amount_per_thread;
-    let actual = api.get_pending(account);
+    RunSummary {
+        expected: num_threads * amount_per_thread,
+        actual: api.get_pending(account),
+    }
+}
+
+fn run_fixed_test(api: fixed::ClientAPI, use_barrier: bool) {
+    let summary = run_fixed_workload(api, use_barrier);
 
     println!("\n=== Results ===");
-    println!("Expected total: {}", expected);
-    println!("Actual total: {}", actual);
+    println!("Expected total: {}", summary.expected);
+    println!("Actual total: {}", summary.actual);
+    summary.outcome().print_metrics();
 
-    if actual == expected {
-        println!("\n[FIXED]");
-        println!("All updates preserved! Atomic read-modify-write with Mutex.");
-        println!("The entire sequence is protected by a single lock.");
-    } else {
-        println!("\n[ERROR]");
-        println!("Unexpected result (should not happen with fix).");
-    }
+    assert_eq!(summary.lost(), 0, "fixed version lost updates");
+
+    println!();
+    print_banner("[FIXED]");
+    println!("All updates preserved! Atomic read-modify-write with Mutex.");
+    println!("The entire sequence is protected by a single lock.");
+}
+
+/// Self-test of the lost-update invariant: the fixed version must pass it,
+/// and the same version with the lock dropped before the write must not.
+/// Both runs start behind a barrier so the regression cannot slip through
+/// on spawn timing.
+fn run_verify() {
+    println!("--- FIXED ---");
+    let clean = run_fixed_workload(fixed::ClientAPI::new(), true);
+    println!("\n--- FIXED with --inject-regression ---");
+    let injected = run_fixed_workload(fixed::ClientAPI::with_injected_regression(), true);
+
+    println!("\n=== Metrics ===");
+    println!("fixed_lost_updates: {}", clean.lost());
+    println!("injected_lost_updates: {}", injected.lost());
+
+    assert_eq!(clean.lost(), 0, "fixed version lost updates without injection");
+    assert!(injected.lost() > 0, "injected regression lost no updates, so the invariant missed it");
+
+    println!("\n=== Results ===");
+    print_banner("[FIXED]");
+    println!("Without injection the fixed version lost nothing.");
+    println!("With the lock dropped before the write it lost {} units, and the check caught it.", injected.lost());
 }
 
 fn run_fixed_atomic_test() {
@@ -318,11 +811,347 @@ fn run_fixed_atomic_test() {
     println!("Actual total: {}", actual);
 
     if actual == expected {
-        println!("\n[FIXED-ATOMIC]");
+        println!();
+        print_banner("[FIXED-ATOMIC]");
         println!("All updates preserved! Using AtomicU64::fetch_add.");
         println!("Lock-free atomic operations ensure no updates are lost.");
     } else {
-        println!("\n[ERROR]");
+        println!();
+        print_banner("[ERROR]");
         println!("Unexpected result (should not happen with fix).");
     }
 }
+
+/// Adds per thread in the CAS run, enough to keep the threads colliding
+const CAS_ADDS_PER_THREAD: u64 = 50;
+
+fn run_fixed_cas_test() {
+    let api = Arc::new(fixed_cas::ClientAPI::new());
+
+    let account = "alice";
+    let num_threads = 10;
+    let amount_per_add = 100;
+
+    println!(
+        "{} threads x {} adds of {} to '{}'\n",
+        num_threads, CAS_ADDS_PER_THREAD, amount_per_add, account
+    );
+
+    let handles: Vec<_> = (0..num_threads)
+        .map(|i| {
+            let api = Arc::clone(&api);
+            thread::spawn(move || {
+                for _ in 0..CAS_ADDS_PER_THREAD {
+                    api.add_pending_order(account, amount_per_add);
+                }
+                println!("[FIXED-CAS] Thread {} finished {} adds", i, CAS_ADDS_PER_THREAD);
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let expected = num_threads * CAS_ADDS_PER_THREAD * amount_per_add;
+    let actual = api.get_pending(account);
+    let retries = api.cas_retries();
+
+    println!("\n=== Results ===");
+    println!("Expected total: {}", expected);
+    println!("Actual total: {}", actual);
+
+    println!("\n=== Metrics ===");
+    println!("operations: {}", num_threads * CAS_ADDS_PER_THREAD);
+    println!("cas_retries: {}", retries);
+
+    assert_eq!(actual, expected, "CAS loop lost updates");
+
+    println!();
+    print_banner("[FIXED-CAS]");
+    println!("All updates preserved! {} stale CAS attempts were retried.", retries);
+    println!("A failed compare_exchange returns the current value to recompute from.");
+}
+
+/// What a run did to the units it was asked to add. Data-loss bugs report
+/// through `DataLoss`, so their metrics read as units lost out of a total.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// `lost` of the `total` units added never reached the final balance
+    DataLoss { lost: u64, total: u64 },
+    /// All `total` units reached the final balance
+    Intact { total: u64 },
+}
+
+impl Outcome {
+    fn from_counts(kept: u64, total: u64) -> Self {
+        match total.saturating_sub(kept) {
+            0 => Outcome::Intact { total },
+            lost => Outcome::DataLoss { lost, total },
+        }
+    }
+
+    fn lost(&self) -> u64 {
+        match self {
+            Outcome::DataLoss { lost, .. } => *lost,
+            Outcome::Intact { .. } => 0,
+        }
+    }
+
+    fn print_metrics(&self) {
+        let (name, total) = match self {
+            Outcome::DataLoss { total, .. } => ("data_loss", total),
+            Outcome::Intact { total } => ("intact", total),
+        };
+        println!("\n=== Metrics ===");
+        println!("outcome: {}", name);
+        println!("lost_units: {}", self.lost());
+        println!("total_units: {}", total);
+    }
+}
+
+/// Final state of one version's run, diffed by `--compare`
+struct RunSummary {
+    expected: u64,
+    actual: u64,
+}
+
+impl RunSummary {
+    fn lost(&self) -> u64 {
+        self.outcome().lost()
+    }
+
+    fn outcome(&self) -> Outcome {
+        Outcome::from_counts(self.actual, self.expected)
+    }
+
+    fn metrics(&self) -> [(&'static str, u64); 3] {
+        [
+            ("expected_total", self.expected),
+            ("actual_total", self.actual),
+            ("lost_updates", self.lost()),
+        ]
+    }
+}
+
+/// Run the standard 10 x 100 workload against one version
+fn run_workload<A: Send + Sync + 'static>(
+    api: Arc<A>,
+    add: fn(&A, &str, u64),
+    get: fn(&A, &str) -> u64,
+) -> RunSummary {
+    let account = "alice";
+    let num_threads = 10;
+    let amount_per_thread = 100;
+
+    let handles: Vec<_> = (0..num_threads)
+        .map(|_| {
+            let api = Arc::clone(&api);
+            thread::spawn(move || add(&api, account, amount_per_thread))
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    RunSummary {
+        expected: num_threads * amount_per_thread,
+        actual: get(&api, account),
+    }
+}
+
+/// Side-by-side table of two summaries; differing rows are marked with `*`
+fn render_diff(buggy: &RunSummary, fixed: &RunSummary) -> String {
+    let mut out = format!("  {:<16} {:>8} {:>8}\n", "metric", "buggy", "fixed");
+    for ((name, b), (_, f)) in buggy.metrics().iter().zip(fixed.metrics().iter()) {
+        let marker = if b != f { "*" } else { " " };
+        out.push_str(&format!("{} {:<16} {:>8} {:>8}\n", marker, name, b, f));
+    }
+    out
+}
+
+fn run_compare() {
+    println!("--- BUGGY ---");
+    let buggy = run_workload(
+        Arc::new(buggy::ClientAPI::new()),
+        buggy::ClientAPI::add_pending_order,
+        buggy::ClientAPI::get_pending,
+    );
+    println!("\n--- FIXED ---");
+    let fixed = run_workload(
+        Arc::new(fixed::ClientAPI::new()),
+        fixed::ClientAPI::add_pending_order,
+        fixed::ClientAPI::get_pending,
+    );
+
+    println!("\n=== Compare ===");
+    print!("{}", render_diff(&buggy, &fixed));
+
+    assert_eq!(fixed.lost(), 0, "fixed version lost updates");
+
+    println!("\n=== Results ===");
+    if buggy.lost() > 0 {
+        print_banner("[BUG DEMONSTRATED]");
+        println!("Same workload: buggy lost {} units, fixed lost 0.", buggy.lost());
+        println!("Only the read-modify-write atomicity differs between the two.");
+    } else {
+        print_banner("[NOTE]");
+        println!("Buggy version lost nothing this run (timing-dependent race).");
+        println!("Fixed version lost 0 as expected.");
+    }
+}
+
+fn run_explore(use_fixed: bool, threads: usize) {
+    let amount = 100;
+    let steps = explore::thread_steps(use_fixed);
+    let schedules = explore::interleavings(threads, steps.len());
+    let expected = threads as u64 * amount;
+
+    let violations: Vec<_> = schedules
+        .iter()
+        .filter_map(|schedule| {
+            let total = explore::run_schedule(schedule, &steps, amount, threads);
+            (total != expected).then_some((schedule, total))
+        })
+        .collect();
+
+    for (schedule, total) in violations.iter().take(5) {
+        println!("  {} => total {} (expected {})",
+                 explore::describe(schedule, &steps), total, expected);
+    }
+    if violations.len() > 5 {
+        println!("  ... and {} more", violations.len() - 5);
+    }
+
+    println!("\n=== Results ===");
+    println!("{} of {} interleavings lose an update.", violations.len(), schedules.len());
+
+    if use_fixed {
+        if violations.is_empty() {
+            println!();
+            print_banner("[FIXED]");
+            println!("No interleaving of the locked read-modify-write loses an update.");
+        } else {
+            println!();
+            print_banner("[ERROR]");
+            println!("Unexpected lost update in fixed version.");
+        }
+    } else if !violations.is_empty() {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("A write based on a stale read overwrites another thread's update.");
+        println!("\nRun with --explore --fixed to check the mutex version.");
+    }
+}
+
+/// Adds per timed run in `--overhead`
+const OVERHEAD_OPS: u64 = 200_000;
+/// Timed runs per version; the fastest one is reported
+const OVERHEAD_RUNS: usize = 5;
+/// Largest fixed/buggy time ratio `--overhead` accepts
+const OVERHEAD_BOUND: f64 = 2.0;
+
+/// Fastest of OVERHEAD_RUNS single-threaded runs of OVERHEAD_OPS adds, in
+/// nanoseconds per add
+fn time_adds<A>(new: fn() -> A, add: fn(&A, &str, u64), get: fn(&A, &str) -> u64) -> f64 {
+    (0..OVERHEAD_RUNS)
+        .map(|_| {
+            let api = new();
+            let start = std::time::Instant::now();
+            for _ in 0..OVERHEAD_OPS {
+                add(&api, "alice", 1);
+            }
+            let elapsed = start.elapsed();
+            assert_eq!(get(&api, "alice"), OVERHEAD_OPS, "uncontended run lost updates");
+            elapsed.as_nanos() as f64 / OVERHEAD_OPS as f64
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Cost of the fix on the happy path: one thread, so nothing races, and no
+/// logging or simulated delay, so only the locking is timed
+fn run_overhead() {
+    println!("{} runs of {} adds per version, fastest run reported\n", OVERHEAD_RUNS, OVERHEAD_OPS);
+
+    let buggy = time_adds(buggy::ClientAPI::quiet, buggy::ClientAPI::add_pending_order, buggy::ClientAPI::get_pending);
+    println!("[BUGGY] read lock, then write lock: {:.1} ns/add", buggy);
+    let fixed = time_adds(fixed::ClientAPI::quiet, fixed::ClientAPI::add_pending_order, fixed::ClientAPI::get_pending);
+    println!("[FIXED] one mutex acquisition:     {:.1} ns/add", fixed);
+
+    let ratio = fixed / buggy;
+    println!("\n=== Metrics ===");
+    println!("buggy_ns_per_op: {:.1}", buggy);
+    println!("fixed_ns_per_op: {:.1}", fixed);
+    println!("buggy_ops_per_sec: {:.0}", 1e9 / buggy);
+    println!("fixed_ops_per_sec: {:.0}", 1e9 / fixed);
+    println!("overhead_pct: {:+.1}", (ratio - 1.0) * 100.0);
+
+    assert!(
+        ratio <= OVERHEAD_BOUND,
+        "fixed version is {:.2}x slower than buggy, above the {:.1}x bound",
+        ratio,
+        OVERHEAD_BOUND
+    );
+
+    println!("\n=== Results ===");
+    print_banner("[FIXED]");
+    println!("Holding one lock across the read-modify-write costs {:.2}x the buggy path uncontended,", ratio);
+    println!("within the {:.1}x bound.", OVERHEAD_BOUND);
+}
+
+/// Run `add` on threads `t0` and `t1`, each call logged as `<thread>.add`
+fn run_logged_pair(log: &Arc<EventLog>, add: impl Fn() + Send + Sync + 'static) {
+    let add = Arc::new(add);
+    let handles: Vec<_> = (0..2)
+        .map(|i| {
+            let (log, add) = (Arc::clone(log), Arc::clone(&add));
+            thread::Builder::new()
+                .name(format!("t{}", i))
+                .spawn(move || log.record(&format!("t{}.add", i), || add()))
+                .unwrap()
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+/// `--interleave`: two threads add 100 each. The buggy run is forced to
+/// read twice before writing; the event log then states the losing
+/// interleaving as happens-before facts.
+fn run_interleave() {
+    println!("--- BUGGY (ReadHook: both threads read before either writes) ---");
+    let buggy_log = Arc::new(EventLog::default());
+    let buggy = Arc::new(buggy::ClientAPI::with_hook(Arc::new(ReadHook::new(2)), Arc::clone(&buggy_log)));
+    let api = Arc::clone(&buggy);
+    run_logged_pair(&buggy_log, move || api.add_pending_order("alice", 100));
+    println!("\n[BUGGY] Event log:\n{}", buggy_log.render());
+
+    println!("--- FIXED ---");
+    let fixed_log = Arc::new(EventLog::default());
+    let fixed = Arc::new(fixed::ClientAPI::with_log(Arc::clone(&fixed_log)));
+    let api = Arc::clone(&fixed);
+    run_logged_pair(&fixed_log, move || api.add_pending_order("alice", 100));
+    println!("\n[FIXED] Event log:\n{}", fixed_log.render());
+
+    // Both reads precede both writes, so each write is computed from 0
+    buggy_log.assert_happens_before("t0.read", "t1.write");
+    buggy_log.assert_happens_before("t1.read", "t0.write");
+    buggy_log.assert_concurrent("t0.add", "t1.add");
+    assert_eq!(buggy.get_pending("alice"), 100, "forced interleaving should lose one add");
+
+    // The lock orders the two read-modify-writes one after the other
+    assert!(
+        fixed_log.happens_before("t0.rmw", "t1.rmw") || fixed_log.happens_before("t1.rmw", "t0.rmw"),
+        "fixed read-modify-writes overlapped:\n{}",
+        fixed_log.render()
+    );
+    assert_eq!(fixed.get_pending("alice"), 200);
+
+    println!("=== Results ===");
+    println!("[BUGGY] t0.read and t1.read both happen before t0.write and t1.write: total 100 of 200");
+    println!("[FIXED] t0.rmw and t1.rmw are ordered by the lock: total 200 of 200");
+    println!();
+    print_banner("[BUG DEMONSTRATED]");
+    println!("The adds were concurrent and neither read saw the other's write.");
+}
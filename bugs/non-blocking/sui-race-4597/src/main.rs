@@ -9,7 +9,9 @@
 
 use std::collections::HashMap;
 use std::env;
-use std::sync::{Arc, Mutex, RwLock};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Barrier, Mutex, RwLock};
 use std::thread;
 use std::time::Duration;
 
@@ -32,11 +34,23 @@ pub struct TransactionRequest {
     gas_object_id: ObjectId,
     gas_version: SequenceNumber, // Version expected by the request
     gas_required: u64,
+    /// Further objects the transaction reads and bumps, at the versions it
+    /// expects. Gas is only paid from `gas_object_id`.
+    other_objects: Vec<(ObjectId, SequenceNumber)>,
 }
 
-/// Object store that tracks the latest version of each object
+impl TransactionRequest {
+    /// Every object the transaction references, gas object first
+    fn references(&self) -> Vec<(&str, SequenceNumber)> {
+        std::iter::once((self.gas_object_id.as_str(), self.gas_version))
+            .chain(self.other_objects.iter().map(|(id, version)| (id.as_str(), *version)))
+            .collect()
+    }
+}
+
+/// Object store that keeps every version of each object, oldest first
 pub struct ObjectStore {
-    objects: RwLock<HashMap<ObjectId, GasObject>>,
+    objects: RwLock<HashMap<ObjectId, Vec<GasObject>>>,
 }
 
 impl ObjectStore {
@@ -46,28 +60,254 @@ impl ObjectStore {
         }
     }
 
+    /// Start `obj`'s history, replacing any earlier one
     fn insert(&self, obj: GasObject) {
-        self.objects.write().unwrap().insert(obj.id.clone(), obj);
+        self.objects.write().unwrap().insert(obj.id.clone(), vec![obj]);
     }
 
     fn get_latest(&self, id: &str) -> Option<GasObject> {
-        self.objects.read().unwrap().get(id).cloned()
+        self.objects.read().unwrap().get(id).and_then(|history| history.last()).cloned()
     }
 
+    /// `id` as it was at `version`, which need not be the latest
     fn get_at_version(&self, id: &str, version: SequenceNumber) -> Option<GasObject> {
-        let obj = self.objects.read().unwrap().get(id).cloned();
-        // Simulate version check
-        obj.filter(|o| o.version == version)
+        self.objects
+            .read()
+            .unwrap()
+            .get(id)?
+            .iter()
+            .find(|o| o.version == version)
+            .cloned()
+    }
+
+    /// Every stored version of `id`, oldest first
+    fn history(&self, id: &str) -> Vec<GasObject> {
+        self.objects.read().unwrap().get(id).cloned().unwrap_or_default()
+    }
+
+    /// Append the version after `history`'s latest with `new_balance`
+    fn push_version(history: &mut Vec<GasObject>, new_balance: u64) -> GasObject {
+        let mut next = history.last().unwrap().clone();
+        next.version += 1;
+        next.balance = new_balance;
+        history.push(next.clone());
+        next
+    }
+
+    /// Final store contents as JSON, sorted by object id
+    fn dump_state(&self) -> String {
+        let objects = self.objects.read().unwrap();
+        let mut ids: Vec<_> = objects.keys().collect();
+        ids.sort();
+        let entries: Vec<String> = ids
+            .into_iter()
+            .map(|id| {
+                let obj = objects[id].last().unwrap();
+                format!(
+                    "{{\"id\": \"{}\", \"version\": {}, \"balance\": {}}}",
+                    obj.id, obj.version, obj.balance
+                )
+            })
+            .collect();
+        format!("{{\"objects\": [{}]}}", entries.join(", "))
     }
 
     fn update(&self, id: &str, new_balance: u64) -> Option<GasObject> {
         let mut objects = self.objects.write().unwrap();
-        if let Some(obj) = objects.get_mut(id) {
-            obj.version += 1;
-            obj.balance = new_balance;
-            return Some(obj.clone());
+        objects.get_mut(id).map(|history| Self::push_version(history, new_balance))
+    }
+
+    /// Apply `(id, version, new_balance)` updates only if every object is
+    /// still at its `version`, so a write can never land on a version the
+    /// transaction did not validate. Nothing is written if any check fails.
+    fn update_all_at_versions(
+        &self,
+        updates: &[(&str, SequenceNumber, u64)],
+    ) -> Result<Vec<GasObject>, TxError> {
+        let mut objects = self.objects.write().unwrap();
+        for &(id, version, _) in updates {
+            let obj = objects.get(id).and_then(|history| history.last()).ok_or(TxError::ObjectNotFound)?;
+            if obj.version != version {
+                return Err(TxError::VersionMismatch {
+                    object: id.to_string(),
+                    requested: version,
+                    current: obj.version,
+                });
+            }
+        }
+        Ok(updates
+            .iter()
+            .map(|&(id, _, new_balance)| Self::push_version(objects.get_mut(id).unwrap(), new_balance))
+            .collect())
+    }
+
+    /// Deduct `(id, amount)` from each object's latest version, whatever
+    /// version that is, so deductions made since a snapshot are kept.
+    /// Nothing is written if any object is missing or short.
+    fn apply_deductions(&self, deductions: &[(&str, u64)]) -> Result<Vec<GasObject>, TxError> {
+        let mut objects = self.objects.write().unwrap();
+        for &(id, amount) in deductions {
+            let obj = objects.get(id).and_then(|history| history.last()).ok_or(TxError::ObjectNotFound)?;
+            if obj.balance < amount {
+                return Err(TxError::InsufficientGas { have: obj.balance, need: amount });
+            }
+        }
+        Ok(deductions
+            .iter()
+            .map(|&(id, amount)| {
+                let history = objects.get_mut(id).unwrap();
+                let balance = history.last().unwrap().balance - amount;
+                Self::push_version(history, balance)
+            })
+            .collect())
+    }
+}
+
+/// One recorded operation, spanning two ticks of the log's clock
+#[derive(Clone, Debug)]
+pub struct Event {
+    name: String,
+    begin: u64,
+    end: u64,
+}
+
+/// Ordered record of what each thread did, for asserting interleavings.
+/// `a` happens before `b` if `a` ended before `b` began; the two are
+/// concurrent if neither happens before the other.
+#[derive(Default)]
+pub struct EventLog {
+    clock: AtomicU64,
+    events: Mutex<Vec<Event>>,
+}
+
+impl EventLog {
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Run `op` and record it as `name`, spanning the whole call
+    pub fn record<T>(&self, name: &str, op: impl FnOnce() -> T) -> T {
+        let begin = self.tick();
+        let result = op();
+        let end = self.tick();
+        self.events.lock().unwrap().push(Event {
+            name: name.to_string(),
+            begin,
+            end,
+        });
+        result
+    }
+
+    fn find(&self, name: &str) -> Event {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|event| event.name == name)
+            .cloned()
+            .unwrap_or_else(|| panic!("no event {:?} in the log:\n{}", name, self.render()))
+    }
+
+    pub fn happens_before(&self, a: &str, b: &str) -> bool {
+        self.find(a).end < self.find(b).begin
+    }
+
+    pub fn assert_happens_before(&self, a: &str, b: &str) {
+        assert!(
+            self.happens_before(a, b),
+            "expected {} to happen before {}:\n{}",
+            a,
+            b,
+            self.render()
+        );
+    }
+
+    pub fn assert_concurrent(&self, a: &str, b: &str) {
+        assert!(
+            !self.happens_before(a, b) && !self.happens_before(b, a),
+            "expected {} and {} to be concurrent:\n{}",
+            a,
+            b,
+            self.render()
+        );
+    }
+
+    /// Events in the order they began, one `[begin, end] name` per line
+    pub fn render(&self) -> String {
+        let mut events = self.events.lock().unwrap().clone();
+        events.sort_by_key(|event| event.begin);
+        events
+            .iter()
+            .map(|event| format!("  [{:>2}, {:>2}] {}\n", event.begin, event.end, event.name))
+            .collect()
+    }
+}
+
+/// Name of the current thread, used to prefix logged events
+fn thread_label() -> String {
+    thread::current().name().unwrap_or("main").to_string()
+}
+
+/// Forces the interleaving for `--concurrent`: a transaction pauses right
+/// after reading its gas object until a competing updater has bumped it.
+/// Reads, writes and the update are recorded in `log`.
+pub struct RaceHook {
+    read_done: Barrier,
+    update_done: Barrier,
+    log: EventLog,
+}
+
+impl RaceHook {
+    fn new() -> Self {
+        Self {
+            read_done: Barrier::new(2),
+            update_done: Barrier::new(2),
+            log: EventLog::default(),
+        }
+    }
+
+    /// Called by the transaction between its read and its write
+    fn after_read(&self) {
+        self.read_done.wait();
+        self.update_done.wait();
+    }
+
+    /// Called by the updater: runs `update` inside the transaction's window
+    fn run_update(&self, update: impl FnOnce()) {
+        self.read_done.wait();
+        self.log.record(&format!("{}.update", thread_label()), update);
+        self.update_done.wait();
+    }
+
+    /// Run a transaction step, logged as `<thread>.<step>`
+    fn step<T>(&self, step: &str, op: impl FnOnce() -> T) -> T {
+        self.log.record(&format!("{}.{}", thread_label(), step), op)
+    }
+}
+
+/// Why a transaction failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxError {
+    ObjectNotFound,
+    VersionMismatch {
+        object: ObjectId,
+        requested: SequenceNumber,
+        current: SequenceNumber,
+    },
+    InsufficientGas { have: u64, need: u64 },
+}
+
+impl fmt::Display for TxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxError::ObjectNotFound => write!(f, "Gas object not found"),
+            TxError::VersionMismatch { object, requested, current } => {
+                write!(f, "Version mismatch on {}: requested {}, current {}", object, requested, current)
+            }
+            TxError::InsufficientGas { have, need } => {
+                write!(f, "Insufficient gas: {} < {}", have, need)
+            }
         }
-        None
     }
 }
 
@@ -77,8 +317,9 @@ pub struct TransactionResult {
     digest: TxDigest,
     success: bool,
     gas_used: u64,
+    gas_version_requested: SequenceNumber,
     gas_version_used: SequenceNumber,
-    error: Option<String>,
+    error: Option<TxError>,
 }
 
 /// Buggy version - uses latest gas version instead of request version
@@ -88,6 +329,7 @@ mod buggy {
     pub struct TransactionProcessor {
         store: Arc<ObjectStore>,
         results: Mutex<Vec<TransactionResult>>,
+        hook: Option<Arc<RaceHook>>,
     }
 
     impl TransactionProcessor {
@@ -95,6 +337,15 @@ mod buggy {
             Self {
                 store,
                 results: Mutex::new(Vec::new()),
+                hook: None,
+            }
+        }
+
+        pub fn with_hook(store: Arc<ObjectStore>, hook: Arc<RaceHook>) -> Self {
+            Self {
+                store,
+                results: Mutex::new(Vec::new()),
+                hook: Some(hook),
             }
         }
 
@@ -104,15 +355,16 @@ mod buggy {
                      request.digest, request.gas_version);
 
             // BUG: Get latest version instead of request version
-            let gas_obj = match self.store.get_latest(&request.gas_object_id) {
+            let gas_obj = match self.step("read", || self.store.get_latest(&request.gas_object_id)) {
                 Some(obj) => obj,
                 None => {
                     self.record_result(TransactionResult {
                         digest: request.digest.clone(),
                         success: false,
                         gas_used: 0,
+                        gas_version_requested: request.gas_version,
                         gas_version_used: 0,
-                        error: Some("Gas object not found".to_string()),
+                        error: Some(TxError::ObjectNotFound),
                     });
                     return;
                 }
@@ -129,6 +381,22 @@ mod buggy {
                 // In buggy version, we proceed anyway with wrong version
             }
 
+            // BUG: The other referenced objects are read at their latest
+            // version too, and a mismatch is only logged
+            let mut others = Vec::new();
+            for (id, version) in &request.other_objects {
+                let Some(obj) = self.store.get_latest(id) else { continue };
+                if obj.version != *version {
+                    println!("[BUGGY] VERSION MISMATCH! Tx {} expected {} version {}, got {}",
+                             request.digest, id, version, obj.version);
+                }
+                others.push(obj);
+            }
+
+            if let Some(hook) = &self.hook {
+                hook.after_read();
+            }
+
             // Simulate some processing time (widens race window)
             thread::sleep(Duration::from_millis(20));
 
@@ -138,21 +406,30 @@ mod buggy {
                     digest: request.digest.clone(),
                     success: false,
                     gas_used: 0,
+                    gas_version_requested: request.gas_version,
                     gas_version_used: gas_obj.version,
-                    error: Some(format!("Insufficient gas: {} < {}",
-                                       gas_obj.balance, request.gas_required)),
+                    error: Some(TxError::InsufficientGas {
+                        have: gas_obj.balance,
+                        need: request.gas_required,
+                    }),
                 });
                 return;
             }
 
             // Deduct gas (updates version)
             let new_balance = gas_obj.balance - request.gas_required;
-            let updated = self.store.update(&request.gas_object_id, new_balance);
+            let updated = self.step("write", || {
+                for obj in &others {
+                    self.store.update(&obj.id, obj.balance);
+                }
+                self.store.update(&request.gas_object_id, new_balance)
+            });
 
             self.record_result(TransactionResult {
                 digest: request.digest.clone(),
                 success: true,
                 gas_used: request.gas_required,
+                gas_version_requested: request.gas_version,
                 gas_version_used: gas_obj.version,
                 error: None,
             });
@@ -161,6 +438,14 @@ mod buggy {
                      request.digest, updated.map(|o| o.version).unwrap_or(0));
         }
 
+        /// Run `op`, logged through the race hook when there is one
+        fn step<T>(&self, step: &str, op: impl FnOnce() -> T) -> T {
+            match &self.hook {
+                Some(hook) => hook.step(step, op),
+                None => op(),
+            }
+        }
+
         fn record_result(&self, result: TransactionResult) {
             self.results.lock().unwrap().push(result);
         }
@@ -175,9 +460,24 @@ mod buggy {
 mod fixed {
     use super::*;
 
+    /// Which versions a request may name
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum VersionPolicy {
+        /// Every reference must be at its latest version, and the write only
+        /// applies if it still is
+        #[default]
+        Strict,
+        /// A reference may name any version still in the store's history.
+        /// The transaction reads that snapshot, and its gas is deducted from
+        /// the latest version, so later updates are not overwritten.
+        Snapshot,
+    }
+
     pub struct TransactionProcessor {
         store: Arc<ObjectStore>,
         results: Mutex<Vec<TransactionResult>>,
+        hook: Option<Arc<RaceHook>>,
+        policy: VersionPolicy,
     }
 
     impl TransactionProcessor {
@@ -185,6 +485,26 @@ mod fixed {
             Self {
                 store,
                 results: Mutex::new(Vec::new()),
+                hook: None,
+                policy: VersionPolicy::default(),
+            }
+        }
+
+        pub fn with_hook(store: Arc<ObjectStore>, hook: Arc<RaceHook>) -> Self {
+            Self {
+                store,
+                results: Mutex::new(Vec::new()),
+                hook: Some(hook),
+                policy: VersionPolicy::default(),
+            }
+        }
+
+        pub fn with_policy(store: Arc<ObjectStore>, policy: VersionPolicy) -> Self {
+            Self {
+                store,
+                results: Mutex::new(Vec::new()),
+                hook: None,
+                policy,
             }
         }
 
@@ -193,39 +513,33 @@ mod fixed {
             println!("[FIXED] Processing tx {} (requested gas version: {})",
                      request.digest, request.gas_version);
 
-            // FIX: Get gas object at the specific requested version
-            let gas_obj = match self.store.get_at_version(
-                &request.gas_object_id,
-                request.gas_version
-            ) {
-                Some(obj) => obj,
-                None => {
+            // FIX: Get every referenced object at its requested version
+            let objects = match self.step("read", || self.read_at_versions(request)) {
+                Ok(objects) => objects,
+                Err(error) => {
                     // FIX: Version mismatch is an error, not silently ignored
-                    let latest = self.store.get_latest(&request.gas_object_id);
-                    let error_msg = match latest {
-                        Some(obj) => format!(
-                            "Version mismatch: requested {}, current {}",
-                            request.gas_version, obj.version
-                        ),
-                        None => "Gas object not found".to_string(),
-                    };
-
-                    println!("[FIXED] Tx {} failed: {}", request.digest, error_msg);
+                    println!("[FIXED] Tx {} failed: {}", request.digest, error);
 
                     self.record_result(TransactionResult {
                         digest: request.digest.clone(),
                         success: false,
                         gas_used: 0,
+                        gas_version_requested: request.gas_version,
                         gas_version_used: request.gas_version,
-                        error: Some(error_msg),
+                        error: Some(error),
                     });
                     return;
                 }
             };
 
+            let gas_obj = objects[0].clone();
             println!("[FIXED] Tx {} got gas version {} (matches request)",
                      request.digest, gas_obj.version);
 
+            if let Some(hook) = &self.hook {
+                hook.after_read();
+            }
+
             thread::sleep(Duration::from_millis(20));
 
             if gas_obj.balance < request.gas_required {
@@ -233,26 +547,102 @@ mod fixed {
                     digest: request.digest.clone(),
                     success: false,
                     gas_used: 0,
+                    gas_version_requested: request.gas_version,
                     gas_version_used: gas_obj.version,
-                    error: Some(format!("Insufficient gas: {} < {}",
-                                       gas_obj.balance, request.gas_required)),
+                    error: Some(TxError::InsufficientGas {
+                        have: gas_obj.balance,
+                        need: request.gas_required,
+                    }),
                 });
                 return;
             }
 
+            // FIX: The write is conditional on the version we validated, so a
+            // concurrent update in between is reported instead of overwritten
             let new_balance = gas_obj.balance - request.gas_required;
-            let updated = self.store.update(&request.gas_object_id, new_balance);
+            let write = || match self.policy {
+                VersionPolicy::Strict => {
+                    let updates: Vec<_> = objects
+                        .iter()
+                        .map(|obj| {
+                            let balance = if obj.id == gas_obj.id { new_balance } else { obj.balance };
+                            (obj.id.as_str(), obj.version, balance)
+                        })
+                        .collect();
+                    self.store.update_all_at_versions(&updates)
+                }
+                VersionPolicy::Snapshot => {
+                    let deductions: Vec<_> = objects
+                        .iter()
+                        .map(|obj| {
+                            let amount = if obj.id == gas_obj.id { request.gas_required } else { 0 };
+                            (obj.id.as_str(), amount)
+                        })
+                        .collect();
+                    self.store.apply_deductions(&deductions)
+                }
+            };
+            let updated = match self.step("write", write) {
+                Ok(objects) => objects[0].clone(),
+                Err(error) => {
+                    println!("[FIXED] Tx {} failed at write: {}", request.digest, error);
+                    self.record_result(TransactionResult {
+                        digest: request.digest.clone(),
+                        success: false,
+                        gas_used: 0,
+                        gas_version_requested: request.gas_version,
+                        gas_version_used: gas_obj.version,
+                        error: Some(error),
+                    });
+                    return;
+                }
+            };
 
             self.record_result(TransactionResult {
                 digest: request.digest.clone(),
                 success: true,
                 gas_used: request.gas_required,
+                gas_version_requested: request.gas_version,
                 gas_version_used: gas_obj.version,
                 error: None,
             });
 
             println!("[FIXED] Tx {} completed, gas object now at version {}",
-                     request.digest, updated.map(|o| o.version).unwrap_or(0));
+                     request.digest, updated.version);
+        }
+
+        /// Read every referenced object at the version the request names.
+        /// Fails on the first reference that is missing, or stale under the
+        /// processor's policy.
+        fn read_at_versions(&self, request: &TransactionRequest) -> Result<Vec<GasObject>, TxError> {
+            request
+                .references()
+                .into_iter()
+                .map(|(id, version)| {
+                    let found = match self.policy {
+                        VersionPolicy::Strict => self.store.get_latest(id).filter(|o| o.version == version),
+                        VersionPolicy::Snapshot => self.store.get_at_version(id, version),
+                    };
+                    found.ok_or_else(|| {
+                        match self.store.get_latest(id) {
+                            Some(obj) => TxError::VersionMismatch {
+                                object: id.to_string(),
+                                requested: version,
+                                current: obj.version,
+                            },
+                            None => TxError::ObjectNotFound,
+                        }
+                    })
+                })
+                .collect()
+        }
+
+        /// Run `op`, logged through the race hook when there is one
+        fn step<T>(&self, step: &str, op: impl FnOnce() -> T) -> T {
+            match &self.hook {
+                Some(hook) => hook.step(step, op),
+                None => op(),
+            }
         }
 
         fn record_result(&self, result: TransactionResult) {
@@ -265,129 +655,243 @@ mod fixed {
     }
 }
 
+// The store and the race hook are shared by every transaction thread. Losing
+// `Send` or `Sync` on either should fail here, not at a distant spawn.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ObjectStore>();
+    assert_send_sync::<RaceHook>();
+};
+
+const DEFAULT_BALANCE: u64 = 1000;
+
+/// Default scenario: two transactions both created against gas version 1
+const DEFAULT_TXS: &[(SequenceNumber, u64)] = &[(1, 400), (1, 300)];
+
+/// Parse `--txs 1:400,2:300` into (requested version, gas required) pairs
+fn parse_txs(spec: &str) -> Option<Vec<(SequenceNumber, u64)>> {
+    spec.split(',')
+        .map(|pair| {
+            let (version, gas) = pair.split_once(':')?;
+            Some((version.trim().parse().ok()?, gas.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn gas_id(n: usize) -> ObjectId {
+    format!("gas_{:03}", n)
+}
+
+/// Store holding `objects` gas objects, each at version 1 with `balance`
+fn seeded_store(objects: usize, balance: u64) -> Arc<ObjectStore> {
+    let store = Arc::new(ObjectStore::new());
+    for n in 1..=objects {
+        store.insert(GasObject {
+            id: gas_id(n),
+            version: 1,
+            balance,
+        });
+    }
+    store
+}
+
+/// Transaction `i` pays from gas object `i % objects` and references the
+/// next `refs - 1` objects after it, all at its requested version
+fn build_requests(txs: &[(SequenceNumber, u64)], objects: usize, refs: usize) -> Vec<TransactionRequest> {
+    txs.iter()
+        .enumerate()
+        .map(|(i, &(gas_version, gas_required))| TransactionRequest {
+            digest: format!("tx_{:03}", i + 1),
+            gas_object_id: gas_id(i % objects + 1),
+            gas_version,
+            gas_required,
+            other_objects: (1..refs)
+                .map(|r| (gas_id((i + r) % objects + 1), gas_version))
+                .collect(),
+        })
+        .collect()
+}
+
+fn print_scenario(balance: u64, objects: usize, requests: &[TransactionRequest]) {
+    let ids: Vec<_> = (1..=objects).map(gas_id).collect();
+    println!("Scenario: {} transactions against {} (version 1, balance {})",
+             requests.len(), ids.join(", "), balance);
+    for request in requests {
+        if request.other_objects.is_empty() {
+            println!("  {} expects gas version {}, needs {} gas",
+                     request.digest, request.gas_version, request.gas_required);
+        } else {
+            let refs: Vec<_> = request.references().iter()
+                .map(|(id, version)| format!("{} v{}", id, version))
+                .collect();
+            println!("  {} expects {}, needs {} gas from {}",
+                     request.digest, refs.join(", "), request.gas_required, request.gas_object_id);
+        }
+    }
+    println!("Transactions execute one after another; each success bumps the version\n");
+}
+
+fn print_metrics(results: &[TransactionResult]) {
+    println!("\n=== Metrics ===");
+    for result in results {
+        println!("{}: success={} version_requested={} version_used={} gas_used={}",
+                 result.digest, result.success, result.gas_version_requested,
+                 result.gas_version_used, result.gas_used);
+    }
+}
+
+fn print_state(store: &ObjectStore) {
+    println!("\n=== State (JSON) ===");
+    println!("{}", store.dump_state());
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let dump_state = args.iter().any(|arg| arg == "--dump-state");
+    let balance = args
+        .iter()
+        .position(|arg| arg == "--balance")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_BALANCE);
+    let txs = args
+        .iter()
+        .position(|arg| arg == "--txs")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|v| parse_txs(v))
+        .unwrap_or_else(|| DEFAULT_TXS.to_vec());
+    let parse_count = |name: &str| -> usize {
+        match args.iter().position(|arg| arg == name).and_then(|i| args.get(i + 1)) {
+            Some(value) => match value.parse() {
+                Ok(count) if count > 0 => count,
+                _ => {
+                    eprintln!("Invalid {} '{}': expected a positive integer", name, value);
+                    std::process::exit(2);
+                }
+            },
+            None => 1,
+        }
+    };
+    let objects = parse_count("--objects");
+    let refs = parse_count("--refs");
+    if refs > objects {
+        eprintln!("--refs {} exceeds --objects {}: a transaction references distinct objects", refs, objects);
+        std::process::exit(2);
+    }
 
     println!("=== Sui Issue #4597: Gas Object Version Race ===\n");
 
-    if use_fixed {
+    if args.iter().any(|arg| arg == "--snapshot") {
+        println!("Running FIXED version under Strict and Snapshot policies...\n");
+        run_snapshot_test(balance);
+    } else if args.iter().any(|arg| arg == "--multi-object") {
+        println!("Running BUGGY and FIXED versions on a transaction with one stale object...\n");
+        run_multi_object_test(balance);
+    } else if args.iter().any(|arg| arg == "--concurrent") {
+        println!(
+            "Running {} version (updater races tx1 between read and write)...\n",
+            if use_fixed { "FIXED" } else { "BUGGY" }
+        );
+        run_concurrent_test(use_fixed, balance);
+    } else if use_fixed {
         println!("Running FIXED version (use request version, validate match)...\n");
-        run_fixed_test();
+        run_fixed_test(balance, &txs, objects, refs, dump_state);
     } else {
         println!("Running BUGGY version (use latest version, ignore mismatch)...\n");
-        run_buggy_test();
+        run_buggy_test(balance, &txs, objects, refs, dump_state);
     }
 }
 
-fn run_buggy_test() {
-    let store = Arc::new(ObjectStore::new());
-
-    // Create gas object with version 1
-    store.insert(GasObject {
-        id: "gas_001".to_string(),
-        version: 1,
-        balance: 1000,
-    });
+fn run_buggy_test(balance: u64, txs: &[(SequenceNumber, u64)], objects: usize, refs: usize, dump_state: bool) {
+    // Create the gas objects at version 1
+    let store = seeded_store(objects, balance);
 
     let processor = Arc::new(buggy::TransactionProcessor::new(Arc::clone(&store)));
 
-    println!("Scenario: Two transactions created when gas was at version 1");
-    println!("tx1 executes first, updates gas to version 2");
-    println!("tx2 executes after but still expects version 1\n");
-
-    // Transaction 1: created when gas was at version 1
-    let tx1 = TransactionRequest {
-        digest: "tx_001".to_string(),
-        gas_object_id: "gas_001".to_string(),
-        gas_version: 1, // Expects version 1
-        gas_required: 400,
-    };
-
-    // Transaction 2: also created when gas was at version 1
-    // But by the time it executes, gas is at version 2
-    let tx2 = TransactionRequest {
-        digest: "tx_002".to_string(),
-        gas_object_id: "gas_001".to_string(),
-        gas_version: 1, // Expects version 1, but will see version 2
-        gas_required: 300,
-    };
+    // All requests are created up front, but by the time a later one executes
+    // the gas object may already have moved past its requested version
+    let requests = build_requests(txs, objects, refs);
+    print_scenario(balance, objects, &requests);
 
-    // Execute tx1 first, completely
-    processor.execute(&tx1);
-
-    // Now tx2 executes - gas is now at version 2
-    // Bug: tx2 expects version 1 but gets version 2 and proceeds anyway
-    processor.execute(&tx2);
+    // Bug: a stale request gets the latest version and proceeds anyway
+    for request in &requests {
+        processor.execute(request);
+    }
 
     // Check results
     let results = processor.get_results();
 
     println!("\n=== Results ===");
-
-    let mut version_mismatches = false;
     for result in &results {
         println!("{:?}", result);
-        if let Some(ref _err) = result.error {
-            if result.gas_version_used != 1 {
-                version_mismatches = true;
-            }
-        }
     }
 
-    // Check for the bug
-    let successful: Vec<_> = results.iter().filter(|r| r.success).collect();
+    // Check for the bug: a successful tx that ran against a version it never asked for
+    let wrong_version: Vec<_> = results.iter()
+        .filter(|r| r.success && r.gas_version_used != r.gas_version_requested)
+        .collect();
 
-    if successful.len() == 2 || version_mismatches {
-        println!("\n[BUG DEMONSTRATED]");
+    print_metrics(&results);
+
+    if !wrong_version.is_empty() {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("{} transaction(s) silently executed against the wrong gas version!",
+                 wrong_version.len());
         println!("Problems observed:");
         println!("  - Transactions used latest gas version, not request version");
         println!("  - Version mismatch was silently ignored");
         println!("  - Lack of idempotency: same request can give different results");
-        println!("  - Second tx may see updated version from first tx");
+        println!("  - Later txs may see updated version from earlier txs");
         println!("\nRun with --fixed to see version validation.");
-    } else if successful.len() == 1 {
-        println!("\nOne transaction succeeded (timing).");
-        println!("Bug: The other tx may have used wrong version silently.");
+    } else {
+        println!("\nEvery transaction happened to see its requested version.");
+        println!("Bug: a stale request would have used the wrong version silently.");
         println!("Run with --fixed to see proper version validation.");
     }
-}
 
-fn run_fixed_test() {
-    let store = Arc::new(ObjectStore::new());
+    if dump_state {
+        print_state(&store);
+    }
+}
 
-    store.insert(GasObject {
-        id: "gas_001".to_string(),
-        version: 1,
-        balance: 1000,
-    });
+fn run_fixed_test(balance: u64, txs: &[(SequenceNumber, u64)], objects: usize, refs: usize, dump_state: bool) {
+    let store = seeded_store(objects, balance);
 
     let processor = Arc::new(fixed::TransactionProcessor::new(Arc::clone(&store)));
 
-    println!("Scenario: Two transactions created when gas was at version 1");
-    println!("tx1 executes first, updates gas to version 2");
-    println!("tx2 executes after but still expects version 1\n");
-
-    let tx1 = TransactionRequest {
-        digest: "tx_001".to_string(),
-        gas_object_id: "gas_001".to_string(),
-        gas_version: 1,
-        gas_required: 400,
-    };
+    let requests = build_requests(txs, objects, refs);
+    print_scenario(balance, objects, &requests);
 
-    let tx2 = TransactionRequest {
-        digest: "tx_002".to_string(),
-        gas_object_id: "gas_001".to_string(),
-        gas_version: 1, // Expects version 1, but gas is now at version 2
-        gas_required: 300,
-    };
-
-    // Execute tx1 first, completely
-    processor.execute(&tx1);
-
-    // Now tx2 executes - gas is now at version 2
-    // Fix: tx2 fails because version 1 != current version 2
-    processor.execute(&tx2);
+    // Fix: a stale request fails because its version != current version
+    for request in &requests {
+        processor.execute(request);
+    }
 
     let results = processor.get_results();
 
@@ -398,19 +902,245 @@ fn run_fixed_test() {
 
     let successful: Vec<_> = results.iter().filter(|r| r.success).collect();
     let failed_version: Vec<_> = results.iter()
-        .filter(|r| !r.success && r.error.as_ref().map(|e| e.contains("Version mismatch")).unwrap_or(false))
+        .filter(|r| matches!(r.error, Some(TxError::VersionMismatch { .. })))
         .collect();
+    let wrong_version = results.iter()
+        .any(|r| r.success && r.gas_version_used != r.gas_version_requested);
+
+    print_metrics(&results);
 
-    if successful.len() == 1 && failed_version.len() == 1 {
-        println!("\n[FIXED]");
-        println!("One transaction succeeded, one failed with version mismatch!");
+    if !wrong_version {
+        println!();
+        print_banner("[FIXED]");
+        println!("{} transaction(s) succeeded, {} failed with version mismatch!",
+                 successful.len(), failed_version.len());
         println!("\nFix: Use request version, not latest version");
         println!("  - Validates gas version matches request");
         println!("  - Returns clear error on version mismatch");
         println!("  - Ensures idempotency: same request = same result");
-        println!("  - Second tx must be retried with updated version");
+        println!("  - Stale txs must be retried with updated version");
     } else {
         println!("\nResults: {} succeeded, {} version errors",
                  successful.len(), failed_version.len());
     }
+
+    if dump_state {
+        print_state(&store);
+    }
+}
+
+/// Amount the competing updater deducts in `--concurrent`
+const UPDATER_DEDUCTION: u64 = 100;
+
+fn run_concurrent_test(use_fixed: bool, balance: u64) {
+    let store = Arc::new(ObjectStore::new());
+    store.insert(GasObject {
+        id: "gas_001".to_string(),
+        version: 1,
+        balance,
+    });
+
+    let request = TransactionRequest {
+        digest: "tx1".to_string(),
+        gas_object_id: "gas_001".to_string(),
+        gas_version: 1,
+        gas_required: 400,
+        other_objects: Vec::new(),
+    };
+    println!("Gas object: gas_001 v1, balance {}", balance);
+    println!("tx1 requests v1 and needs 400; an updater deducts {} concurrently\n", UPDATER_DEDUCTION);
+
+    let hook = Arc::new(RaceHook::new());
+
+    // Updater thread: bumps the gas object while tx1 sits between read and write
+    let updater_store = Arc::clone(&store);
+    let updater_hook = Arc::clone(&hook);
+    let updater = thread::Builder::new().name("updater".to_string()).spawn(move || {
+        updater_hook.run_update(|| {
+            let current = updater_store.get_latest("gas_001").unwrap();
+            let updated = updater_store
+                .update("gas_001", current.balance - UPDATER_DEDUCTION)
+                .unwrap();
+            println!(
+                "[UPDATER] Deducted {} from gas_001: v{} -> v{}",
+                UPDATER_DEDUCTION, current.version, updated.version
+            );
+        });
+    }).unwrap();
+
+    let tx_thread = thread::Builder::new().name("tx1".to_string());
+    let tx_hook = Arc::clone(&hook);
+    let results = if use_fixed {
+        let processor = fixed::TransactionProcessor::with_hook(Arc::clone(&store), Arc::clone(&hook));
+        let tx = tx_thread.spawn(move || {
+            tx_hook.step("execute", || processor.execute(&request));
+            processor.get_results()
+        });
+        tx.unwrap().join().unwrap()
+    } else {
+        let processor = buggy::TransactionProcessor::with_hook(Arc::clone(&store), Arc::clone(&hook));
+        let tx = tx_thread.spawn(move || {
+            tx_hook.step("execute", || processor.execute(&request));
+            processor.get_results()
+        });
+        tx.unwrap().join().unwrap()
+    };
+    updater.join().unwrap();
+
+    println!("\nEvent log:\n{}", hook.log.render());
+    // The hook's schedule, stated as happens-before: tx1 read, then the
+    // update, then tx1's write, all inside one execute call
+    hook.log.assert_happens_before("tx1.read", "updater.update");
+    hook.log.assert_happens_before("updater.update", "tx1.write");
+    hook.log.assert_concurrent("tx1.execute", "updater.update");
+
+    let result = &results[0];
+    let final_obj = store.get_latest("gas_001").unwrap();
+    // Version the write was applied on top of, if tx1 wrote at all
+    let written_on = if result.success { final_obj.version - 1 } else { 0 };
+
+    println!("\n=== Results ===");
+    println!("{:?}", result);
+    println!("Final gas object: v{}, balance {}", final_obj.version, final_obj.balance);
+
+    if use_fixed {
+        assert!(!result.success);
+        assert_eq!(
+            result.error,
+            Some(TxError::VersionMismatch { object: "gas_001".to_string(), requested: 1, current: 2 })
+        );
+        assert_eq!(final_obj.balance, balance - UPDATER_DEDUCTION);
+        println!();
+        print_banner("[FIXED]");
+        println!("tx1 validated v1, saw the object move to v2 at write time,");
+        println!("and failed cleanly with VersionMismatch. The updater's deduction is intact.");
+    } else {
+        assert!(result.success);
+        assert_ne!(written_on, result.gas_version_used);
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!(
+            "tx1 validated v{} but its write landed on v{}.",
+            result.gas_version_used, written_on
+        );
+        println!(
+            "Balance should be {}, but the updater's deduction was overwritten: {}.",
+            balance - UPDATER_DEDUCTION - 400,
+            final_obj.balance
+        );
+        println!("\nRun with --concurrent --fixed to see the conditional write.");
+    }
+}
+
+/// `--multi-object`: tx1 pays from gas_001 (fresh at v1) and also references
+/// gas_002 at v1, but gas_002 has already moved on to v2
+fn run_multi_object_test(balance: u64) {
+    let request = TransactionRequest {
+        digest: "tx1".to_string(),
+        gas_object_id: gas_id(1),
+        gas_version: 1,
+        gas_required: 400,
+        other_objects: vec![(gas_id(2), 1)],
+    };
+    let setup = || {
+        let store = seeded_store(2, balance);
+        store.update(&gas_id(2), balance).unwrap();
+        store
+    };
+    println!("gas_001 is at v1, gas_002 has moved on to v2");
+    println!("tx1 references gas_001 v1 and gas_002 v1, and pays 400 from gas_001\n");
+
+    println!("--- BUGGY ---");
+    let buggy_store = setup();
+    let buggy = buggy::TransactionProcessor::new(Arc::clone(&buggy_store));
+    buggy.execute(&request);
+    let buggy_result = buggy.get_results().remove(0);
+
+    println!("\n--- FIXED ---");
+    let fixed_store = setup();
+    let fixed = fixed::TransactionProcessor::new(Arc::clone(&fixed_store));
+    fixed.execute(&request);
+    let fixed_result = fixed.get_results().remove(0);
+
+    println!("\n=== Results ===");
+    println!("[BUGGY] {:?}", buggy_result);
+    println!("[FIXED] {:?}", fixed_result);
+
+    print_state(&buggy_store);
+    print_state(&fixed_store);
+
+    assert!(buggy_result.success, "buggy processor should ignore the stale reference");
+    assert!(!fixed_result.success);
+    assert_eq!(
+        fixed_result.error,
+        Some(TxError::VersionMismatch { object: gas_id(2), requested: 1, current: 2 })
+    );
+    // Rejection happens before any write, so neither object moved
+    assert_eq!(fixed_store.get_latest(&gas_id(1)).unwrap().version, 1);
+    assert_eq!(fixed_store.get_latest(&gas_id(2)).unwrap().version, 2);
+
+    println!();
+    print_banner("[BUG DEMONSTRATED]");
+    println!("The buggy processor logged the stale gas_002 and charged tx1 anyway.");
+    print_banner("[FIXED]");
+    println!("Every reference is validated; tx1 was rejected naming gas_002 and nothing was written.");
+}
+
+/// `--snapshot`: tx_001 and tx_002 both request gas_001 at v1. tx_001 runs
+/// first and bumps it to v2; tx_002 then asks for v1, which is still in the
+/// store's history
+fn run_snapshot_test(balance: u64) {
+    let requests = build_requests(&[(1, 400), (1, 300)], 1, 1);
+    print_scenario(balance, 1, &requests);
+
+    let run = |policy: fixed::VersionPolicy| {
+        println!("--- {:?} ---", policy);
+        let store = seeded_store(1, balance);
+        let processor = fixed::TransactionProcessor::with_policy(Arc::clone(&store), policy);
+        for request in &requests {
+            processor.execute(request);
+        }
+        println!();
+        (store, processor.get_results())
+    };
+    let (strict_store, strict) = run(fixed::VersionPolicy::Strict);
+    let (snapshot_store, snapshot) = run(fixed::VersionPolicy::Snapshot);
+
+    println!("=== Results ===");
+    for result in &strict {
+        println!("[Strict] {:?}", result);
+    }
+    for result in &snapshot {
+        println!("[Snapshot] {:?}", result);
+    }
+    let history: Vec<_> = snapshot_store
+        .history(&gas_id(1))
+        .iter()
+        .map(|obj| format!("v{} balance {}", obj.version, obj.balance))
+        .collect();
+    println!("Snapshot history of gas_001: {}", history.join(", "));
+
+    print_metrics(&snapshot);
+    print_state(&strict_store);
+    print_state(&snapshot_store);
+
+    // Strict: tx_002's v1 is no longer the latest, so it is rejected
+    assert!(strict[0].success);
+    assert_eq!(
+        strict[1].error,
+        Some(TxError::VersionMismatch { object: gas_id(1), requested: 1, current: 2 })
+    );
+    // Snapshot: tx_002 reads v1 from history and succeeds, on top of tx_001
+    assert!(snapshot.iter().all(|r| r.success), "both transactions should succeed");
+    assert_eq!(snapshot[1].gas_version_used, 1);
+    assert_eq!(snapshot_store.get_at_version(&gas_id(1), 1).unwrap().balance, balance);
+    let latest = snapshot_store.get_latest(&gas_id(1)).unwrap();
+    assert_eq!(latest.version, 3);
+    assert_eq!(latest.balance, balance - 400 - 300);
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Strict rejected tx_002 because v1 was no longer the latest.");
+    println!("Snapshot found v1 in gas_001's history, so tx_002 succeeded;");
+    println!("its gas came off v2, keeping tx_001's deduction.");
 }
@@ -9,6 +9,7 @@
 
 use std::collections::{HashMap, HashSet};
 use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -23,12 +24,111 @@ pub struct Task {
     outputs: Vec<ObjectId>, // Objects this task produces
 }
 
+/// Simulated execution time of one task unless `--work-ms` is given
+const EXECUTION_TIME: Duration = Duration::from_millis(50);
+
 #[derive(Debug, Clone)]
 pub enum TaskResult {
     Success,
     Failed(String),
 }
 
+/// Wait-for graph snapshot, exported as Graphviz DOT with `--format dot`
+///
+/// Each crate builds on its own, so this is copied into ballista-deadlock-132,
+/// reth-deadlock-10842, sui-blocking-5201, sui-deadlock-335, sui-deadlock-960
+/// and sui-race-4990. Keep the copies identical; reth-deadlock-10842 also
+/// has `remove_edge`.
+#[derive(Debug, Default)]
+pub struct WaitForGraph {
+    edges: Vec<(String, String, String)>,
+}
+
+impl WaitForGraph {
+    fn add_edge(&mut self, from: &str, to: &str, label: &str) {
+        self.edges.push((from.to_string(), to.to_string(), label.to_string()));
+    }
+
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph {} {{\n", name);
+        for (from, to, label) in &self.edges {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", from, to, label));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Task dependency graph: an edge from each task to the task (or genesis)
+/// producing each of its inputs; edges into a failed task's missing input are
+/// flagged
+fn dependency_graph(tasks: &[Task], results: &HashMap<String, TaskResult>) -> WaitForGraph {
+    let mut graph = WaitForGraph::default();
+    for task in tasks {
+        let failed = matches!(results.get(&task.id), Some(TaskResult::Failed(_)));
+        for input in &task.inputs {
+            let producer = tasks
+                .iter()
+                .find(|t| t.outputs.contains(input))
+                .map(|t| t.id.as_str())
+                .unwrap_or("genesis");
+            let label = if failed {
+                format!("needs {} (FAILED)", input.0)
+            } else {
+                format!("needs {}", input.0)
+            };
+            graph.add_edge(&task.id, producer, &label);
+        }
+    }
+    graph
+}
+
+/// Small deterministic PRNG (SplitMix64) so generated graphs replay from a seed
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Random task DAG: task `i` produces `obj_{i+1}` and consumes one or two
+/// objects chosen from genesis (`obj_0`) and the outputs of earlier tasks.
+/// Submission order is shuffled so dependents often start before producers.
+fn generate_tasks(seed: u64, size: usize) -> Vec<Task> {
+    let mut rng = SeededRng(seed);
+    let mut tasks: Vec<Task> = (0..size)
+        .map(|i| {
+            let mut inputs = vec![ObjectId(format!("obj_{}", rng.below(i + 1)))];
+            if i > 0 && rng.below(2) == 0 {
+                let extra = ObjectId(format!("obj_{}", rng.below(i + 1)));
+                if !inputs.contains(&extra) {
+                    inputs.push(extra);
+                }
+            }
+            Task {
+                id: format!("T{}", i),
+                inputs,
+                outputs: vec![ObjectId(format!("obj_{}", i + 1))],
+            }
+        })
+        .collect();
+
+    for i in (1..tasks.len()).rev() {
+        let j = rng.below(i + 1);
+        tasks.swap(i, j);
+    }
+    tasks
+}
+
 /// Simulates blockchain state with object versions
 pub struct State {
     available_objects: Mutex<HashSet<ObjectId>>,
@@ -55,6 +155,66 @@ impl State {
             objects.insert(obj);
         }
     }
+
+    /// Replay `tasks` from genesis against the final object set and report
+    /// every way it fails to be a derivation of them:
+    /// - an object with no chain of tasks back to genesis behind it
+    /// - a task whose outputs never appeared
+    fn derivation_errors(&self, tasks: &[Task]) -> Vec<String> {
+        let objects = self.available_objects.lock().unwrap();
+        let mut derived: HashSet<ObjectId> = HashSet::from([ObjectId("obj_0".to_string())]);
+        let mut replayed: HashSet<&str> = HashSet::new();
+
+        // A task replays once its inputs are derived and its outputs exist
+        loop {
+            let next = tasks.iter().find(|task| {
+                !replayed.contains(task.id.as_str())
+                    && task.inputs.iter().all(|input| derived.contains(input))
+                    && task.outputs.iter().all(|output| objects.contains(output))
+            });
+            let Some(task) = next else { break };
+            replayed.insert(&task.id);
+            derived.extend(task.outputs.iter().cloned());
+        }
+
+        let mut errors = Vec::new();
+        let mut underived: Vec<_> = objects.iter().filter(|obj| !derived.contains(*obj)).collect();
+        underived.sort_by(|a, b| a.0.cmp(&b.0));
+        for obj in underived {
+            errors.push(format!("{} exists but has no derivation from genesis", obj.0));
+        }
+        for task in tasks.iter().filter(|task| !replayed.contains(task.id.as_str())) {
+            let missing: Vec<_> = task
+                .inputs
+                .iter()
+                .filter(|input| !derived.contains(*input))
+                .map(|input| input.0.as_str())
+                .collect();
+            if missing.is_empty() {
+                let outputs: Vec<_> = task.outputs.iter().map(|output| output.0.as_str()).collect();
+                errors.push(format!("{} had its inputs but its outputs {:?} are missing", task.id, outputs));
+            } else {
+                errors.push(format!("{} cannot be derived, inputs {:?} are missing", task.id, missing));
+            }
+        }
+        errors
+    }
+
+    /// True if the final objects are exactly what executing `tasks` in
+    /// dependency order from genesis would produce
+    fn verify_derivation(&self, tasks: &[Task]) -> bool {
+        self.derivation_errors(tasks).is_empty()
+    }
+}
+
+/// Print the derivation check after a run and return whether it passed
+fn report_derivation(state: &State, tasks: &[Task]) -> bool {
+    let errors = state.derivation_errors(tasks);
+    println!("\nDerivation from genesis valid: {}", errors.is_empty());
+    for error in &errors {
+        println!("  - {}", error);
+    }
+    errors.is_empty()
 }
 
 /// Buggy executor: executes tasks in parallel without dependency checking
@@ -124,17 +284,52 @@ mod fixed {
         state: Arc<State>,
         results: Mutex<HashMap<String, TaskResult>>,
         pending: Mutex<Vec<Task>>,
+        work: Duration,
+        /// Tasks executing right now, and the most seen at once
+        executing: AtomicUsize,
+        max_executing: AtomicUsize,
+        /// Logical clock ordering execution starts and ends
+        clock: AtomicUsize,
+        /// (task id, start tick, end tick) for every executed task
+        spans: Mutex<Vec<(String, usize, usize)>>,
     }
 
     impl ParallelExecutor {
         pub fn new(state: Arc<State>) -> Self {
+            Self::with_work(state, EXECUTION_TIME)
+        }
+
+        pub fn with_work(state: Arc<State>, work: Duration) -> Self {
             Self {
                 state,
                 results: Mutex::new(HashMap::new()),
                 pending: Mutex::new(Vec::new()),
+                work,
+                executing: AtomicUsize::new(0),
+                max_executing: AtomicUsize::new(0),
+                clock: AtomicUsize::new(0),
+                spans: Mutex::new(Vec::new()),
             }
         }
 
+        /// Execute `task` whose inputs are ready, tracking how many tasks
+        /// overlap and when this one started and ended
+        fn run(&self, task: &Task) {
+            let start = self.clock.fetch_add(1, Ordering::SeqCst);
+            let executing = self.executing.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_executing.fetch_max(executing, Ordering::SeqCst);
+
+            // Simulate execution time
+            thread::sleep(self.work);
+
+            // Add outputs to state
+            self.state.add_objects(task.outputs.clone());
+
+            self.executing.fetch_sub(1, Ordering::SeqCst);
+            let end = self.clock.fetch_add(1, Ordering::SeqCst);
+            self.spans.lock().unwrap().push((task.id.clone(), start, end));
+        }
+
         /// FIX: Only execute when all dependencies are ready
         pub fn execute_task(&self, task: Task) -> TaskResult {
             // Check if dependencies are ready
@@ -151,12 +346,7 @@ mod fixed {
             }
 
             println!("[FIXED] Executing task {}", task.id);
-
-            // Simulate execution time
-            thread::sleep(Duration::from_millis(50));
-
-            // Add outputs to state
-            self.state.add_objects(task.outputs.clone());
+            self.run(&task);
 
             println!("[FIXED] Task {} completed successfully", task.id);
             let result = TaskResult::Success;
@@ -184,8 +374,7 @@ mod fixed {
 
                 if let Some(task) = ready_task {
                     println!("[FIXED] Executing previously pending task {}", task.id);
-                    thread::sleep(Duration::from_millis(50));
-                    self.state.add_objects(task.outputs.clone());
+                    self.run(&task);
                     println!("[FIXED] Task {} completed successfully", task.id);
                     self.results
                         .lock()
@@ -200,25 +389,129 @@ mod fixed {
         pub fn get_results(&self) -> HashMap<String, TaskResult> {
             self.results.lock().unwrap().clone()
         }
+
+        /// Most tasks that were executing at the same time
+        pub fn max_concurrency(&self) -> usize {
+            self.max_executing.load(Ordering::SeqCst)
+        }
+
+        /// Dependency edges `(producer, consumer)` where the consumer started
+        /// executing before its producer had finished
+        pub fn dependency_violations(&self, tasks: &[Task]) -> Vec<(String, String)> {
+            let spans = self.spans.lock().unwrap();
+            let span = |id: &str| spans.iter().find(|(task_id, _, _)| task_id == id).map(|&(_, start, end)| (start, end));
+            let mut violations = Vec::new();
+            for consumer in tasks {
+                let Some((started, _)) = span(&consumer.id) else { continue };
+                for input in &consumer.inputs {
+                    let Some(producer) = tasks.iter().find(|task| task.outputs.contains(input)) else { continue };
+                    if span(&producer.id).is_none_or(|(_, ended)| ended > started) {
+                        violations.push((producer.id.clone(), consumer.id.clone()));
+                    }
+                }
+            }
+            violations
+        }
+    }
+}
+
+/// Number of tasks in a generated graph unless `--size` is given
+const DEFAULT_GRAPH_SIZE: usize = 6;
+/// Shape of the `--parallelism` workload unless `--independent` and
+/// `--chain-depth` are given
+const DEFAULT_INDEPENDENT: u64 = 4;
+const DEFAULT_CHAIN_DEPTH: u64 = 3;
+/// Attempts `--repeat-until-bug` makes unless `--max-attempts` is given
+const DEFAULT_MAX_ATTEMPTS: u64 = 20;
+
+/// How a `--repeat-until-bug` search ended
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// Attempt `attempt` (1-based) ran graph `seed` and left tasks incomplete
+    BugManifested { attempt: u64, seed: u64, incomplete: usize },
+    /// Every attempt completed all of its tasks
+    Exhausted { attempts: u64 },
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
     }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let format_dot = args
+        .iter()
+        .position(|arg| arg == "--format")
+        .and_then(|i| args.get(i + 1))
+        .is_some_and(|format| format == "dot");
+    let parse_flag = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.parse::<u64>().ok())
+    };
+    let seed = parse_flag("--seed");
+    let fuzz = parse_flag("--fuzz");
+    let size = parse_flag("--size").unwrap_or(DEFAULT_GRAPH_SIZE as u64) as usize;
 
     println!("=== Sui Issue #4990: Parallel Certificate Execution Race ===\n");
 
-    if use_fixed {
+    if args.iter().any(|arg| arg == "--parallelism") {
+        let independent = parse_flag("--independent").unwrap_or(DEFAULT_INDEPENDENT) as usize;
+        let depth = parse_flag("--chain-depth").unwrap_or(DEFAULT_CHAIN_DEPTH) as usize;
+        let work = parse_flag("--work-ms").map_or(EXECUTION_TIME, Duration::from_millis);
+        println!("Running FIXED version on {} independent tasks and a chain of {} ({:?} per task)...\n",
+                 independent, depth, work);
+        run_parallelism_test(independent, depth, work);
+    } else if args.iter().any(|arg| arg == "--repeat-until-bug") {
+        let start_seed = seed.unwrap_or(0);
+        let max_attempts = parse_flag("--max-attempts").unwrap_or(DEFAULT_MAX_ATTEMPTS);
+        if max_attempts == 0 {
+            eprintln!("--max-attempts must be at least 1");
+            std::process::exit(2);
+        }
+        println!("Repeating BUGGY version from seed {} until the bug shows ({} attempts max, {} tasks each)...\n",
+                 start_seed, max_attempts, size);
+        run_repeat_test(start_seed, max_attempts, size);
+    } else if let Some(seed) = seed {
+        println!("Running {} version on generated graph (seed {}, {} tasks)...\n",
+                 if use_fixed { "FIXED" } else { "BUGGY" }, seed, size);
+        run_seeded_test(use_fixed, seed, size);
+    } else if let Some(count) = fuzz {
+        println!("Fuzzing {} version over seeds 0..{} ({} tasks each)...\n",
+                 if use_fixed { "FIXED" } else { "BUGGY" }, count, size);
+        run_fuzz_test(use_fixed, count, size);
+    } else if use_fixed {
         println!("Running FIXED version (dependency-aware execution)...\n");
         run_fixed_test();
     } else {
         println!("Running BUGGY version (parallel execution without dependency tracking)...\n");
-        run_buggy_test();
+        run_buggy_test(format_dot);
     }
 }
 
-fn run_buggy_test() {
+fn run_buggy_test(format_dot: bool) {
     let state = Arc::new(State::new());
     let executor = Arc::new(buggy::ParallelExecutor::new(Arc::clone(&state)));
 
@@ -247,7 +540,7 @@ fn run_buggy_test() {
     let mut handles = vec![];
 
     // BUG: Submit all tasks in parallel without checking dependencies
-    for task in tasks {
+    for task in tasks.clone() {
         let executor = Arc::clone(&executor);
         let handle = thread::spawn(move || {
             executor.execute_task(task);
@@ -273,15 +566,25 @@ fn run_buggy_test() {
         }
     }
 
+    // A failed task's outputs, and everything derived from them, are missing
+    let derivation_valid = report_derivation(&state, &tasks);
+    assert_eq!(derivation_valid, failed_count == 0, "derivation check should agree with the failed tasks");
+
     if failed_count > 0 {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!(
             "{} task(s) failed due to parallel execution without dependency tracking.",
             failed_count
         );
         println!("Tasks raced ahead before their dependencies completed.");
+        if format_dot {
+            println!("\n=== Dependency Graph (DOT) ===");
+            print!("{}", dependency_graph(&tasks, &results).to_dot("sui_4990"));
+        }
     } else {
-        println!("\n[NOTE]");
+        println!();
+        print_banner("[NOTE]");
         println!("No failures this run (timing-dependent race).");
         println!("Try running multiple times to see the race condition.");
     }
@@ -313,7 +616,7 @@ fn run_fixed_test() {
 
     let mut handles = vec![];
 
-    for task in tasks {
+    for task in tasks.clone() {
         let executor = Arc::clone(&executor);
         let handle = thread::spawn(move || {
             executor.execute_task(task);
@@ -335,7 +638,234 @@ fn run_fixed_test() {
         }
     }
 
-    println!("\n[FIXED]");
+    assert!(report_derivation(&state, &tasks), "fixed executor must produce a valid derivation");
+
+    println!();
+    print_banner("[FIXED]");
     println!("All tasks completed successfully with dependency tracking.");
     println!("Tasks waited for their dependencies before executing.");
 }
+
+/// Run one generated graph with every task submitted on its own thread.
+/// Returns the number of tasks that did not complete successfully.
+fn run_generated(use_fixed: bool, tasks: &[Task]) -> usize {
+    let state = Arc::new(State::new());
+    let results = if use_fixed {
+        let executor = Arc::new(fixed::ParallelExecutor::new(Arc::clone(&state)));
+        let handles: Vec<_> = tasks.iter().cloned().map(|task| {
+            let executor = Arc::clone(&executor);
+            thread::spawn(move || { executor.execute_task(task); })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!(state.verify_derivation(tasks), "fixed executor must produce a valid derivation");
+        executor.get_results()
+    } else {
+        let executor = Arc::new(buggy::ParallelExecutor::new(Arc::clone(&state)));
+        let handles: Vec<_> = tasks.iter().cloned().map(|task| {
+            let executor = Arc::clone(&executor);
+            thread::spawn(move || { executor.execute_task(task); })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        executor.get_results()
+    };
+
+    tasks.iter()
+        .filter(|task| !matches!(results.get(&task.id), Some(TaskResult::Success)))
+        .count()
+}
+
+fn run_seeded_test(use_fixed: bool, seed: u64, size: usize) {
+    let tasks = generate_tasks(seed, size);
+    println!("Generated tasks (submission order):");
+    for task in &tasks {
+        let inputs: Vec<_> = task.inputs.iter().map(|o| o.0.as_str()).collect();
+        println!("  {} consumes {:?}, produces {}", task.id, inputs, task.outputs[0].0);
+    }
+    println!();
+
+    let incomplete = run_generated(use_fixed, &tasks);
+
+    println!("\n=== Results ===");
+    println!("{} of {} tasks did not complete", incomplete, tasks.len());
+    if incomplete == 0 {
+        println!("\n[{}]", if use_fixed { "FIXED" } else { "NOTE" });
+        println!("Every task in graph seed {} completed.", seed);
+    } else if use_fixed {
+        println!();
+        print_banner("[ERROR]");
+        println!("Fixed executor left tasks unfinished on seed {}.", seed);
+    } else {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
+        println!("Tasks raced ahead of their producers on seed {}.", seed);
+    }
+}
+
+fn run_fuzz_test(use_fixed: bool, count: u64, size: usize) {
+    let mut failing_seeds = Vec::new();
+    for seed in 0..count {
+        let incomplete = run_generated(use_fixed, &generate_tasks(seed, size));
+        println!("[FUZZ] seed={} incomplete={}", seed, incomplete);
+        if incomplete > 0 {
+            failing_seeds.push(seed);
+        }
+    }
+
+    println!("\n=== Results ===");
+    println!("{} of {} generated graphs had incomplete tasks", failing_seeds.len(), count);
+
+    match (use_fixed, failing_seeds.first()) {
+        (true, None) => {
+            println!();
+            print_banner("[FIXED]");
+            println!("Dependency tracking completed every generated graph.");
+        }
+        (true, Some(seed)) => {
+            println!();
+            print_banner("[ERROR]");
+            println!("Fixed executor failed; replay with --fixed --seed {}", seed);
+        }
+        (false, Some(seed)) => {
+            println!();
+            print_banner("[BUG DEMONSTRATED]");
+            println!("First failing seed: {} (replay with --seed {})", seed, seed);
+        }
+        (false, None) => {
+            println!();
+            print_banner("[NOTE]");
+            println!("No generated graph failed; try more seeds or a larger --size.");
+        }
+    }
+}
+
+/// Run the buggy executor on seeds `start_seed, start_seed + 1, ...` until
+/// one leaves tasks incomplete or `max_attempts` runs have passed
+fn repeat_until_bug(start_seed: u64, max_attempts: u64, size: usize) -> Outcome {
+    for attempt in 1..=max_attempts {
+        let seed = start_seed + attempt - 1;
+        let incomplete = run_generated(false, &generate_tasks(seed, size));
+        println!("[REPEAT] attempt={} seed={} incomplete={}", attempt, seed, incomplete);
+        if incomplete > 0 {
+            return Outcome::BugManifested { attempt, seed, incomplete };
+        }
+    }
+    Outcome::Exhausted { attempts: max_attempts }
+}
+
+fn run_repeat_test(start_seed: u64, max_attempts: u64, size: usize) {
+    let outcome = repeat_until_bug(start_seed, max_attempts, size);
+
+    println!("\n=== Results ===");
+    match outcome {
+        Outcome::BugManifested { attempt, seed, incomplete } => {
+            assert!(attempt <= max_attempts);
+            println!("Bug manifested on attempt {} (seed {}): {} tasks incomplete", attempt, seed, incomplete);
+
+            // The graph, not the schedule, decides the outcome, so the seed
+            // that triggered the bug triggers it again on the first attempt
+            println!("\nReplaying seed {} with a single attempt:", seed);
+            let replay = repeat_until_bug(seed, 1, size);
+            assert!(
+                matches!(replay, Outcome::BugManifested { attempt: 1, seed: replayed, .. } if replayed == seed),
+                "seed {} should trigger the bug on the first attempt, got {:?}",
+                seed,
+                replay
+            );
+
+            println!("\n=== Metrics ===");
+            println!("attempts: {}", attempt);
+            println!("max_attempts: {}", max_attempts);
+            println!("trigger_seed: {}", seed);
+
+            println!();
+            print_banner("[BUG DEMONSTRATED]");
+            println!("Tasks raced ahead of their producers on attempt {}.", attempt);
+            println!("Replay with --seed {}", seed);
+        }
+        Outcome::Exhausted { attempts } => {
+            assert_eq!(attempts, max_attempts);
+            println!("Bug did not manifest in {} attempts (seeds {}..={})",
+                     attempts, start_seed, start_seed + attempts - 1);
+
+            println!("\n=== Metrics ===");
+            println!("attempts: {}", attempts);
+            println!("max_attempts: {}", max_attempts);
+
+            println!();
+            print_banner("[NOTE]");
+            println!("Attempt cap reached; try a larger --max-attempts or --size.");
+        }
+    }
+}
+
+/// A dependent chain `C1 -> C2 -> ...` hanging off genesis, plus tasks that
+/// each consume only genesis. The chain is submitted tail first, so its later
+/// tasks arrive before their producers.
+fn mixed_tasks(independent: usize, depth: usize) -> Vec<Task> {
+    let chain = (1..=depth).rev().map(|i| Task {
+        id: format!("C{}", i),
+        inputs: vec![ObjectId(if i == 1 { "obj_0".to_string() } else { format!("chain_{}", i - 1) })],
+        outputs: vec![ObjectId(format!("chain_{}", i))],
+    });
+    let free = (1..=independent).map(|i| Task {
+        id: format!("I{}", i),
+        inputs: vec![ObjectId("obj_0".to_string())],
+        outputs: vec![ObjectId(format!("free_{}", i))],
+    });
+    chain.chain(free).collect()
+}
+
+fn run_parallelism_test(independent: usize, depth: usize, work: Duration) {
+    let tasks = mixed_tasks(independent, depth);
+    let state = Arc::new(State::new());
+    let executor = Arc::new(fixed::ParallelExecutor::with_work(Arc::clone(&state), work));
+
+    let started = std::time::Instant::now();
+    let handles: Vec<_> = tasks.iter().cloned().map(|task| {
+        let executor = Arc::clone(&executor);
+        thread::spawn(move || { executor.execute_task(task); })
+    }).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let elapsed = started.elapsed();
+
+    let results = executor.get_results();
+    let completed = tasks.iter()
+        .filter(|task| matches!(results.get(&task.id), Some(TaskResult::Success)))
+        .count();
+    let violations = executor.dependency_violations(&tasks);
+    let max_concurrency = executor.max_concurrency();
+
+    println!("\n=== Results ===");
+    println!("Completed: {} of {} tasks", completed, tasks.len());
+    println!("Max tasks executing at once: {}", max_concurrency);
+    println!("Dependency violations: {:?}", violations);
+    let derivation_valid = report_derivation(&state, &tasks);
+
+    println!("\n=== Metrics ===");
+    println!("independent_tasks: {}", independent);
+    println!("chain_depth: {}", depth);
+    println!("work_ms: {}", work.as_millis());
+    println!("max_concurrency: {}", max_concurrency);
+    println!("elapsed_ms: {}", elapsed.as_millis());
+    println!("serial_ms: {}", (work * tasks.len() as u32).as_millis());
+
+    assert_eq!(completed, tasks.len(), "fixed executor must complete every task");
+    assert!(violations.is_empty(), "a task started before its producer finished: {:?}", violations);
+    assert!(derivation_valid);
+    // The chain head and every independent task consume only genesis, so
+    // with any work to overlap at least two of them run at once
+    if independent > 0 && depth + independent > 1 && !work.is_zero() {
+        assert!(max_concurrency > 1, "independent tasks should execute concurrently");
+    }
+
+    println!();
+    print_banner("[FIXED]");
+    println!("Up to {} tasks executed at once, while the chain ran one link at a time.", max_concurrency);
+    println!("Dependency tracking serializes only the tasks that depend on each other.");
+}
@@ -8,9 +8,9 @@
 //!
 //! Original Issue: https://github.com/MystenLabs/sui/issues/5469
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Barrier, Mutex, RwLock};
 use std::thread;
 use std::time::Duration;
 
@@ -83,6 +83,35 @@ impl PendingCerts {
     }
 }
 
+/// Forces the race: the download thread stops right before its pending
+/// check and waits for consensus processing to finish first
+pub struct RaceHook {
+    download_done: Barrier,
+    consensus_done: Barrier,
+}
+
+impl RaceHook {
+    fn new() -> Self {
+        Self {
+            download_done: Barrier::new(2),
+            consensus_done: Barrier::new(2),
+        }
+    }
+
+    /// Called by the download thread between download and pending check
+    fn before_pending_check(&self) {
+        self.download_done.wait();
+        self.consensus_done.wait();
+    }
+
+    /// Called by the consensus thread: runs `process` inside that window
+    fn run_consensus(&self, process: impl FnOnce()) {
+        self.download_done.wait();
+        process();
+        self.consensus_done.wait();
+    }
+}
+
 /// Buggy version - race between download check and process completion
 mod buggy {
     use super::*;
@@ -90,11 +119,16 @@ mod buggy {
     pub struct NodeSyncState {
         store: Arc<NodeSyncStore>,
         pending: Arc<PendingCerts>,
+        hook: Option<Arc<RaceHook>>,
     }
 
     impl NodeSyncState {
         pub fn new(store: Arc<NodeSyncStore>, pending: Arc<PendingCerts>) -> Self {
-            Self { store, pending }
+            Self { store, pending, hook: None }
+        }
+
+        pub fn with_hook(store: Arc<NodeSyncStore>, pending: Arc<PendingCerts>, hook: Arc<RaceHook>) -> Self {
+            Self { store, pending, hook: Some(hook) }
         }
 
         /// Download and process a certificate
@@ -124,6 +158,9 @@ mod buggy {
 
             // Small delay to widen race window for demonstration
             thread::sleep(Duration::from_millis(10));
+            if let Some(hook) = &self.hook {
+                hook.before_pending_check();
+            }
 
             // Check if cert is still pending (another thread may have processed it)
             if !self.pending.contains(cert_digest) {
@@ -178,14 +215,141 @@ mod buggy {
 mod fixed {
     use super::*;
 
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+    /// Execute a certificate and compute its effect.
+    /// Digests containing "poison" can never be executed (a poison message).
+    fn execute(cert_digest: &str) -> Result<CertificateEffect, String> {
+        if cert_digest.contains("poison") {
+            return Err(format!("cert {} is malformed", cert_digest));
+        }
+        Ok(CertificateEffect {
+            cert_digest: cert_digest.to_string(),
+            effect_digest: format!("effect_{}", cert_digest),
+        })
+    }
+
+    /// FIX: Failed certs are retried a bounded number of times, then
+    /// quarantined in a dead-letter list instead of being retried forever
+    pub struct RetryQueue {
+        max_attempts: u32,
+        queue: Mutex<VecDeque<(CertDigest, u32)>>,
+        /// Quarantined certs with the number of attempts each one got
+        dead_letters: Mutex<Vec<(CertDigest, u32)>>,
+        retries: Mutex<u64>,
+    }
+
+    impl RetryQueue {
+        pub fn new(max_attempts: u32) -> Self {
+            Self {
+                max_attempts: max_attempts.max(1),
+                queue: Mutex::new(VecDeque::new()),
+                dead_letters: Mutex::new(Vec::new()),
+                retries: Mutex::new(0),
+            }
+        }
+
+        /// Enqueue a cert that has already failed once
+        pub fn enqueue(&self, cert_digest: &str) {
+            let mut queue = self.queue.lock().unwrap();
+            if queue.iter().any(|(digest, _)| digest == cert_digest) {
+                return;
+            }
+            println!("[RETRY] Queued cert {} for retry", cert_digest);
+            queue.push_back((cert_digest.to_string(), 1));
+        }
+
+        /// Retry queued certs until every one is either stored or dead-lettered.
+        /// A failing cert goes to the back of the queue so it never blocks others.
+        pub fn drain(&self, store: &NodeSyncStore) {
+            loop {
+                let next = self.queue.lock().unwrap().pop_front();
+                let Some((cert_digest, attempts)) = next else {
+                    break;
+                };
+
+                if store.has_effect(&cert_digest) {
+                    println!("[RETRY] Effect for cert {} stored elsewhere, done", cert_digest);
+                    continue;
+                }
+
+                if attempts >= self.max_attempts {
+                    println!("[RETRY] Cert {} already failed {} times, moving to dead letters",
+                             cert_digest, attempts);
+                    self.dead_letters.lock().unwrap().push((cert_digest, attempts));
+                    continue;
+                }
+
+                *self.retries.lock().unwrap() += 1;
+                let attempt = attempts + 1;
+
+                match execute(&cert_digest) {
+                    Ok(effect) => {
+                        println!("[RETRY] Cert {} succeeded on attempt {}", cert_digest, attempt);
+                        store.store_effect(effect);
+                    }
+                    Err(reason) if attempt >= self.max_attempts => {
+                        println!(
+                            "[RETRY] Cert {} failed {} times ({}), moving to dead letters",
+                            cert_digest, attempt, reason
+                        );
+                        self.dead_letters.lock().unwrap().push((cert_digest, attempt));
+                    }
+                    Err(reason) => {
+                        println!("[RETRY] Cert {} failed attempt {}: {}", cert_digest, attempt, reason);
+                        self.queue.lock().unwrap().push_back((cert_digest, attempt));
+                        thread::sleep(Duration::from_millis(5));
+                    }
+                }
+            }
+        }
+
+        pub fn dead_letters(&self) -> Vec<CertDigest> {
+            self.dead_letters.lock().unwrap().iter().map(|(digest, _)| digest.clone()).collect()
+        }
+
+        /// Attempts a dead-lettered cert got, counting the first one
+        pub fn dead_letter_attempts(&self, cert_digest: &str) -> Option<u32> {
+            self.dead_letters
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(digest, _)| digest == cert_digest)
+                .map(|&(_, attempts)| attempts)
+        }
+
+        pub fn max_attempts(&self) -> u32 {
+            self.max_attempts
+        }
+
+        pub fn retry_count(&self) -> u64 {
+            *self.retries.lock().unwrap()
+        }
+    }
+
     pub struct NodeSyncState {
         store: Arc<NodeSyncStore>,
         pending: Arc<PendingCerts>,
+        retry_queue: Arc<RetryQueue>,
+        hook: Option<Arc<RaceHook>>,
     }
 
     impl NodeSyncState {
-        pub fn new(store: Arc<NodeSyncStore>, pending: Arc<PendingCerts>) -> Self {
-            Self { store, pending }
+        pub fn new(
+            store: Arc<NodeSyncStore>,
+            pending: Arc<PendingCerts>,
+            retry_queue: Arc<RetryQueue>,
+        ) -> Self {
+            Self { store, pending, retry_queue, hook: None }
+        }
+
+        pub fn with_hook(
+            store: Arc<NodeSyncStore>,
+            pending: Arc<PendingCerts>,
+            retry_queue: Arc<RetryQueue>,
+            hook: Arc<RaceHook>,
+        ) -> Self {
+            Self { store, pending, retry_queue, hook: Some(hook) }
         }
 
         /// FIX: Always ensure effect is stored, regardless of pending status
@@ -204,6 +368,9 @@ mod fixed {
             println!("[FIXED] Download complete for cert: {}", cert_digest);
 
             thread::sleep(Duration::from_millis(10));
+            if let Some(hook) = &self.hook {
+                hook.before_pending_check();
+            }
 
             // FIX: Don't skip processing based on pending status
             // Always check if effect exists before deciding to skip
@@ -214,12 +381,13 @@ mod fixed {
             }
 
             // Process and store effect
-            let effect = CertificateEffect {
-                cert_digest: cert_digest.to_string(),
-                effect_digest: format!("effect_{}", cert_digest),
-            };
-
-            self.store.store_effect(effect);
+            match execute(cert_digest) {
+                Ok(effect) => self.store.store_effect(effect),
+                Err(reason) => {
+                    println!("[FIXED] Failed to execute cert {}: {}", cert_digest, reason);
+                    self.retry_queue.enqueue(cert_digest);
+                }
+            }
             self.pending.remove(cert_digest);
         }
 
@@ -238,42 +406,344 @@ mod fixed {
             self.pending.remove(cert_digest);
 
             // Even on "failure", ensure we don't lose the effect
-            // In the fix, failures should be retried or handled properly
+            // Simulate an intermittent failure (e.g., db error, timeout)
             let should_fail = cert_digest.contains("fail");
 
             if should_fail {
-                // FIX: Re-add to pending for retry instead of losing the effect
-                println!("[FIXED] Processing failed for cert {}, re-adding to pending for retry", cert_digest);
-                self.pending.add(cert_digest);
-                // In real fix, would trigger retry mechanism
-                // For demo, we'll store a placeholder effect
-                let effect = CertificateEffect {
-                    cert_digest: cert_digest.to_string(),
-                    effect_digest: format!("effect_{}_retry", cert_digest),
-                };
-                self.store.store_effect(effect);
-                self.pending.remove(cert_digest);
+                // FIX: Hand the cert to the retry queue instead of losing the effect
+                println!("[FIXED] Processing failed for cert {}, queueing for retry", cert_digest);
+                self.retry_queue.enqueue(cert_digest);
                 return;
             }
 
-            let effect = CertificateEffect {
-                cert_digest: cert_digest.to_string(),
-                effect_digest: format!("effect_{}", cert_digest),
-            };
-            self.store.store_effect(effect);
+            match execute(cert_digest) {
+                Ok(effect) => self.store.store_effect(effect),
+                Err(reason) => {
+                    println!("[FIXED] Failed to execute cert {}: {}", cert_digest, reason);
+                    self.retry_queue.enqueue(cert_digest);
+                }
+            }
+        }
+    }
+}
+
+/// Effect records in reusable slots, reached through a digest index.
+/// Pruning unlinks a digest from the index and frees its slot, and the next
+/// insert reuses the lowest free slot.
+pub struct SlotStore {
+    index: RwLock<HashMap<CertDigest, usize>>,
+    slots: RwLock<Vec<Option<CertificateEffect>>>,
+    free: Mutex<Vec<usize>>,
+}
+
+impl SlotStore {
+    fn new() -> Self {
+        Self {
+            index: RwLock::new(HashMap::new()),
+            slots: RwLock::new(Vec::new()),
+            free: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn insert(&self, effect: CertificateEffect) {
+        let digest = effect.cert_digest.clone();
+        let mut slots = self.slots.write().unwrap();
+        let slot = match self.free.lock().unwrap().pop() {
+            Some(slot) => {
+                slots[slot] = Some(effect);
+                slot
+            }
+            None => {
+                slots.push(Some(effect));
+                slots.len() - 1
+            }
+        };
+        drop(slots);
+        self.index.write().unwrap().insert(digest, slot);
+    }
+
+    fn lookup(&self, digest: &str) -> Option<usize> {
+        self.index.read().unwrap().get(digest).copied()
+    }
+
+    fn read_slot(&self, slot: usize) -> Option<CertificateEffect> {
+        self.slots.read().unwrap()[slot].clone()
+    }
+
+    /// Remove a digest from the index; its slot stays intact until freed
+    fn unlink(&self, digest: &str) -> Option<usize> {
+        self.index.write().unwrap().remove(digest)
+    }
+
+    fn free_slot(&self, slot: usize) {
+        self.slots.write().unwrap()[slot] = None;
+        let mut free = self.free.lock().unwrap();
+        free.push(slot);
+        free.sort_unstable_by(|a, b| b.cmp(a));
+    }
+}
+
+/// A read that found `digest` in the index must return that cert's effect.
+/// An empty slot or another cert's effect means the entry was reclaimed
+/// while the reader was still using it.
+fn is_consistent(digest: &str, read: &Option<CertificateEffect>) -> bool {
+    read.as_ref().is_some_and(|effect| effect.cert_digest == digest)
+}
+
+/// Forces a prune between a reader's index lookup and its slot read
+pub struct ReclaimHook {
+    looked_up: Barrier,
+    pruned: Barrier,
+}
+
+impl ReclaimHook {
+    fn new() -> Self {
+        Self {
+            looked_up: Barrier::new(2),
+            pruned: Barrier::new(2),
+        }
+    }
+
+    /// Called by the reader after it found the slot in the index
+    fn after_lookup(&self) {
+        self.looked_up.wait();
+        self.pruned.wait();
+    }
+
+    /// Called by the pruner: runs `prune` inside the reader's window
+    fn run_pruner(&self, prune: impl FnOnce()) {
+        self.looked_up.wait();
+        prune();
+        self.pruned.wait();
+    }
+}
+
+/// Buggy pruning - a slot is freed as soon as its digest is unlinked
+mod buggy_reclaim {
+    use super::*;
+
+    pub struct EffectCache {
+        store: SlotStore,
+        hook: Option<Arc<ReclaimHook>>,
+    }
+
+    impl EffectCache {
+        pub fn new() -> Self {
+            Self { store: SlotStore::new(), hook: None }
+        }
+
+        pub fn with_hook(hook: Arc<ReclaimHook>) -> Self {
+            Self { store: SlotStore::new(), hook: Some(hook) }
+        }
+
+        pub fn insert(&self, effect: CertificateEffect) {
+            self.store.insert(effect);
+        }
+
+        /// Returns None if the digest is not indexed, otherwise what its slot held
+        pub fn read(&self, digest: &str) -> Option<Option<CertificateEffect>> {
+            let slot = self.store.lookup(digest)?;
+            if let Some(hook) = &self.hook {
+                hook.after_lookup();
+            }
+            // Window between finding the slot and reading it
+            thread::yield_now();
+            Some(self.store.read_slot(slot))
+        }
+
+        /// BUG: frees the slot immediately, even if a reader already found it
+        pub fn prune(&self, digest: &str) {
+            if let Some(slot) = self.store.unlink(digest) {
+                self.store.free_slot(slot);
+            }
         }
     }
 }
 
+/// Fixed pruning - epoch-based reclamation with a grace period
+mod fixed_epoch {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    /// Global epoch plus the epoch each active reader pinned
+    pub struct Epochs {
+        global: AtomicU64,
+        pinned: Mutex<Vec<u64>>,
+    }
+
+    /// Keeps its epoch pinned until dropped
+    pub struct EpochGuard<'a> {
+        epochs: &'a Epochs,
+        epoch: u64,
+    }
+
+    impl Epochs {
+        fn new() -> Self {
+            Self {
+                global: AtomicU64::new(0),
+                pinned: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn pin(&self) -> EpochGuard<'_> {
+            let mut pinned = self.pinned.lock().unwrap();
+            let epoch = self.global.load(Ordering::SeqCst);
+            pinned.push(epoch);
+            EpochGuard { epochs: self, epoch }
+        }
+
+        /// Retire at the current epoch, then move on to the next one
+        fn retire_epoch(&self) -> u64 {
+            self.global.fetch_add(1, Ordering::SeqCst)
+        }
+
+        fn oldest_pinned(&self) -> Option<u64> {
+            self.pinned.lock().unwrap().iter().copied().min()
+        }
+    }
+
+    impl Drop for EpochGuard<'_> {
+        fn drop(&mut self) {
+            let mut pinned = self.epochs.pinned.lock().unwrap();
+            if let Some(pos) = pinned.iter().position(|&epoch| epoch == self.epoch) {
+                pinned.swap_remove(pos);
+            }
+        }
+    }
+
+    pub struct EffectCache {
+        store: SlotStore,
+        epochs: Epochs,
+        /// (retire epoch, slot) pairs waiting out their grace period
+        retired: Mutex<Vec<(u64, usize)>>,
+        reclaimed: AtomicUsize,
+        hook: Option<Arc<ReclaimHook>>,
+    }
+
+    impl EffectCache {
+        pub fn new() -> Self {
+            Self::build(None)
+        }
+
+        pub fn with_hook(hook: Arc<ReclaimHook>) -> Self {
+            Self::build(Some(hook))
+        }
+
+        fn build(hook: Option<Arc<ReclaimHook>>) -> Self {
+            Self {
+                store: SlotStore::new(),
+                epochs: Epochs::new(),
+                retired: Mutex::new(Vec::new()),
+                reclaimed: AtomicUsize::new(0),
+                hook,
+            }
+        }
+
+        pub fn insert(&self, effect: CertificateEffect) {
+            self.store.insert(effect);
+        }
+
+        /// FIX: the reader pins the epoch before the lookup, so a slot it
+        /// finds cannot be freed until it is done with it
+        pub fn read(&self, digest: &str) -> Option<Option<CertificateEffect>> {
+            let _guard = self.epochs.pin();
+            let slot = self.store.lookup(digest)?;
+            if let Some(hook) = &self.hook {
+                hook.after_lookup();
+            }
+            thread::yield_now();
+            Some(self.store.read_slot(slot))
+        }
+
+        /// FIX: unlink now, free later. The slot is retired at the current
+        /// epoch and only freed once no reader pinned at or before it remains.
+        pub fn prune(&self, digest: &str) {
+            if let Some(slot) = self.store.unlink(digest) {
+                let epoch = self.epochs.retire_epoch();
+                self.retired.lock().unwrap().push((epoch, slot));
+            }
+            self.try_reclaim();
+        }
+
+        /// Free every retired slot whose grace period has passed
+        pub fn try_reclaim(&self) -> usize {
+            let oldest = self.epochs.oldest_pinned();
+            let mut retired = self.retired.lock().unwrap();
+            let before = retired.len();
+            retired.retain(|&(epoch, slot)| {
+                let safe = oldest.is_none_or(|oldest| epoch < oldest);
+                if safe {
+                    self.store.free_slot(slot);
+                }
+                !safe
+            });
+            let freed = before - retired.len();
+            self.reclaimed.fetch_add(freed, Ordering::SeqCst);
+            freed
+        }
+
+        pub fn pending_reclaim(&self) -> usize {
+            self.retired.lock().unwrap().len()
+        }
+
+        pub fn reclaimed(&self) -> usize {
+            self.reclaimed.load(Ordering::SeqCst)
+        }
+    }
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let with_poison = args.iter().any(|arg| arg == "--with-poison");
+    let max_attempts = args
+        .iter()
+        .position(|arg| arg == "--max-attempts")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(fixed::DEFAULT_MAX_ATTEMPTS);
 
     println!("=== Sui Issue #5469: Missing Certificate Effect Race ===\n");
 
+    if args.iter().any(|arg| arg == "--reclaim") {
+        println!("Pruning effects under concurrent readers, BUGGY and FIXED...\n");
+        run_reclaim_test();
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "--force-race") {
+        println!("Forcing the race for BUGGY and FIXED versions...\n");
+        run_force_race_test();
+        return;
+    }
+
     if use_fixed {
         println!("Running FIXED version (check effect existence, not pending status)...\n");
-        run_fixed_test();
+        run_fixed_test(max_attempts, with_poison);
     } else {
         println!("Running BUGGY version (race causes missing effect)...\n");
         run_buggy_test();
@@ -328,7 +798,8 @@ fn run_buggy_test() {
         .collect();
 
     if !missing.is_empty() {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Missing effects for certificates: {:?}", missing);
         println!("\nProblem:");
         println!("  - Download thread saw cert removed from pending");
@@ -341,19 +812,104 @@ fn run_buggy_test() {
     }
 }
 
-fn run_fixed_test() {
-    let store = Arc::new(NodeSyncStore::new());
-    let pending = Arc::new(PendingCerts::new());
+/// The cert whose consensus processing fails in the forced schedule
+const FORCED_CERT: &str = "cert_fail_2";
+
+/// Run one download and one consensus thread for `FORCED_CERT`, with
+/// consensus processing scheduled inside the download thread's window
+fn run_forced<S: Send + Sync + 'static>(
+    state: S,
+    download: fn(&S, &str),
+    consensus: fn(&S, &str),
+    hook: Arc<RaceHook>,
+) {
+    let state = Arc::new(state);
+    let s = Arc::clone(&state);
+    let download_handle = thread::spawn(move || download(&s, FORCED_CERT));
+    let s = Arc::clone(&state);
+    let consensus_handle = thread::spawn(move || hook.run_consensus(|| consensus(&s, FORCED_CERT)));
+
+    download_handle.join().unwrap();
+    consensus_handle.join().unwrap();
+}
 
-    let certs = vec!["cert_1", "cert_fail_2", "cert_3"];
+fn run_force_race_test() {
+    println!("Schedule: download {} -> consensus removes it from pending and fails", FORCED_CERT);
+    println!("          -> download checks pending\n");
+
+    println!("--- BUGGY ---");
+    let buggy_store = Arc::new(NodeSyncStore::new());
+    let hook = Arc::new(RaceHook::new());
+    run_forced(
+        buggy::NodeSyncState::with_hook(
+            Arc::clone(&buggy_store),
+            Arc::new(PendingCerts::new()),
+            Arc::clone(&hook),
+        ),
+        buggy::NodeSyncState::download_and_sync,
+        buggy::NodeSyncState::process_from_consensus,
+        hook,
+    );
+    let buggy_stored = buggy_store.has_effect(FORCED_CERT);
+
+    println!("\n--- FIXED ---");
+    let fixed_store = Arc::new(NodeSyncStore::new());
+    let retry_queue = Arc::new(fixed::RetryQueue::new(fixed::DEFAULT_MAX_ATTEMPTS));
+    let hook = Arc::new(RaceHook::new());
+    run_forced(
+        fixed::NodeSyncState::with_hook(
+            Arc::clone(&fixed_store),
+            Arc::new(PendingCerts::new()),
+            Arc::clone(&retry_queue),
+            Arc::clone(&hook),
+        ),
+        fixed::NodeSyncState::download_and_sync,
+        fixed::NodeSyncState::process_from_consensus,
+        hook,
+    );
+    retry_queue.drain(&fixed_store);
+    let fixed_stored = fixed_store.has_effect(FORCED_CERT);
 
-    println!("Scenario: Download 3 certs while consensus processes them concurrently");
-    println!("cert_fail_2 will fail in consensus processing (but effect still stored)\n");
+    println!("\n=== Results ===");
+    println!("[BUGGY] Effect for {} stored: {}", FORCED_CERT, buggy_stored);
+    println!("[FIXED] Effect for {} stored: {}", FORCED_CERT, fixed_stored);
+
+    assert!(!buggy_stored, "forced schedule must lose the effect in the buggy version");
+    assert!(fixed_stored, "fixed version must store the effect under the forced schedule");
+
+    println!();
+    print_banner("[BUG DEMONSTRATED]");
+    println!("Consensus removed the cert from pending and failed; the download thread");
+    println!("then saw it was no longer pending and skipped it. No effect was stored.");
+    print_banner("[FIXED]");
+    println!("The download thread checked for the effect itself, found none, and stored it.");
+}
+
+/// Invariant after both paths and the retry drain, checked in debug builds:
+/// every certificate is accounted for exactly once, either as a stored effect
+/// or a dead letter
+fn debug_assert_accounted(certs: &[&str], stored_effects: &[CertDigest], dead_letters: &[CertDigest]) {
+    debug_assert_eq!(
+        stored_effects.len() + dead_letters.len(),
+        certs.len(),
+        "fixed node sync lost or duplicated a certificate effect"
+    );
+}
+
+/// Download and process every cert on the fixed paths, then drain the retries
+fn run_fixed_sync(certs: &[&str], max_attempts: u32) -> (Arc<NodeSyncStore>, Arc<fixed::RetryQueue>) {
+    let store = Arc::new(NodeSyncStore::new());
+    let pending = Arc::new(PendingCerts::new());
+    let retry_queue = Arc::new(fixed::RetryQueue::new(max_attempts));
 
     let mut handles = vec![];
 
-    for cert in &certs {
-        let state = fixed::NodeSyncState::new(Arc::clone(&store), Arc::clone(&pending));
+    for cert in certs {
+        let state = fixed::NodeSyncState::new(
+            Arc::clone(&store),
+            Arc::clone(&pending),
+            Arc::clone(&retry_queue),
+        );
         let cert = cert.to_string();
         handles.push(thread::spawn(move || {
             state.download_and_sync(&cert);
@@ -362,8 +918,12 @@ fn run_fixed_test() {
 
     thread::sleep(Duration::from_millis(30));
 
-    for cert in &certs {
-        let state = fixed::NodeSyncState::new(Arc::clone(&store), Arc::clone(&pending));
+    for cert in certs {
+        let state = fixed::NodeSyncState::new(
+            Arc::clone(&store),
+            Arc::clone(&pending),
+            Arc::clone(&retry_queue),
+        );
         let cert = cert.to_string();
         handles.push(thread::spawn(move || {
             state.process_from_consensus(&cert);
@@ -374,24 +934,277 @@ fn run_fixed_test() {
         handle.join().unwrap();
     }
 
+    // Retry everything that failed; poison certs end up in dead letters
+    retry_queue.drain(&store);
+
+    (store, retry_queue)
+}
+
+fn run_fixed_test(max_attempts: u32, with_poison: bool) {
+    let mut certs = vec!["cert_1", "cert_fail_2", "cert_3"];
+    if with_poison {
+        certs.push("cert_poison_4");
+    }
+
+    println!("Scenario: Download {} certs while consensus processes them concurrently", certs.len());
+    println!("cert_fail_2 will fail in consensus processing (but effect still stored)");
+    if with_poison {
+        println!("cert_poison_4 can never be executed (poison message)");
+    }
+    println!("Retry queue: max {} attempts per cert\n", max_attempts);
+
+    let (store, retry_queue) = run_fixed_sync(&certs, max_attempts);
+
     let stored_effects = store.get_all_effects();
+    let dead_letters = retry_queue.dead_letters();
+    let max_attempts = retry_queue.max_attempts();
+    debug_assert_accounted(&certs, &stored_effects, &dead_letters);
 
     println!("\n=== Results ===");
     println!("Expected certificates: {:?}", certs);
     println!("Stored effects: {:?}", stored_effects);
-
-    let missing: Vec<_> = certs.iter()
+    println!("Dead letters: {:?}", dead_letters);
+
+    println!("\n=== Metrics ===");
+    println!("retry_attempts: {}", retry_queue.retry_count());
+    println!("dead_letter_count: {}", dead_letters.len());
+
+    // Every healthy cert must have its effect stored, and only the poison
+    // certs may be dead-lettered, each after exactly `max_attempts` tries.
+    // The first try happens on the sync paths, so each poison cert accounts
+    // for `max_attempts - 1` retries.
+    let (poison, healthy): (Vec<&str>, Vec<&str>) = certs.iter().partition(|c| c.contains("poison"));
+    let missing: Vec<_> = healthy.iter()
         .filter(|c| !stored_effects.contains(&c.to_string()))
         .collect();
-
-    if missing.is_empty() {
-        println!("\n[FIXED]");
+    assert!(missing.is_empty(), "fixed node sync lost effects for {:?}", missing);
+    let mut quarantined = dead_letters.clone();
+    quarantined.sort();
+    assert_eq!(quarantined, poison, "dead letters should be exactly the poison certs");
+    for cert in &poison {
+        assert_eq!(
+            retry_queue.dead_letter_attempts(cert),
+            Some(max_attempts),
+            "{} should be dead-lettered after {} attempts",
+            cert,
+            max_attempts
+        );
+    }
+    assert_eq!(
+        retry_queue.retry_count(),
+        poison.len() as u64 * u64::from(max_attempts - 1),
+        "only poison certs should be retried"
+    );
+
+    println!();
+    print_banner("[FIXED]");
+    if dead_letters.is_empty() {
         println!("All effects stored!");
-        println!("\nFix: Check effect existence, not pending status");
-        println!("  - Don't skip based on pending flag alone");
-        println!("  - Always verify effect is stored before skipping");
-        println!("  - Handle failures by retry, not silent skip");
     } else {
-        println!("\nUnexpected: Missing effects {:?}", missing);
+        println!("All healthy effects stored; {} poison cert(s) quarantined after {} attempts!",
+                 dead_letters.len(), max_attempts);
+    }
+    println!("\nFix: Check effect existence, not pending status");
+    println!("  - Don't skip based on pending flag alone");
+    println!("  - Always verify effect is stored before skipping");
+    println!("  - Handle failures by retry, not silent skip");
+    println!("  - Quarantine certs that fail every retry in dead letters");
+}
+
+fn effect_for(digest: &str) -> CertificateEffect {
+    CertificateEffect {
+        cert_digest: digest.to_string(),
+        effect_digest: format!("effect_{}", digest),
+    }
+}
+
+/// The reader looks up `cert_old`; before it reads the slot, the pruner
+/// prunes `cert_old` and inserts `cert_new`, which reuses a freed slot.
+/// Returns what the reader read.
+fn run_reclaim_forced<C: Send + Sync + 'static>(
+    cache: C,
+    insert: fn(&C, CertificateEffect),
+    read: fn(&C, &str) -> Option<Option<CertificateEffect>>,
+    prune: fn(&C, &str),
+    hook: Arc<ReclaimHook>,
+) -> (Arc<C>, Option<CertificateEffect>) {
+    let cache = Arc::new(cache);
+    insert(&cache, effect_for("cert_old"));
+
+    let c = Arc::clone(&cache);
+    let reader = thread::spawn(move || read(&c, "cert_old").expect("cert_old is indexed"));
+    let c = Arc::clone(&cache);
+    let pruner = thread::spawn(move || {
+        hook.run_pruner(|| {
+            prune(&c, "cert_old");
+            insert(&c, effect_for("cert_new"));
+        })
+    });
+
+    let seen = reader.join().unwrap();
+    pruner.join().unwrap();
+    (cache, seen)
+}
+
+/// Effects inserted and pruned by the churn thread in the stress run
+const RECLAIM_ROUNDS: usize = 2000;
+/// Effects kept live behind the churn thread
+const RECLAIM_WINDOW: usize = 4;
+const RECLAIM_READERS: usize = 4;
+
+/// One thread inserts effect k and prunes effect k - RECLAIM_WINDOW while
+/// readers look up recent digests. Returns (reads that found the digest,
+/// inconsistent reads).
+fn run_reclaim_stress<C: Send + Sync + 'static>(
+    cache: Arc<C>,
+    insert: fn(&C, CertificateEffect),
+    read: fn(&C, &str) -> Option<Option<CertificateEffect>>,
+    prune: fn(&C, &str),
+) -> (usize, usize) {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    let head = Arc::new(AtomicUsize::new(0));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let readers: Vec<_> = (0..RECLAIM_READERS)
+        .map(|r| {
+            let (cache, head, done) = (Arc::clone(&cache), Arc::clone(&head), Arc::clone(&done));
+            thread::spawn(move || {
+                let (mut found, mut inconsistent) = (0, 0);
+                let mut i = r;
+                while !done.load(Ordering::SeqCst) {
+                    let oldest = head.load(Ordering::SeqCst).saturating_sub(RECLAIM_WINDOW);
+                    let digest = format!("cert_{}", oldest + i % RECLAIM_WINDOW);
+                    if let Some(seen) = read(&cache, &digest) {
+                        found += 1;
+                        if !is_consistent(&digest, &seen) {
+                            inconsistent += 1;
+                        }
+                    }
+                    i += 1;
+                }
+                (found, inconsistent)
+            })
+        })
+        .collect();
+
+    for k in 0..RECLAIM_ROUNDS {
+        insert(&cache, effect_for(&format!("cert_{}", k)));
+        head.store(k, Ordering::SeqCst);
+        if k >= RECLAIM_WINDOW {
+            prune(&cache, &format!("cert_{}", k - RECLAIM_WINDOW));
+        }
+        thread::yield_now();
+    }
+    done.store(true, Ordering::SeqCst);
+
+    readers
+        .into_iter()
+        .map(|reader| reader.join().unwrap())
+        .fold((0, 0), |(f, i), (found, inconsistent)| (f + found, i + inconsistent))
+}
+
+fn run_reclaim_test() {
+    let describe = |seen: &Option<CertificateEffect>| match seen {
+        Some(effect) => format!("{} ({})", effect.cert_digest, effect.effect_digest),
+        None => "an empty slot".to_string(),
+    };
+    println!("Schedule: reader finds cert_old -> pruner prunes cert_old, inserts cert_new");
+    println!("          -> reader reads the slot it found\n");
+
+    let hook = Arc::new(ReclaimHook::new());
+    let (_, buggy_seen) = run_reclaim_forced(
+        buggy_reclaim::EffectCache::with_hook(Arc::clone(&hook)),
+        buggy_reclaim::EffectCache::insert,
+        buggy_reclaim::EffectCache::read,
+        buggy_reclaim::EffectCache::prune,
+        hook,
+    );
+    println!("[BUGGY] Reader asked for cert_old and read {}", describe(&buggy_seen));
+
+    let hook = Arc::new(ReclaimHook::new());
+    let (fixed_cache, fixed_seen) = run_reclaim_forced(
+        fixed_epoch::EffectCache::with_hook(Arc::clone(&hook)),
+        fixed_epoch::EffectCache::insert,
+        fixed_epoch::EffectCache::read,
+        fixed_epoch::EffectCache::prune,
+        hook,
+    );
+    let deferred = fixed_cache.pending_reclaim();
+    let freed_after = fixed_cache.try_reclaim();
+    println!("[FIXED] Reader asked for cert_old and read {}", describe(&fixed_seen));
+    println!(
+        "[FIXED] {} slot waited out the reader's grace period, {} freed after it unpinned",
+        deferred, freed_after
+    );
+
+    assert!(!is_consistent("cert_old", &buggy_seen), "forced prune must reclaim the slot under the reader");
+    assert!(is_consistent("cert_old", &fixed_seen), "pinned reader must still see cert_old");
+    assert_eq!((deferred, freed_after), (1, 1), "slot must be freed only after the reader unpins");
+
+    println!("\nStress: {} inserts and prunes, {} readers, {} live effects", RECLAIM_ROUNDS, RECLAIM_READERS, RECLAIM_WINDOW);
+    let (buggy_found, buggy_torn) = run_reclaim_stress(
+        Arc::new(buggy_reclaim::EffectCache::new()),
+        buggy_reclaim::EffectCache::insert,
+        buggy_reclaim::EffectCache::read,
+        buggy_reclaim::EffectCache::prune,
+    );
+    let fixed_cache = Arc::new(fixed_epoch::EffectCache::new());
+    let (fixed_found, fixed_torn) = run_reclaim_stress(
+        Arc::clone(&fixed_cache),
+        fixed_epoch::EffectCache::insert,
+        fixed_epoch::EffectCache::read,
+        fixed_epoch::EffectCache::prune,
+    );
+    fixed_cache.try_reclaim();
+    println!("[BUGGY] {} of {} reads saw a reclaimed slot", buggy_torn, buggy_found);
+    println!("[FIXED] {} of {} reads saw a reclaimed slot", fixed_torn, fixed_found);
+
+    assert_eq!(fixed_torn, 0, "epoch-protected readers must always see a consistent entry");
+    assert_eq!(fixed_cache.pending_reclaim(), 0, "every retired slot is freed once readers finish");
+
+    println!("\n=== Metrics ===");
+    println!("buggy_reads: {}", buggy_found);
+    println!("buggy_inconsistent_reads: {}", buggy_torn);
+    println!("fixed_reads: {}", fixed_found);
+    println!("fixed_inconsistent_reads: {}", fixed_torn);
+    println!("fixed_reclaimed: {}", fixed_cache.reclaimed());
+
+    println!("\n=== Results ===");
+    print_banner("[BUG DEMONSTRATED]");
+    println!("Freeing a slot as soon as it is unlinked lets a reader that already found it");
+    println!("read whatever the slot holds next.");
+    print_banner("[FIXED]");
+    println!("Readers pin an epoch; a retired slot is freed only after every reader that");
+    println!("could have seen it has unpinned.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_sync_passes_its_debug_invariants() {
+        for certs in [
+            vec!["cert_1", "cert_fail_2", "cert_3"],
+            vec!["cert_1", "cert_fail_2", "cert_3", "cert_poison_4"],
+        ] {
+            let (store, retry_queue) = run_fixed_sync(&certs, 3);
+            debug_assert_accounted(&certs, &store.get_all_effects(), &retry_queue.dead_letters());
+        }
+    }
+
+    /// A cert that never reaches the store or the dead letters, as when the
+    /// buggy skip drops it, makes the invariant fire
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "fixed node sync lost or duplicated a certificate effect")]
+    fn dropped_cert_trips_the_debug_invariant() {
+        let (store, retry_queue) = run_fixed_sync(&["cert_1", "cert_fail_2"], 3);
+        debug_assert_accounted(
+            &["cert_1", "cert_fail_2", "cert_3"],
+            &store.get_all_effects(),
+            &retry_queue.dead_letters(),
+        );
     }
 }
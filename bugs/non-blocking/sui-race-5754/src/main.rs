@@ -8,9 +8,9 @@
 //! Fix PR: https://github.com/MystenLabs/sui/pull/7044
 
 use std::cmp::max;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
 use std::thread;
 use std::time::Duration;
 
@@ -26,15 +26,33 @@ pub struct ObjectRef {
 /// Stores parent sync information for objects
 pub struct ParentSyncTable {
     entries: RwLock<HashMap<ObjectId, ObjectRef>>,
+    /// Objects read by an open epoch; checkpoint updates to them wait
+    frozen: Mutex<HashSet<ObjectId>>,
+    epoch_ended: Condvar,
 }
 
 impl ParentSyncTable {
     fn new() -> Self {
         Self {
             entries: RwLock::new(HashMap::new()),
+            frozen: Mutex::new(HashSet::new()),
+            epoch_ended: Condvar::new(),
         }
     }
 
+    /// Apply a checkpoint update, waiting while an open epoch has the object
+    /// frozen. Returns true if the update had to wait.
+    fn apply_checkpoint_update(&self, obj_ref: ObjectRef) -> bool {
+        let mut frozen = self.frozen.lock().unwrap();
+        let deferred = frozen.contains(&obj_ref.id);
+        while frozen.contains(&obj_ref.id) {
+            frozen = self.epoch_ended.wait(frozen).unwrap();
+        }
+        // Written under the frozen lock so begin_epoch can't interleave
+        self.update_entry(obj_ref);
+        deferred
+    }
+
     fn get_latest_parent_entry(&self, id: &str) -> Option<ObjectRef> {
         let entries = self.entries.read().unwrap();
         entries.get(id).cloned()
@@ -69,6 +87,44 @@ impl SharedObjectTable {
     }
 }
 
+/// Checkpoint updates that have been executed but not yet committed to
+/// parent_sync
+pub struct PendingUpdates {
+    pending_versions: RwLock<HashMap<ObjectId, Version>>,
+}
+
+impl PendingUpdates {
+    fn new() -> Self {
+        Self {
+            pending_versions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn get_pending_version(&self, id: &str) -> Option<Version> {
+        let pending = self.pending_versions.read().unwrap();
+        pending.get(id).cloned()
+    }
+
+    fn stage(&self, id: &str, version: Version) {
+        let mut pending = self.pending_versions.write().unwrap();
+        pending.insert(id.to_string(), version);
+    }
+
+    /// Move a staged version into parent_sync. The parent_sync write happens
+    /// before the pending entry is removed, so a reader always sees it in at
+    /// least one of the two places.
+    fn commit(&self, id: &str, parent_sync: &ParentSyncTable) {
+        let version = self.get_pending_version(id);
+        if let Some(version) = version {
+            parent_sync.update_entry(ObjectRef {
+                id: id.to_string(),
+                version,
+            });
+            self.pending_versions.write().unwrap().remove(id);
+        }
+    }
+}
+
 /// Buggy version - uses stale version from parent sync
 mod buggy {
     use super::*;
@@ -114,9 +170,40 @@ mod buggy {
 mod fixed {
     use super::*;
 
+    /// Which sources `get_next_version` takes the max over
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum VersionSelection {
+        /// max(parent_sync, initial_shared)
+        Max,
+        /// Also include checkpoint updates not yet committed to parent_sync
+        PendingAware,
+    }
+
+    impl VersionSelection {
+        pub fn parse(name: &str) -> Option<Self> {
+            match name {
+                "max" => Some(Self::Max),
+                "pending" => Some(Self::PendingAware),
+                _ => None,
+            }
+        }
+    }
+
     pub struct EpochStore {
         parent_sync: Arc<ParentSyncTable>,
         shared_objects: Arc<SharedObjectTable>,
+        pending: Option<Arc<PendingUpdates>>,
+    }
+
+    /// Next versions read by `begin_epoch`, stable until `end_epoch`
+    pub struct EpochSnapshot {
+        next_versions: HashMap<ObjectId, Version>,
+    }
+
+    impl EpochSnapshot {
+        pub fn get_next_version(&self, object_id: &str) -> Option<Version> {
+            self.next_versions.get(object_id).cloned()
+        }
     }
 
     impl EpochStore {
@@ -127,9 +214,59 @@ mod fixed {
             Self {
                 parent_sync,
                 shared_objects,
+                pending: None,
             }
         }
 
+        /// Select versions with `VersionSelection::PendingAware`, reading
+        /// in-flight checkpoint updates from `pending`
+        pub fn with_pending(
+            parent_sync: Arc<ParentSyncTable>,
+            shared_objects: Arc<SharedObjectTable>,
+            pending: Arc<PendingUpdates>,
+        ) -> Self {
+            Self {
+                parent_sync,
+                shared_objects,
+                pending: Some(pending),
+            }
+        }
+
+        pub fn selection(&self) -> VersionSelection {
+            if self.pending.is_some() {
+                VersionSelection::PendingAware
+            } else {
+                VersionSelection::Max
+            }
+        }
+
+        /// Freeze `objects` against checkpoint updates and read their next
+        /// versions as one consistent view. Updates issued before
+        /// `end_epoch` wait instead of landing mid-epoch.
+        pub fn begin_epoch(&self, objects: &[&str]) -> EpochSnapshot {
+            let mut frozen = self.parent_sync.frozen.lock().unwrap();
+            let next_versions = objects
+                .iter()
+                .map(|id| {
+                    frozen.insert(id.to_string());
+                    (id.to_string(), self.get_next_version(id))
+                })
+                .collect();
+            println!("[FIXED] Epoch opened, froze {:?}", objects);
+            EpochSnapshot { next_versions }
+        }
+
+        /// Release the objects frozen by `begin_epoch` and let deferred
+        /// checkpoint updates through
+        pub fn end_epoch(&self, snapshot: EpochSnapshot) {
+            let mut frozen = self.parent_sync.frozen.lock().unwrap();
+            for id in snapshot.next_versions.keys() {
+                frozen.remove(id);
+            }
+            self.parent_sync.epoch_ended.notify_all();
+            println!("[FIXED] Epoch closed, released {} object(s)", snapshot.next_versions.len());
+        }
+
         /// FIX: Use max of parent_sync version and initial_shared_version
         pub fn get_next_version(&self, object_id: &str) -> Version {
             let initial_version = self
@@ -145,6 +282,17 @@ mod fixed {
 
             // FIX: Use max to handle both cases correctly
             let version = max(parent_version, initial_version);
+
+            if let Some(pending) = &self.pending {
+                let pending_version = pending.get_pending_version(object_id).unwrap_or(0);
+                let version = max(version, pending_version);
+                println!(
+                    "[FIXED] Object '{}': parent_sync={}, initial_shared={}, pending={}, using max={}",
+                    object_id, parent_version, initial_version, pending_version, version
+                );
+                return version + 1;
+            }
+
             println!(
                 "[FIXED] Object '{}': parent_sync={}, initial_shared={}, using max={}",
                 object_id, parent_version, initial_version, version
@@ -154,13 +302,50 @@ mod fixed {
     }
 }
 
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let selection = match args.iter().position(|arg| arg == "--selection").and_then(|i| args.get(i + 1)) {
+        Some(name) => fixed::VersionSelection::parse(name).unwrap_or_else(|| {
+            eprintln!("Unknown --selection '{}': expected 'max' or 'pending'", name);
+            std::process::exit(2);
+        }),
+        None => fixed::VersionSelection::Max,
+    };
 
     println!("=== Sui Issue #5754: Object Version Race ===\n");
 
-    if use_fixed {
+    if args.iter().any(|arg| arg == "--epoch-barrier") {
+        println!("Running FIXED version (epoch barrier defers checkpoint updates)...\n");
+        run_epoch_barrier_test();
+    } else if args.iter().any(|arg| arg == "--pending-window") {
+        println!("Running FIXED version ({:?} selection, uncommitted checkpoint update)...\n", selection);
+        run_pending_window_test(selection);
+    } else if use_fixed {
         println!("Running FIXED version (max of versions)...\n");
         run_fixed_test();
     } else {
@@ -239,12 +424,14 @@ fn run_buggy_test() {
     println!("Epoch chose version: {}", epoch_version);
 
     if epoch_version < initial_shared_version {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Epoch initialization used stale version {}!", epoch_version);
         println!("Should have used at least {} (initial_shared_version)", initial_shared_version + 1);
         println!("This can cause version conflicts in transaction processing.");
     } else if epoch_version < final_parent_version {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Race condition: parent_sync was updated after read!");
         println!("Epoch used version {}, but parent_sync is now at {}", epoch_version, final_parent_version);
     }
@@ -309,9 +496,162 @@ fn run_fixed_test() {
     println!("Epoch chose version: {}", epoch_version);
 
     if epoch_version > initial_shared_version {
-        println!("\n[FIXED]");
+        println!();
+        print_banner("[FIXED]");
         println!("Epoch initialization correctly used version {}!", epoch_version);
         println!("Used max(parent_sync, initial_shared) to ensure consistency.");
         println!("No version conflicts possible.");
     }
 }
+
+fn run_pending_window_test(selection: fixed::VersionSelection) {
+    let parent_sync = Arc::new(ParentSyncTable::new());
+    let shared_objects = Arc::new(SharedObjectTable::new());
+    let pending = Arc::new(PendingUpdates::new());
+
+    let object_id = "obj_upgrade";
+    let initial_shared_version: Version = 100;
+    let committed_version: Version = 150;
+    let in_flight_version: Version = 200;
+
+    shared_objects.set_initial_shared_version(object_id, initial_shared_version);
+    parent_sync.update_entry(ObjectRef {
+        id: object_id.to_string(),
+        version: committed_version,
+    });
+    println!(
+        "Object '{}': initial_shared={}, parent_sync={}",
+        object_id, initial_shared_version, committed_version
+    );
+
+    let epoch_store = Arc::new(match selection {
+        fixed::VersionSelection::Max => {
+            fixed::EpochStore::new(Arc::clone(&parent_sync), Arc::clone(&shared_objects))
+        }
+        fixed::VersionSelection::PendingAware => fixed::EpochStore::with_pending(
+            Arc::clone(&parent_sync),
+            Arc::clone(&shared_objects),
+            Arc::clone(&pending),
+        ),
+    });
+
+    // Thread 2: checkpoint executes version 200 but commits it late
+    let pending2 = Arc::clone(&pending);
+    let parent_sync2 = Arc::clone(&parent_sync);
+    let object_id2 = object_id.to_string();
+    let handle2 = thread::spawn(move || {
+        println!("\n[Thread 2] Checkpoint executed version {}, commit pending...", in_flight_version);
+        pending2.stage(&object_id2, in_flight_version);
+        thread::sleep(Duration::from_millis(50));
+        pending2.commit(&object_id2, &parent_sync2);
+        println!("[Thread 2] Committed version {} to parent_sync", in_flight_version);
+    });
+
+    // Thread 1: epoch initialization reads inside the commit window
+    let epoch_store1 = Arc::clone(&epoch_store);
+    let object_id1 = object_id.to_string();
+    let handle1 = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        println!("\n[Thread 1] Epoch initialization starting (commit still pending)...");
+        epoch_store1.get_next_version(&object_id1)
+    });
+
+    let epoch_version = handle1.join().unwrap();
+    handle2.join().unwrap();
+
+    println!("\n=== Results ===");
+    println!("Selection: {:?}", epoch_store.selection());
+    println!("In-flight checkpoint version: {}", in_flight_version);
+    println!("Epoch chose version: {}", epoch_version);
+
+    match selection {
+        fixed::VersionSelection::PendingAware => {
+            assert_eq!(epoch_version, in_flight_version + 1);
+            println!();
+            print_banner("[FIXED]");
+            println!("The uncommitted checkpoint update was accounted for.");
+            println!("max(parent_sync, initial_shared, pending) picked the highest version.");
+        }
+        fixed::VersionSelection::Max => {
+            println!();
+            print_banner("[NOTE]");
+            println!(
+                "max(parent_sync, initial_shared) chose {} while version {} was in flight.",
+                epoch_version, in_flight_version
+            );
+            println!("Run with --pending-window --selection pending to include pending updates.");
+        }
+    }
+}
+
+fn run_epoch_barrier_test() {
+    let parent_sync = Arc::new(ParentSyncTable::new());
+    let shared_objects = Arc::new(SharedObjectTable::new());
+
+    let object_id = "obj_upgrade";
+    let initial_shared_version: Version = 100;
+    let checkpoint_version: Version = 150;
+
+    shared_objects.set_initial_shared_version(object_id, initial_shared_version);
+    println!(
+        "Object '{}' became shared at version {}",
+        object_id, initial_shared_version
+    );
+
+    let epoch_store = Arc::new(fixed::EpochStore::new(
+        Arc::clone(&parent_sync),
+        Arc::clone(&shared_objects),
+    ));
+
+    // Thread 1: epoch initialization reads at open and again before close
+    let epoch_store1 = Arc::clone(&epoch_store);
+    let object_id1 = object_id.to_string();
+    let handle1 = thread::spawn(move || {
+        println!("\n[Thread 1] Epoch initialization starting...");
+        let snapshot = epoch_store1.begin_epoch(&[&object_id1]);
+        let first = snapshot.get_next_version(&object_id1).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        let second = snapshot.get_next_version(&object_id1).unwrap();
+        println!("[Thread 1] Read version {} at open, {} before close", first, second);
+        epoch_store1.end_epoch(snapshot);
+        (first, second)
+    });
+
+    // Thread 2: checkpoint update issued while the epoch is open
+    let parent_sync2 = Arc::clone(&parent_sync);
+    let object_id2 = object_id.to_string();
+    let handle2 = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        println!("\n[Thread 2] Checkpoint sync updating to version {}...", checkpoint_version);
+        let deferred = parent_sync2.apply_checkpoint_update(ObjectRef {
+            id: object_id2,
+            version: checkpoint_version,
+        });
+        println!("[Thread 2] Updated parent_sync to version {}", checkpoint_version);
+        deferred
+    });
+
+    let (first, second) = handle1.join().unwrap();
+    let deferred = handle2.join().unwrap();
+
+    let final_parent_version = parent_sync
+        .get_latest_parent_entry(object_id)
+        .map(|r| r.version)
+        .unwrap_or(0);
+
+    println!("\n=== Results ===");
+    println!("Epoch version at open: {}", first);
+    println!("Epoch version before close: {}", second);
+    println!("Checkpoint update deferred: {}", deferred);
+    println!("Final parent_sync version: {}", final_parent_version);
+
+    assert!(deferred, "checkpoint update landed while the epoch was open");
+    assert_eq!(first, initial_shared_version + 1);
+    assert_eq!(first, second);
+    assert_eq!(final_parent_version, checkpoint_version);
+
+    println!();
+    print_banner("[FIXED]");
+    println!("The checkpoint update waited for end_epoch().");
+    println!("The epoch saw the pre-update version for its whole lifetime.");
+}
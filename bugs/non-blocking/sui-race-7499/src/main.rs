@@ -166,20 +166,47 @@ fn run_test(use_fixed: bool) {
     println!("Total bind failures: {}", total_failures);
 
     if !use_fixed && total_failures > 0 {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Server failed to bind {} times due to port not being released in time.", total_failures);
         println!("In the original code, this caused a panic (unwrap on bind error).");
         println!("\nRun with --fixed to see retry logic.");
     } else if use_fixed {
-        println!("\n[FIXED]");
+        println!();
+        print_banner("[FIXED]");
         println!("Retry logic with backoff handles transient bind failures gracefully.");
     } else {
-        println!("\n[NOTE]");
+        println!();
+        print_banner("[NOTE]");
         println!("No failures this run. The race is timing-dependent.");
         println!("Run multiple times or under load to trigger.");
     }
 }
 
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
@@ -8,15 +8,94 @@
 use std::env;
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
 
 static BUILD_DIR: &str = "/tmp/sui_build_race_test";
 
+/// Artifacts each build writes when `--artifacts` is not given
+const DEFAULT_ARTIFACTS: usize = 3;
+
+fn artifact_name(index: usize) -> String {
+    format!("artifact_{}.bin", index)
+}
+
+/// Content that identifies which build wrote an artifact
+fn artifact_content(thread_id: usize, index: usize) -> String {
+    format!("thread {} artifact {}\n", thread_id, index)
+}
+
+/// Write this build's numbered artifacts into `dir`
+fn write_artifacts(dir: &Path, thread_id: usize, artifacts: usize) -> std::io::Result<()> {
+    for index in 0..artifacts {
+        thread::sleep(std::time::Duration::from_micros(20));
+        fs::write(dir.join(artifact_name(index)), artifact_content(thread_id, index))?;
+    }
+    Ok(())
+}
+
+/// Artifacts that still hold their own build's content after the run
+struct Survivors {
+    artifacts: usize,
+    threads: usize,
+}
+
+/// What a run did to the artifacts it was asked to write. Data-loss bugs
+/// report through `DataLoss`, so their metrics read as units lost out of a
+/// total.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// `lost` of the `total` artifacts were overwritten or never written
+    DataLoss { lost: u64, total: u64 },
+    /// All `total` artifacts survived intact
+    Intact { total: u64 },
+}
+
+impl Outcome {
+    fn from_counts(kept: u64, total: u64) -> Self {
+        match total.saturating_sub(kept) {
+            0 => Outcome::Intact { total },
+            lost => Outcome::DataLoss { lost, total },
+        }
+    }
+
+    fn lost(&self) -> u64 {
+        match self {
+            Outcome::DataLoss { lost, .. } => *lost,
+            Outcome::Intact { .. } => 0,
+        }
+    }
+}
+
+/// Check every thread's expected artifacts, looking for thread `t`'s output
+/// in `dir_for(t)`
+fn count_survivors(dir_for: impl Fn(usize) -> PathBuf, threads: usize, artifacts: usize) -> Survivors {
+    let mut survivors = Survivors { artifacts: 0, threads: 0 };
+    for thread_id in 0..threads {
+        let dir = dir_for(thread_id);
+        let kept = (0..artifacts)
+            .filter(|&index| {
+                fs::read_to_string(dir.join(artifact_name(index)))
+                    .is_ok_and(|content| content == artifact_content(thread_id, index))
+            })
+            .count();
+        survivors.artifacts += kept;
+        if kept > 0 {
+            survivors.threads += 1;
+        }
+    }
+    survivors
+}
+
 /// Simulates a build operation that creates a directory and writes files
-fn build_package_buggy(thread_id: usize, success_count: Arc<AtomicUsize>, error_count: Arc<AtomicUsize>) {
+fn build_package_buggy(
+    thread_id: usize,
+    artifacts: usize,
+    success_count: Arc<AtomicUsize>,
+    error_count: Arc<AtomicUsize>,
+) {
     let build_path = PathBuf::from(BUILD_DIR);
 
     // BUG: Multiple threads race to create the same directory
@@ -44,7 +123,11 @@ fn build_package_buggy(thread_id: usize, success_count: Arc<AtomicUsize>, error_
         Ok(mut file) => {
             // Write thread ID to detect overwrites
             let content = format!("Built by thread {}\n", thread_id);
-            match file.write_all(content.as_bytes()) {
+            // BUG: every build writes the same artifact names into the shared dir
+            let written = file
+                .write_all(content.as_bytes())
+                .and_then(|_| write_artifacts(&build_path, thread_id, artifacts));
+            match written {
                 Ok(_) => {
                     success_count.fetch_add(1, Ordering::SeqCst);
                 }
@@ -69,9 +152,14 @@ fn build_package_buggy(thread_id: usize, success_count: Arc<AtomicUsize>, error_
 }
 
 /// Fixed version: Each thread uses its own temporary directory
-fn build_package_fixed(thread_id: usize, success_count: Arc<AtomicUsize>, _error_count: Arc<AtomicUsize>) {
+fn build_package_fixed(
+    thread_id: usize,
+    artifacts: usize,
+    success_count: Arc<AtomicUsize>,
+    _error_count: Arc<AtomicUsize>,
+) {
     // FIX: Each build operation gets its own unique directory
-    let build_path = PathBuf::from(format!("{}/build_{}", BUILD_DIR, thread_id));
+    let build_path = fixed_build_dir(thread_id);
 
     // No race: Each thread has its own directory
     fs::create_dir_all(&build_path).expect("Failed to create unique build dir");
@@ -83,16 +171,54 @@ fn build_package_fixed(thread_id: usize, success_count: Arc<AtomicUsize>, _error
     let mut file = File::create(&output_file).expect("Failed to create file");
     let content = format!("Built by thread {}\n", thread_id);
     file.write_all(content.as_bytes()).expect("Failed to write");
+    write_artifacts(&build_path, thread_id, artifacts).expect("Failed to write artifacts");
 
     success_count.fetch_add(1, Ordering::SeqCst);
 
-    // Cleanup our own directory
-    let _ = fs::remove_dir_all(&build_path);
+    // Our directory is left for verification; main removes BUILD_DIR after
+}
+
+fn fixed_build_dir(thread_id: usize) -> PathBuf {
+    PathBuf::from(format!("{}/build_{}", BUILD_DIR, thread_id))
+}
+
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let artifacts = match args.iter().position(|arg| arg == "--artifacts").and_then(|i| args.get(i + 1)) {
+        Some(value) => match value.parse::<usize>() {
+            Ok(count) if count > 0 => count,
+            _ => {
+                eprintln!("Invalid --artifacts '{}': expected a positive count", value);
+                std::process::exit(2);
+            }
+        },
+        None => DEFAULT_ARTIFACTS,
+    };
 
     println!("=== Sui Issue #8113: Concurrent Build Directory Race ===\n");
 
@@ -117,9 +243,9 @@ fn main() {
 
         let handle = thread::spawn(move || {
             if use_fixed {
-                build_package_fixed(i, success, errors);
+                build_package_fixed(i, artifacts, success, errors);
             } else {
-                build_package_buggy(i, success, errors);
+                build_package_buggy(i, artifacts, success, errors);
             }
         });
 
@@ -148,16 +274,53 @@ fn main() {
         }
     }
 
+    // Verify every build's artifacts where that build expects to find them
+    let expected = num_threads * artifacts;
+    let survivors = if use_fixed {
+        count_survivors(fixed_build_dir, num_threads, artifacts)
+    } else {
+        count_survivors(|_| PathBuf::from(BUILD_DIR), num_threads, artifacts)
+    };
+    println!(
+        "Artifacts intact: {} of {} ({} of {} threads kept any)",
+        survivors.artifacts, expected, survivors.threads, num_threads
+    );
+
     // Cleanup
     let _ = fs::remove_dir_all(BUILD_DIR);
 
-    if !use_fixed {
-        println!("\n[BUG DEMONSTRATED]");
+    let outcome = Outcome::from_counts(survivors.artifacts as u64, expected as u64);
+
+    if use_fixed {
+        assert_eq!(survivors.artifacts, expected, "isolated builds must keep every artifact");
+        assert_eq!(outcome, Outcome::Intact { total: expected as u64 });
+    } else {
+        // Each shared artifact name holds at most one build's output
+        assert!(survivors.artifacts <= artifacts, "shared dir kept more artifacts than it has names");
+    }
+
+    println!("\n=== Metrics ===");
+    println!("threads: {}", num_threads);
+    println!("artifacts_per_thread: {}", artifacts);
+    println!("expected_artifacts: {}", expected);
+    println!("surviving_artifacts: {}", survivors.artifacts);
+    println!("lost_artifacts: {}", expected - survivors.artifacts);
+    println!("threads_with_survivors: {}", survivors.threads);
+    println!("outcome: {}", if outcome.lost() > 0 { "data_loss" } else { "intact" });
+    println!("lost_units: {}", outcome.lost());
+    println!("total_units: {}", expected);
+
+    if let Outcome::DataLoss { lost, total } = outcome {
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Multiple threads raced to write to the same file.");
         println!("All {} threads reported success, but only 1 thread's data persisted.", successes);
+        println!("{} of {} artifacts were overwritten by other builds.", lost, total);
         println!("\nRun with --fixed to see the correct behavior.");
     } else {
-        println!("\n[FIXED]");
+        println!();
+        print_banner("[FIXED]");
         println!("Each thread used its own directory - no race condition.");
+        println!("All {} artifacts from {} builds were intact.", expected, num_threads);
     }
 }
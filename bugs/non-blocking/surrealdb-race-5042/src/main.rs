@@ -9,9 +9,13 @@
 use std::collections::HashMap;
 use std::env;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Barrier, RwLock};
 use std::thread;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How recently another update must have landed for the buggy handler to
+/// treat its own update as a conflict
+const CONFLICT_WINDOW: Duration = Duration::from_millis(50);
 
 #[derive(Debug, Clone)]
 pub struct AuthToken {
@@ -58,11 +62,16 @@ mod buggy {
 
     pub struct AuthHandler {
         store: Arc<AuthStore>,
+        conflict_window: Duration,
     }
 
     impl AuthHandler {
         pub fn new(store: Arc<AuthStore>) -> Self {
-            Self { store }
+            Self::with_conflict_window(store, CONFLICT_WINDOW)
+        }
+
+        pub fn with_conflict_window(store: Arc<AuthStore>, conflict_window: Duration) -> Self {
+            Self { store, conflict_window }
         }
 
         /// BUG: Non-atomic read-modify-write on auth record
@@ -93,7 +102,7 @@ mod buggy {
 
                     // BUG: If another thread also reached here, one update
                     // will fail or they'll conflict
-                    if auth_token.last_active > new_time.checked_sub(Duration::from_millis(50)).unwrap() {
+                    if auth_token.last_active > new_time.checked_sub(self.conflict_window).unwrap() {
                         // Another thread just updated this!
                         self.store
                             .failed_auth_count
@@ -158,22 +167,64 @@ mod fixed {
     }
 }
 
+/// Print a result banner: green for `[FIXED]` and its variants, red for
+/// `[BUG DEMONSTRATED]`, `[DEADLOCK DETECTED]` and `[ERROR]`, yellow for
+/// `[NOTE]`. The banner is plain when `NO_COLOR` is set or `--no-color` is
+/// passed, so the output can be piped.
+///
+/// Each crate builds on its own, so this is copied into every crate's
+/// `main.rs`. Keep the copies identical.
+fn print_banner(banner: &str) {
+    let plain = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::args().any(|arg| arg == "--no-color");
+    let color = if banner.starts_with("[FIXED") {
+        "32"
+    } else if banner == "[NOTE]" {
+        "33"
+    } else {
+        "31"
+    };
+    if plain {
+        println!("{}", banner);
+    } else {
+        println!("\x1b[{}m{}\x1b[0m", color, banner);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let use_fixed = args.iter().any(|arg| arg == "--fixed");
+    let use_barrier = args.iter().any(|arg| arg == "--barrier");
+    let use_csv = args.iter().any(|arg| arg == "--csv");
 
     println!("=== SurrealDB Issue #5042: Concurrent Authentication Race ===\n");
 
-    if use_fixed {
+    if let Some(spec) = args.iter().position(|arg| arg == "--sweep").and_then(|i| args.get(i + 1)) {
+        let sweep = match Sweep::parse(spec) {
+            Some(sweep) if sweep.param == "window-us" => sweep,
+            _ => {
+                eprintln!("Invalid --sweep '{}': expected window-us=start..end[:step]", spec);
+                std::process::exit(2);
+            }
+        };
+        println!("Sweeping BUGGY conflict window {}..={}µs...\n", sweep.start, sweep.end);
+        run_sweep_test(&sweep, use_csv);
+    } else if use_fixed {
         println!("Running FIXED version (atomic validate-and-update)...\n");
-        run_fixed_test();
+        run_fixed_test(use_barrier);
     } else {
         println!("Running BUGGY version (racy read-then-update)...\n");
-        run_buggy_test();
+        run_buggy_test(use_barrier);
     }
 }
 
-fn run_buggy_test() {
+/// With `--barrier`, every request waits here so they all authenticate
+/// together instead of in spawn order
+fn start_barrier(use_barrier: bool, requests: usize) -> Option<Arc<Barrier>> {
+    use_barrier.then(|| Arc::new(Barrier::new(requests)))
+}
+
+fn run_buggy_test(use_barrier: bool) {
     let store = Arc::new(AuthStore::new());
     let handler = Arc::new(buggy::AuthHandler::new(Arc::clone(&store)));
 
@@ -182,10 +233,16 @@ fn run_buggy_test() {
     let mut handles = vec![];
 
     // Simulate 10 concurrent requests with the same token
+    let start = start_barrier(use_barrier, 10);
+
     for i in 0..10 {
         let handler = Arc::clone(&handler);
+        let start = start.clone();
         let handle = thread::spawn(move || {
             println!("[BUGGY] Request {} authenticating...", i);
+            if let Some(start) = start {
+                start.wait();
+            }
             handler.authenticate("token_123")
         });
         handles.push(handle);
@@ -206,12 +263,14 @@ fn run_buggy_test() {
     println!("Failed authentications: {}", failed_count);
 
     if failed_count > 0 {
-        println!("\n[BUG DEMONSTRATED]");
+        println!();
+        print_banner("[BUG DEMONSTRATED]");
         println!("Only {} out of 10 requests succeeded!", success_count);
         println!("Concurrent UPDATE $auth SET lastActive caused race conflicts.");
         println!("In production, this caused 'There was a problem with authentication' errors.");
     } else {
-        println!("\n[NOTE]");
+        println!();
+        print_banner("[NOTE]");
         println!("All requests succeeded this time (timing-dependent race).");
         println!("Try running multiple times.");
     }
@@ -219,7 +278,7 @@ fn run_buggy_test() {
     println!("\nRun with --fixed to see atomic version.");
 }
 
-fn run_fixed_test() {
+fn run_fixed_test(use_barrier: bool) {
     let store = Arc::new(AuthStore::new());
     let handler = Arc::new(fixed::AuthHandler::new(Arc::clone(&store)));
 
@@ -227,10 +286,16 @@ fn run_fixed_test() {
 
     let mut handles = vec![];
 
+    let start = start_barrier(use_barrier, 10);
+
     for i in 0..10 {
         let handler = Arc::clone(&handler);
+        let start = start.clone();
         let handle = thread::spawn(move || {
             println!("[FIXED] Request {} authenticating...", i);
+            if let Some(start) = start {
+                start.wait();
+            }
             handler.authenticate("token_123")
         });
         handles.push(handle);
@@ -251,9 +316,141 @@ fn run_fixed_test() {
     println!("Failed authentications: {}", failed_count);
 
     if success_count == 10 {
-        println!("\n[FIXED]");
+        println!();
+        print_banner("[FIXED]");
         println!("All 10 requests succeeded!");
         println!("Atomic validate-and-update prevents race condition.");
         println!("Write lock held during entire authentication sequence.");
     }
 }
+
+/// Range for `--sweep param=start..end:step`, inclusive of `end`
+///
+/// Each crate builds on its own, so this is copied into ballista-deadlock-132,
+/// sui-blocking-5201 and surrealdb-race-5042. Keep the copies identical.
+struct Sweep {
+    param: String,
+    start: u64,
+    end: u64,
+    step: u64,
+}
+
+impl Sweep {
+    fn parse(spec: &str) -> Option<Self> {
+        let (param, range) = spec.split_once('=')?;
+        let (range, step) = match range.split_once(':') {
+            Some((range, step)) => (range, step.parse().ok()?),
+            None => (range, 1),
+        };
+        let (start, end) = range.split_once("..")?;
+        let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+        (step > 0 && start <= end).then(|| Self { param: param.to_string(), start, end, step })
+    }
+
+    fn values(&self) -> impl Iterator<Item = u64> {
+        (self.start..=self.end).step_by(self.step as usize)
+    }
+}
+
+/// Requests per sweep value, all released together by a barrier
+const SWEEP_REQUESTS: usize = 10;
+
+/// Authenticate `SWEEP_REQUESTS` times at once against a fresh store.
+/// Returns how many requests failed.
+fn run_window_probe(conflict_window: Duration) -> usize {
+    let store = Arc::new(AuthStore::new());
+    let handler = Arc::new(buggy::AuthHandler::with_conflict_window(Arc::clone(&store), conflict_window));
+    let start = Arc::new(Barrier::new(SWEEP_REQUESTS));
+
+    let handles: Vec<_> = (0..SWEEP_REQUESTS)
+        .map(|_| {
+            let handler = Arc::clone(&handler);
+            let start = Arc::clone(&start);
+            thread::spawn(move || {
+                start.wait();
+                handler.authenticate("token_123")
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    store.get_failed_count() as usize
+}
+
+/// Print `--csv` output: a header, then one line per row. Fields containing a
+/// comma, quote or newline are quoted.
+///
+/// Each crate builds on its own, so this and `csv_field` are copied into
+/// ballista-deadlock-132, sui-blocking-5201, reth-race-12287 and
+/// surrealdb-race-5042. Keep the copies identical.
+fn print_csv(header: &[&str], rows: &[Vec<String>]) {
+    println!("\n=== CSV ===");
+    println!("{}", header.join(","));
+    for row in rows {
+        assert_eq!(row.len(), header.len(), "CSV row width must match the header");
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        println!("{}", fields.join(","));
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn run_sweep_test(sweep: &Sweep, use_csv: bool) {
+    let rows: Vec<(u64, usize, Duration)> = sweep
+        .values()
+        .map(|window_us| {
+            let started = Instant::now();
+            let failures = run_window_probe(Duration::from_micros(window_us));
+            (window_us, failures, started.elapsed())
+        })
+        .collect();
+
+    println!();
+    for &(window_us, failures, _) in &rows {
+        println!("[SWEEP] window-us={} failures={}/{}", window_us, failures, SWEEP_REQUESTS);
+    }
+
+    if use_csv {
+        let mut csv = Vec::new();
+        for &(window_us, failures, elapsed) in &rows {
+            csv.push(vec![
+                window_us.to_string(),
+                (failures > 0).to_string(),
+                elapsed.as_millis().to_string(),
+                SWEEP_REQUESTS.to_string(),
+                failures.to_string(),
+            ]);
+        }
+        print_csv(&["window_us", "bug_observed", "elapsed_ms", "requests", "failures"], &csv);
+    } else {
+        println!("\n=== Sweep (CSV) ===");
+        println!("window_us,requests,failures");
+        for &(window_us, failures, _) in &rows {
+            println!("{},{},{}", window_us, SWEEP_REQUESTS, failures);
+        }
+    }
+
+    println!("\n=== Results ===");
+    match rows.iter().find(|&&(_, failures, _)| failures > 0) {
+        Some(&(window_us, failures, _)) => {
+            println!("Smallest window with failures: {}µs ({} failed)", window_us, failures);
+            println!();
+            print_banner("[BUG DEMONSTRATED]");
+            println!("Once the window is wider than the gap between updates, concurrent");
+            println!("requests see each other's lastActive write and fail.");
+        }
+        None => {
+            println!("No request failed at any window in the sweep.");
+            println!();
+            print_banner("[NOTE]");
+            println!("Every update landed further apart than the window; try a wider range.");
+        }
+    }
+}